@@ -0,0 +1,388 @@
+//! Throughput benchmark of the marketplace extractor and storer on synthetic transactions.
+//!
+//! ```sh
+//! cargo bench --bench marketplace_throughput > /dev/null
+//! ```
+//!
+//! The report goes to stderr, stdout gets the per event logs of the parsers. Configured with env
+//! vars:
+//! - `BENCH_BATCHES` (default 20) and `BENCH_BATCH_SIZE` (default 1000) transactions per batch
+//! - `BENCH_EVENTS_PER_TXN` (default 2) marketplace events per transaction
+//! - `BENCH_MIX` (default `asks=50,bids=25,collection_bids=15,other=10`) relative weight of each
+//!   order family, `other` being transactions without marketplace events
+//! - `BENCH_MARKETPLACES` (default `aptos_labs,tradeport_v2`) which contracts emit the events
+//! - `BENCH_DATABASE_URL` to also run extractor+storer, a scratch database is created on that
+//!   server and dropped afterwards
+//! - `BENCH_DB_POOL_SIZE` (default 50) and `BENCH_CHUNK_SIZE` (default per table) for the storer
+
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use aptos_indexer_processor_sdk::{
+    aptos_protos::transaction::v1::{Event as EventPB, Transaction},
+    traits::Processable,
+    types::transaction_context::TransactionContext,
+};
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use indexer::{
+    indexers::marketplace_indexer::{extractor::Extractor, storer::Storer},
+    testing::{
+        batch, AptosLabsEventBuilder, AptosLabsEventKind, TradeportV2EventBuilder,
+        TradeportV2EventKind, TransactionBuilder,
+    },
+    utils::{database_connection::new_db_pool, database_migrations::run_migrations},
+};
+
+const APTOS_LABS_ADDR: &str = "0x584b50b999c78ade62f8359c91b5165ff390338d45f8e55969a04e65d76258c9";
+const TRADEPORT_V2_ADDR: &str =
+    "0xe11c12ec495f3989c35e1c6a0af414451223305b579291fc8f3d9d0575a23c26";
+const MARKETPLACE_TABLES: [&str; 6] = [
+    "nft_asks",
+    "nft_bids",
+    "collection_bids",
+    "filled_collection_bids",
+    "activities",
+    "raw_marketplace_events",
+];
+
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .map(|value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid value for {}: {}", name, value))
+        })
+        .unwrap_or(default)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum OrderFamily {
+    Asks,
+    Bids,
+    CollectionBids,
+    Other,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Marketplace {
+    AptosLabs,
+    TradeportV2,
+}
+
+struct BenchConfig {
+    batches: usize,
+    batch_size: usize,
+    events_per_txn: usize,
+    mix: Vec<(OrderFamily, u32)>,
+    marketplaces: Vec<Marketplace>,
+    database_url: Option<String>,
+    db_pool_size: u32,
+    chunk_size: Option<usize>,
+}
+
+impl BenchConfig {
+    fn from_env() -> Self {
+        let mix = std::env::var("BENCH_MIX")
+            .unwrap_or_else(|_| "asks=50,bids=25,collection_bids=15,other=10".to_string())
+            .split(',')
+            .map(|entry| {
+                let (family, weight) = entry
+                    .split_once('=')
+                    .unwrap_or_else(|| panic!("Invalid BENCH_MIX entry: {}", entry));
+                let family = match family.trim() {
+                    "asks" => OrderFamily::Asks,
+                    "bids" => OrderFamily::Bids,
+                    "collection_bids" => OrderFamily::CollectionBids,
+                    "other" => OrderFamily::Other,
+                    other => panic!("Unknown BENCH_MIX family: {}", other),
+                };
+                (
+                    family,
+                    weight.trim().parse().expect("Invalid BENCH_MIX weight"),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert!(
+            mix.iter().map(|(_, weight)| weight).sum::<u32>() > 0,
+            "BENCH_MIX weights must not all be 0"
+        );
+        let marketplaces = std::env::var("BENCH_MARKETPLACES")
+            .unwrap_or_else(|_| "aptos_labs,tradeport_v2".to_string())
+            .split(',')
+            .map(|marketplace| match marketplace.trim() {
+                "aptos_labs" => Marketplace::AptosLabs,
+                "tradeport_v2" => Marketplace::TradeportV2,
+                other => panic!("Unknown BENCH_MARKETPLACES entry: {}", other),
+            })
+            .collect();
+
+        Self {
+            batches: env_or("BENCH_BATCHES", 20),
+            batch_size: env_or("BENCH_BATCH_SIZE", 1000),
+            events_per_txn: env_or("BENCH_EVENTS_PER_TXN", 2),
+            mix,
+            marketplaces,
+            database_url: std::env::var("BENCH_DATABASE_URL").ok(),
+            db_pool_size: env_or("BENCH_DB_POOL_SIZE", 50),
+            chunk_size: std::env::var("BENCH_CHUNK_SIZE")
+                .ok()
+                .map(|size| size.parse().expect("Invalid BENCH_CHUNK_SIZE")),
+        }
+    }
+}
+
+/// Deterministic generator of marketplace traffic. Orders are placed and later filled or
+/// cancelled, so the storer sees the same upsert pattern as on chain.
+struct WorkloadGenerator {
+    rng_state: u64,
+    next_order: u64,
+    // (marketplace, order address) of orders that are still open, per family
+    open_orders: AHashMap<(Marketplace, OrderFamily), Vec<String>>,
+}
+
+impl WorkloadGenerator {
+    fn new(seed: u64) -> Self {
+        Self {
+            rng_state: seed.max(1),
+            next_order: 0,
+            open_orders: AHashMap::new(),
+        }
+    }
+
+    // xorshift64, good enough to vary the workload and keep it reproducible
+    fn next_u32(&mut self, bound: u32) -> u32 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+        (self.rng_state % bound as u64) as u32
+    }
+
+    fn pick_family(&mut self, mix: &[(OrderFamily, u32)]) -> OrderFamily {
+        let total = mix.iter().map(|(_, weight)| weight).sum::<u32>();
+        let mut roll = self.next_u32(total);
+        for (family, weight) in mix {
+            if roll < *weight {
+                return *family;
+            }
+            roll -= weight;
+        }
+        unreachable!()
+    }
+
+    /// Returns the order address and whether the order is new.
+    fn pick_order(&mut self, marketplace: Marketplace, family: OrderFamily) -> (String, bool) {
+        // 60% new orders, the rest fill or cancel an open one
+        let new_order = self.next_u32(100) < 60;
+        let open = self.open_orders.entry((marketplace, family)).or_default();
+        if new_order || open.is_empty() {
+            self.next_order += 1;
+            let order_addr = format!("0x{:064x}", self.next_order);
+            open.push(order_addr.clone());
+            (order_addr, true)
+        } else {
+            (open.swap_remove(0), false)
+        }
+    }
+
+    fn event(&mut self, config: &BenchConfig, family: OrderFamily) -> EventPB {
+        let marketplace =
+            config.marketplaces[self.next_u32(config.marketplaces.len() as u32) as usize];
+        let (order_addr, is_new) = self.pick_order(marketplace, family);
+        let filled = self.next_u32(100) < 70;
+        let price = 1_000_000 + self.next_u32(1_000_000_000) as u64;
+        match marketplace {
+            Marketplace::AptosLabs => {
+                let kind = match (family, is_new, filled) {
+                    (OrderFamily::Asks, true, _) => AptosLabsEventKind::ListingPlaced,
+                    (OrderFamily::Asks, false, true) => AptosLabsEventKind::ListingFilled,
+                    (OrderFamily::Asks, false, false) => AptosLabsEventKind::ListingCancelled,
+                    (OrderFamily::Bids, true, _) => AptosLabsEventKind::TokenOfferPlaced,
+                    (OrderFamily::Bids, false, true) => AptosLabsEventKind::TokenOfferFilled,
+                    (OrderFamily::Bids, false, false) => AptosLabsEventKind::TokenOfferCancelled,
+                    (_, true, _) => AptosLabsEventKind::CollectionOfferPlaced,
+                    (_, false, true) => AptosLabsEventKind::CollectionOfferFilled,
+                    (_, false, false) => AptosLabsEventKind::CollectionOfferCancelled,
+                };
+                AptosLabsEventBuilder::new(kind, APTOS_LABS_ADDR, &order_addr)
+                    .price(price)
+                    .build()
+            }
+            Marketplace::TradeportV2 => {
+                let kind = match (family, is_new, filled) {
+                    (OrderFamily::Asks, true, _) => TradeportV2EventKind::InsertListing,
+                    (OrderFamily::Asks, false, true) => TradeportV2EventKind::Buy,
+                    (OrderFamily::Asks, false, false) => TradeportV2EventKind::DeleteListing,
+                    (OrderFamily::Bids, true, _) => TradeportV2EventKind::InsertTokenBid,
+                    (OrderFamily::Bids, false, true) => TradeportV2EventKind::AcceptTokenBid,
+                    (OrderFamily::Bids, false, false) => TradeportV2EventKind::DeleteTokenBid,
+                    (_, true, _) => TradeportV2EventKind::InsertCollectionBid,
+                    (_, false, true) => TradeportV2EventKind::AcceptCollectionBid,
+                    (_, false, false) => TradeportV2EventKind::DeleteCollectionBid,
+                };
+                TradeportV2EventBuilder::new(kind, TRADEPORT_V2_ADDR, &order_addr)
+                    .price(price)
+                    .build()
+            }
+        }
+    }
+
+    fn batches(&mut self, config: &BenchConfig) -> Vec<Vec<Transaction>> {
+        let mut version = 0;
+        (0..config.batches)
+            .map(|_| {
+                (0..config.batch_size)
+                    .map(|_| {
+                        version += 1;
+                        let family = self.pick_family(&config.mix);
+                        if family == OrderFamily::Other {
+                            return TransactionBuilder::new(version).build();
+                        }
+                        (0..config.events_per_txn)
+                            .fold(TransactionBuilder::new(version), |txn, _| {
+                                txn.event(self.event(config, family))
+                            })
+                            .build()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+struct BenchReport {
+    name: &'static str,
+    transactions: usize,
+    events: usize,
+    batch_latencies: Vec<Duration>,
+}
+
+impl BenchReport {
+    fn percentile(&self, percentile: f64) -> Duration {
+        let mut latencies = self.batch_latencies.clone();
+        latencies.sort();
+        let idx = ((latencies.len() as f64 * percentile).ceil() as usize).saturating_sub(1);
+        latencies.get(idx).copied().unwrap_or_default()
+    }
+
+    fn print(&self) {
+        let total = self.batch_latencies.iter().sum::<Duration>().as_secs_f64();
+        eprintln!(
+            "{:<18} batches={} txns={} events={} txns/sec={:.0} events/sec={:.0} p50={:.2}ms p99={:.2}ms max={:.2}ms",
+            self.name,
+            self.batch_latencies.len(),
+            self.transactions,
+            self.events,
+            self.transactions as f64 / total,
+            self.events as f64 / total,
+            self.percentile(0.5).as_secs_f64() * 1000.0,
+            self.percentile(0.99).as_secs_f64() * 1000.0,
+            self.percentile(1.0).as_secs_f64() * 1000.0,
+        );
+    }
+}
+
+fn extractor() -> Extractor {
+    Extractor::new(
+        vec![APTOS_LABS_ADDR.to_string(), TRADEPORT_V2_ADDR.to_string()],
+        true,
+    )
+}
+
+async fn bench_extractor(batches: &[Vec<Transaction>]) -> BenchReport {
+    let mut extractor = extractor();
+    let mut report = BenchReport {
+        name: "extractor",
+        transactions: 0,
+        events: 0,
+        batch_latencies: vec![],
+    };
+    for transactions in batches {
+        let input = batch(transactions.clone());
+        let start = Instant::now();
+        let output: Option<TransactionContext<_>> = extractor.process(input).await.unwrap();
+        report.batch_latencies.push(start.elapsed());
+        report.transactions += transactions.len();
+        report.events += output.map(|o| o.data.events.len()).unwrap_or_default();
+    }
+    report
+}
+
+async fn bench_extractor_and_storer(
+    config: &BenchConfig,
+    server_url: &str,
+    batches: &[Vec<Transaction>],
+) -> BenchReport {
+    let db_name = format!("marketplace_bench_{}", std::process::id());
+    let mut admin_conn = AsyncPgConnection::establish(server_url)
+        .await
+        .expect("Failed to connect to BENCH_DATABASE_URL");
+    diesel::sql_query(format!("CREATE DATABASE {}", db_name))
+        .execute(&mut admin_conn)
+        .await
+        .expect("Failed to create bench database");
+    let url = match server_url.rsplit_once('/') {
+        Some((base, _)) => format!("{}/{}", base, db_name),
+        None => panic!("Invalid BENCH_DATABASE_URL: {}", server_url),
+    };
+    run_migrations(url.clone()).await.unwrap();
+
+    let pool = new_db_pool(&url, config.db_pool_size).await;
+    let per_table_chunk_sizes = config
+        .chunk_size
+        .map(|size| {
+            MARKETPLACE_TABLES
+                .iter()
+                .map(|table| (table.to_string(), size))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut extractor = extractor();
+    let mut storer = Storer::new(pool.clone()).with_per_table_chunk_sizes(per_table_chunk_sizes);
+    let mut report = BenchReport {
+        name: "extractor+storer",
+        transactions: 0,
+        events: 0,
+        batch_latencies: vec![],
+    };
+    for transactions in batches {
+        let input = batch(transactions.clone());
+        let start = Instant::now();
+        let extracted = extractor.process(input).await.unwrap().unwrap();
+        let events = extracted.data.events.len();
+        storer.process(extracted).await.unwrap();
+        report.batch_latencies.push(start.elapsed());
+        report.transactions += transactions.len();
+        report.events += events;
+    }
+
+    drop(storer);
+    drop(pool);
+    diesel::sql_query(format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", db_name))
+        .execute(&mut admin_conn)
+        .await
+        .expect("Failed to drop bench database");
+    report
+}
+
+#[tokio::main]
+async fn main() {
+    let config = BenchConfig::from_env();
+    let batches = WorkloadGenerator::new(42).batches(&config);
+    eprintln!(
+        "generated {} batches of {} transactions, mix {:?}, marketplaces {:?}",
+        config.batches, config.batch_size, config.mix, config.marketplaces
+    );
+
+    bench_extractor(&batches).await.print();
+
+    match config.database_url.as_deref() {
+        Some(server_url) => bench_extractor_and_storer(&config, server_url, &batches)
+            .await
+            .print(),
+        None => eprintln!("BENCH_DATABASE_URL is not set, skipping extractor+storer"),
+    }
+}
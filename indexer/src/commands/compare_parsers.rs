@@ -0,0 +1,83 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use aptos_indexer_processor_sdk::utils::convert::standardize_address;
+use clap::Args;
+
+use super::marketplace_contract_addresses;
+use crate::{
+    config::indexer_processor_config::IndexerProcessorConfig,
+    indexers::marketplace_indexer::{
+        parser_comparison::{compare_parsed_events, ParsedEvents},
+        processor::MarketplaceProcessor,
+    },
+};
+
+/// Parse the version range of a `parse-events` baseline with the parsers of this build and
+/// report, per event type, the events only one of them parses and the fields that differ.
+#[derive(Debug, Args)]
+pub struct CompareParsersArgs {
+    /// Parsed events written by `parse-events` with the build to compare against
+    #[clap(long)]
+    pub baseline: PathBuf,
+    /// Only compare events of this marketplace contract
+    #[clap(long)]
+    pub marketplace_addr: Option<String>,
+    /// Write the full report as JSON to this file, a summary is printed either way
+    #[clap(long)]
+    pub output: Option<PathBuf>,
+    /// Exit with an error if the parsers differ
+    #[clap(long)]
+    pub fail_on_differences: bool,
+}
+
+impl CompareParsersArgs {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        let file = File::open(&self.baseline)
+            .with_context(|| format!("Failed to open {}", self.baseline.display()))?;
+        let mut baseline: ParsedEvents = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Failed to parse {}", self.baseline.display()))?;
+        let marketplace_addr = self
+            .marketplace_addr
+            .as_deref()
+            .map(|addr| standardize_address(&addr.to_lowercase()));
+        let contract_addresses = marketplace_contract_addresses(
+            &config,
+            marketplace_addr.as_deref(),
+            "compare-parsers",
+        )?;
+        if let Some(marketplace_addr) = &marketplace_addr {
+            let prefix = format!("{}::", marketplace_addr);
+            baseline
+                .events
+                .retain(|event| event.event_type.starts_with(&prefix));
+        }
+
+        let processor = MarketplaceProcessor::new(config).await?;
+        let candidate = processor
+            .parse_range(
+                baseline.start_version,
+                baseline.end_version,
+                contract_addresses,
+            )
+            .await?;
+        let report = compare_parsed_events(baseline, candidate);
+
+        if let Some(output) = &self.output {
+            let file = File::create(output)
+                .with_context(|| format!("Failed to create {}", output.display()))?;
+            serde_json::to_writer_pretty(BufWriter::new(file), &report)
+                .context("Failed to write parser comparison report")?;
+        }
+        println!("{}", report);
+        anyhow::ensure!(
+            !(self.fail_on_differences && report.has_differences()),
+            "The parsers of this build differ from the baseline"
+        );
+        Ok(())
+    }
+}
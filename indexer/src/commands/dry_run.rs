@@ -0,0 +1,59 @@
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use anyhow::{Context, Result};
+use aptos_indexer_processor_sdk::utils::convert::standardize_address;
+use clap::Args;
+
+use super::marketplace_contract_addresses;
+use crate::{
+    config::indexer_processor_config::IndexerProcessorConfig,
+    indexers::marketplace_indexer::processor::MarketplaceProcessor,
+};
+
+/// Process [start_version, end_version] and report the rows it would add or change in the
+/// marketplace tables, compared to what they hold now. Nothing is written and processor_status is
+/// left untouched, so this can run next to the live processor to preview a parser change.
+#[derive(Debug, Args)]
+pub struct DryRunArgs {
+    /// First version to process
+    #[clap(long)]
+    pub start_version: u64,
+    /// Last version to process, inclusive
+    #[clap(long)]
+    pub end_version: u64,
+    /// Only process events of this marketplace contract
+    #[clap(long)]
+    pub marketplace_addr: Option<String>,
+    /// Write the full report as JSON to this file, a summary is printed either way
+    #[clap(long)]
+    pub output: Option<PathBuf>,
+}
+
+impl DryRunArgs {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        anyhow::ensure!(
+            self.start_version <= self.end_version,
+            "--start-version must not be greater than --end-version"
+        );
+        let marketplace_addr = self
+            .marketplace_addr
+            .as_deref()
+            .map(|addr| standardize_address(&addr.to_lowercase()));
+        let contract_addresses =
+            marketplace_contract_addresses(&config, marketplace_addr.as_deref(), "dry-run")?;
+
+        let processor = MarketplaceProcessor::new(config).await?;
+        let report = processor
+            .dry_run(self.start_version, self.end_version, contract_addresses)
+            .await?;
+
+        if let Some(output) = &self.output {
+            let file = File::create(output)
+                .with_context(|| format!("Failed to create {}", output.display()))?;
+            serde_json::to_writer_pretty(BufWriter::new(file), &report)
+                .context("Failed to write dry run report")?;
+        }
+        println!("{}", report);
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use aptos_indexer_processor_sdk::utils::convert::standardize_address;
+use clap::Subcommand;
+use std::io::{BufRead, Write};
+
+use crate::{
+    config::indexer_processor_config::{CustomConfig, IndexerProcessorConfig},
+    utils::{database_connection::new_db_pool, database_utils::ArcDbPool},
+};
+
+pub mod compare_parsers;
+pub mod dry_run;
+pub mod parse_events;
+pub mod record;
+pub mod reindex;
+pub mod reset_chain_id;
+pub mod rewind;
+pub mod stats;
+pub mod status;
+pub mod track_contract;
+pub mod untrack_contract;
+pub mod validate_config;
+
+/// One-off operations that run against the configured database instead of starting the
+/// processor.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Re-derive marketplace tables for a version range
+    Reindex(reindex::ReindexArgs),
+    /// Report what processing a version range would change, without writing anything
+    DryRun(dry_run::DryRunArgs),
+    /// Write what the parsers of this build make of a version range to a file
+    ParseEvents(parse_events::ParseEventsArgs),
+    /// Compare the parsers of this build with a baseline written by parse-events
+    CompareParsers(compare_parsers::CompareParsersArgs),
+    /// Record a version range from the transaction stream to files
+    Record(record::RecordArgs),
+    /// Check the config, database and transaction source and report every problem found
+    ValidateConfig(validate_config::ValidateConfigArgs),
+    /// List every processor with its last processed version and lag
+    Status(status::StatusArgs),
+    /// Move a processor back to process again from a version
+    Rewind(rewind::RewindArgs),
+    /// Remove the chain id from ledger_infos
+    ResetChainId(reset_chain_id::ResetChainIdArgs),
+    /// Count the rows of every table and per marketplace
+    Stats(stats::StatsArgs),
+    /// Track a contract in tracked_contracts, without restarting the processors
+    TrackContract(track_contract::TrackContractArgs),
+    /// Stop tracking a contract of tracked_contracts
+    UntrackContract(untrack_contract::UntrackContractArgs),
+}
+
+impl Command {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        match self {
+            Command::Reindex(args) => args.run(config).await,
+            Command::DryRun(args) => args.run(config).await,
+            Command::ParseEvents(args) => args.run(config).await,
+            Command::CompareParsers(args) => args.run(config).await,
+            Command::Record(args) => args.run(config).await,
+            Command::ValidateConfig(args) => args.run(config).await,
+            Command::Status(args) => args.run(config).await,
+            Command::Rewind(args) => args.run(config).await,
+            Command::ResetChainId(args) => args.run(config).await,
+            Command::Stats(args) => args.run(config).await,
+            Command::TrackContract(args) => args.run(config).await,
+            Command::UntrackContract(args) => args.run(config).await,
+        }
+    }
+}
+
+/// Contract addresses a command should process, the configured marketplace addresses or only
+/// `marketplace_addr` when given, which has to be one of them.
+fn marketplace_contract_addresses(
+    config: &IndexerProcessorConfig,
+    marketplace_addr: Option<&str>,
+    command: &str,
+) -> Result<Vec<String>> {
+    let configured_addresses = match &config.custom_config {
+        CustomConfig::MarketplaceIndexer(contract_addresses) => contract_addresses
+            .iter()
+            .map(|addr| standardize_address(&addr.to_lowercase()))
+            .collect::<Vec<_>>(),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "{} only supports the marketplace indexer config",
+                command
+            ));
+        }
+    };
+    match marketplace_addr {
+        Some(addr) => {
+            anyhow::ensure!(
+                configured_addresses
+                    .iter()
+                    .any(|configured| configured == addr),
+                "Marketplace {} is not in the configured contract addresses",
+                addr
+            );
+            Ok(vec![addr.to_string()])
+        }
+        None => Ok(configured_addresses),
+    }
+}
+
+/// Pool for the operator commands, which only need one connection.
+async fn operator_db_pool(config: &IndexerProcessorConfig) -> Result<ArcDbPool> {
+    Ok(new_db_pool(&config.db_config.connection_string()?, 1).await)
+}
+
+/// Ask on stdin whether to go ahead with a change, `yes` skips the question.
+fn confirm(question: &str, yes: bool) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .context("Failed to read the answer")?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
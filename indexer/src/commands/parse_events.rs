@@ -0,0 +1,68 @@
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use anyhow::{Context, Result};
+use aptos_indexer_processor_sdk::utils::convert::standardize_address;
+use clap::Args;
+
+use super::marketplace_contract_addresses;
+use crate::{
+    config::indexer_processor_config::IndexerProcessorConfig,
+    indexers::marketplace_indexer::{
+        parser_comparison::ParsedEvents, processor::MarketplaceProcessor,
+    },
+};
+
+/// Parse the events of [start_version, end_version] with the parsers of this build and write
+/// the result to a file, the baseline for `compare-parsers` run with another build.
+#[derive(Debug, Args)]
+pub struct ParseEventsArgs {
+    /// First version to parse
+    #[clap(long)]
+    pub start_version: u64,
+    /// Last version to parse, inclusive
+    #[clap(long)]
+    pub end_version: u64,
+    /// Only parse events of this marketplace contract
+    #[clap(long)]
+    pub marketplace_addr: Option<String>,
+    /// File to write the parsed events to, as JSON
+    #[clap(long)]
+    pub output: PathBuf,
+}
+
+impl ParseEventsArgs {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        anyhow::ensure!(
+            self.start_version <= self.end_version,
+            "--start-version must not be greater than --end-version"
+        );
+        let marketplace_addr = self
+            .marketplace_addr
+            .as_deref()
+            .map(|addr| standardize_address(&addr.to_lowercase()));
+        let contract_addresses =
+            marketplace_contract_addresses(&config, marketplace_addr.as_deref(), "parse-events")?;
+
+        let processor = MarketplaceProcessor::new(config).await?;
+        let events = processor
+            .parse_range(self.start_version, self.end_version, contract_addresses)
+            .await?;
+        tracing::info!(
+            start_version = self.start_version,
+            end_version = self.end_version,
+            num_events = events.len(),
+            "Parsed events"
+        );
+
+        let parsed_events = ParsedEvents {
+            start_version: self.start_version,
+            end_version: self.end_version,
+            events,
+        };
+        let file = File::create(&self.output)
+            .with_context(|| format!("Failed to create {}", self.output.display()))?;
+        serde_json::to_writer(BufWriter::new(file), &parsed_events)
+            .context("Failed to write parsed events")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::{Context, Result};
+use aptos_indexer_processor_sdk::{
+    aptos_indexer_transaction_stream::{TransactionStream, TransactionStreamConfig},
+    aptos_protos::transaction::v1::{transaction::TxnData, Transaction},
+    utils::convert::standardize_address,
+};
+use clap::Args;
+use std::path::PathBuf;
+
+use crate::{
+    config::indexer_processor_config::IndexerProcessorConfig,
+    utils::transaction_source::{
+        write_transactions_file, TransactionFileFormat, CHAIN_ID_FILE_NAME,
+    },
+};
+
+/// Capture [start_version, end_version] from the transaction stream into files that
+/// file_source_config can read, one file per batch.
+#[derive(Debug, Args)]
+pub struct RecordArgs {
+    /// First version to record
+    #[clap(long)]
+    pub start_version: u64,
+    /// Last version to record, inclusive
+    #[clap(long)]
+    pub end_version: u64,
+    /// Directory to write the files to, created if missing
+    #[clap(long)]
+    pub output_dir: PathBuf,
+    #[clap(long, value_enum, default_value_t = TransactionFileFormat::Json)]
+    pub format: TransactionFileFormat,
+    /// Only keep transactions with events from the contract addresses in custom_config
+    #[clap(long)]
+    pub only_tracked: bool,
+}
+
+impl RecordArgs {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        anyhow::ensure!(
+            self.start_version <= self.end_version,
+            "--start-version must not be greater than --end-version"
+        );
+        let tracked_addresses = config
+            .custom_config
+            .contract_addresses()
+            .iter()
+            .map(|addr| standardize_address(&addr.to_lowercase()))
+            .collect::<Vec<_>>();
+        std::fs::create_dir_all(&self.output_dir)
+            .with_context(|| format!("Failed to create {}", self.output_dir.display()))?;
+
+        let mut stream = TransactionStream::new(TransactionStreamConfig {
+            starting_version: Some(self.start_version),
+            request_ending_version: Some(self.end_version),
+            ..config.transaction_stream_config
+        })
+        .await?;
+        let chain_id = stream.get_chain_id().await?;
+        std::fs::write(
+            self.output_dir.join(CHAIN_ID_FILE_NAME),
+            chain_id.to_string(),
+        )?;
+
+        let (mut num_files, mut num_transactions) = (0, 0);
+        loop {
+            let batch = stream.get_next_transaction_batch().await?;
+            let transactions = batch
+                .transactions
+                .into_iter()
+                .filter(|txn| txn.version <= self.end_version)
+                .filter(|txn| !self.only_tracked || has_tracked_events(txn, &tracked_addresses))
+                .collect::<Vec<_>>();
+            let reached_end = batch.end_version >= self.end_version;
+            if !transactions.is_empty() {
+                num_transactions += transactions.len();
+                num_files += 1;
+                let path = write_transactions_file(&self.output_dir, transactions, self.format)?;
+                tracing::info!(
+                    "Recorded versions [{}, {}] to {}",
+                    batch.start_version,
+                    batch.end_version,
+                    path.display()
+                );
+            }
+            if reached_end || stream.is_end_of_stream() {
+                break;
+            }
+        }
+
+        tracing::info!(
+            num_files,
+            num_transactions,
+            "Recorded versions [{}, {}] to {}",
+            self.start_version,
+            self.end_version,
+            self.output_dir.display()
+        );
+        Ok(())
+    }
+}
+
+fn has_tracked_events(txn: &Transaction, tracked_addresses: &[String]) -> bool {
+    let events = match txn.txn_data.as_ref() {
+        Some(TxnData::BlockMetadata(tx_inner)) => &tx_inner.events,
+        Some(TxnData::Genesis(tx_inner)) => &tx_inner.events,
+        Some(TxnData::User(tx_inner)) => &tx_inner.events,
+        _ => return false,
+    };
+    events.iter().any(|event| {
+        let event_addr = event.type_str.split("::").next().unwrap_or_default();
+        tracked_addresses.contains(&standardize_address(event_addr))
+    })
+}
@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use aptos_indexer_processor_sdk::utils::convert::standardize_address;
+use clap::Args;
+
+use super::marketplace_contract_addresses;
+use crate::{
+    config::indexer_processor_config::IndexerProcessorConfig,
+    indexers::marketplace_indexer::{
+        processor::MarketplaceProcessor,
+        reindex::{recompute_order_status, replay_from_archive, reset_derived_rows, ReindexRange},
+    },
+    utils::database_connection::get_db_connection,
+};
+
+/// Delete what was derived from events in [start_version, end_version] and replay the range
+/// from the transaction stream, or from the raw event archive. processor_status is left
+/// untouched, so this can run while the live processor keeps going. If the replay fails, run the
+/// command again for the same range.
+#[derive(Debug, Args)]
+pub struct ReindexArgs {
+    /// First version to re-derive
+    #[clap(long)]
+    pub start_version: u64,
+    /// Last version to re-derive, inclusive
+    #[clap(long)]
+    pub end_version: u64,
+    /// Only re-derive rows of this marketplace contract
+    #[clap(long)]
+    pub marketplace_addr: Option<String>,
+    /// Replay events from raw_marketplace_events instead of the transaction stream
+    #[clap(long)]
+    pub from_archive: bool,
+}
+
+impl ReindexArgs {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        anyhow::ensure!(
+            self.start_version <= self.end_version,
+            "--start-version must not be greater than --end-version"
+        );
+        let marketplace_addr = self
+            .marketplace_addr
+            .as_deref()
+            .map(|addr| standardize_address(&addr.to_lowercase()));
+        let contract_addresses =
+            marketplace_contract_addresses(&config, marketplace_addr.as_deref(), "reindex")?;
+        let range = ReindexRange {
+            start_version: self.start_version as i64,
+            end_version: self.end_version as i64,
+            marketplace_addr,
+        };
+
+        let processor = MarketplaceProcessor::new(config).await?;
+        reindex(&processor, range, contract_addresses, self.from_archive).await?;
+        tracing::info!(
+            start_version = self.start_version,
+            end_version = self.end_version,
+            "Reindex finished"
+        );
+        Ok(())
+    }
+}
+
+/// Reset what was derived from events in the range and replay it, from the raw event archive or
+/// the transaction stream.
+pub(super) async fn reindex(
+    processor: &MarketplaceProcessor,
+    range: ReindexRange,
+    contract_addresses: Vec<String>,
+    from_archive: bool,
+) -> Result<()> {
+    tracing::info!(
+        start_version = range.start_version,
+        end_version = range.end_version,
+        marketplace_addr = ?range.marketplace_addr,
+        "Resetting derived rows"
+    );
+    let mut conn = get_db_connection(&processor.db_pool).await?;
+    reset_derived_rows(&mut conn, range.clone())
+        .await
+        .context("Failed to reset derived rows")?;
+    drop(conn);
+
+    if from_archive {
+        let num_events = replay_from_archive(
+            processor.db_pool.clone(),
+            processor.storer(),
+            range.clone(),
+            contract_addresses,
+        )
+        .await?;
+        if num_events == 0 {
+            tracing::warn!(
+                "No archived events found in the range, was archive_raw_events enabled?"
+            );
+        }
+    } else {
+        processor
+            .replay_range(
+                range.start_version as u64,
+                range.end_version as u64,
+                contract_addresses,
+            )
+            .await?;
+    }
+
+    let mut conn = get_db_connection(&processor.db_pool).await?;
+    recompute_order_status(&mut conn, range)
+        .await
+        .context("Failed to recompute order status")
+}
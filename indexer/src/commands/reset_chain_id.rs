@@ -0,0 +1,61 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use serde_json::json;
+
+use super::{confirm, operator_db_pool};
+use crate::{
+    config::indexer_processor_config::IndexerProcessorConfig,
+    db_models::{ledger_info::LedgerInfo, operator_audit_log::NewOperatorAuditLog},
+    schema::ledger_infos,
+    utils::database_connection::get_db_connection,
+};
+
+/// Remove the chain id from ledger_infos, the processor records the chain id of its transaction
+/// stream on its next start. Only needed when a database is deliberately moved to another
+/// network, e.g. after a testnet reset.
+#[derive(Debug, Args)]
+pub struct ResetChainIdArgs {
+    /// Don't ask for confirmation
+    #[clap(long)]
+    pub yes: bool,
+}
+
+impl ResetChainIdArgs {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        let pool = operator_db_pool(&config).await?;
+        let mut conn = get_db_connection(&pool).await?;
+        let Some(ledger_info) = LedgerInfo::get(&mut conn)
+            .await
+            .context("Failed to read ledger_infos")?
+        else {
+            println!("ledger_infos has no chain id, nothing to reset");
+            return Ok(());
+        };
+
+        println!("ledger_infos has chain id {}.", ledger_info.chain_id);
+        if !confirm(
+            "The data in this database is kept. Remove the chain id?",
+            self.yes,
+        )? {
+            println!("Nothing changed");
+            return Ok(());
+        }
+
+        conn.transaction(|conn| {
+            Box::pin(async move {
+                diesel::delete(ledger_infos::table).execute(conn).await?;
+                NewOperatorAuditLog::new(
+                    "reset-chain-id",
+                    json!({ "chain_id": ledger_info.chain_id }),
+                )
+                .insert(conn)
+                .await
+            })
+        })
+        .await
+        .context("Failed to reset the chain id")?;
+        println!("Removed chain id {}", ledger_info.chain_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use serde_json::json;
+
+use super::{confirm, operator_db_pool};
+use crate::{
+    config::indexer_processor_config::IndexerProcessorConfig,
+    db_models::{operator_audit_log::NewOperatorAuditLog, processor_status::ProcessorStatusQuery},
+    schema::processor_status,
+    utils::database_connection::get_db_connection,
+};
+
+/// Move a processor back so it processes again from `version` on its next start. Stop the
+/// processor first, a running processor keeps going from where it is and overwrites the rewind.
+#[derive(Debug, Args)]
+pub struct RewindArgs {
+    /// Name of the processor in processor_status, see the status command
+    pub processor: String,
+    /// First version to process again, 0 removes the processor from processor_status so it starts
+    /// from the configured starting_version
+    pub version: u64,
+    /// Don't ask for confirmation
+    #[clap(long)]
+    pub yes: bool,
+}
+
+impl RewindArgs {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        let pool = operator_db_pool(&config).await?;
+        let mut conn = get_db_connection(&pool).await?;
+        let status = ProcessorStatusQuery::get_by_processor(&self.processor, &mut conn)
+            .await
+            .context("Failed to read processor_status")?
+            .with_context(|| format!("No processor {} in processor_status", self.processor))?;
+        let next_version = status.last_success_version as u64 + 1;
+        anyhow::ensure!(
+            self.version < next_version,
+            "{} continues from version {} already, rewind only moves processors back",
+            self.processor,
+            next_version
+        );
+
+        println!(
+            "{} continues from version {}, after the rewind it processes again from version {}.",
+            self.processor, next_version, self.version
+        );
+        if let Some(starting_version) = config
+            .transaction_stream_config
+            .starting_version
+            .filter(|starting_version| *starting_version > self.version)
+        {
+            println!(
+                "Processors with this config start from starting_version {} at the earliest.",
+                starting_version
+            );
+        }
+        if !confirm("Make sure the processor is stopped. Rewind it?", self.yes)? {
+            println!("Nothing changed");
+            return Ok(());
+        }
+
+        let processor = self.processor.clone();
+        let version = self.version;
+        conn.transaction(|conn| {
+            Box::pin(async move {
+                let target =
+                    processor_status::table.filter(processor_status::processor.eq(&processor));
+                if version == 0 {
+                    diesel::delete(target).execute(conn).await?;
+                } else {
+                    diesel::update(target)
+                        .set((
+                            processor_status::last_success_version.eq(version as i64 - 1),
+                            processor_status::last_updated.eq(diesel::dsl::now),
+                        ))
+                        .execute(conn)
+                        .await?;
+                }
+                NewOperatorAuditLog::new(
+                    "rewind",
+                    json!({
+                        "processor": processor,
+                        "previous_last_success_version": status.last_success_version,
+                        "version": version,
+                    }),
+                )
+                .insert(conn)
+                .await
+            })
+        })
+        .await
+        .context("Failed to rewind processor")?;
+        println!("Rewound {} to version {}", self.processor, self.version);
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use diesel::{sql_query, sql_types::BigInt, sql_types::Text, QueryableByName};
+use diesel_async::RunQueryDsl;
+
+use super::operator_db_pool;
+use crate::{
+    config::indexer_processor_config::IndexerProcessorConfig,
+    utils::database_connection::get_db_connection,
+};
+
+// Tables written by the processors
+const TABLES: [&str; 13] = [
+    "nft_asks",
+    "nft_bids",
+    "collection_bids",
+    "filled_collection_bids",
+    "activities",
+    "sweeps",
+    "transactions",
+    "raw_marketplace_events",
+    "module_upgrade_history",
+    "package_upgrade_history",
+    "backfill_shards",
+    "processor_status",
+    "operator_audit_log",
+];
+// Tables with a marketplace_addr column
+const MARKETPLACE_TABLES: [&str; 4] = ["nft_asks", "nft_bids", "collection_bids", "activities"];
+
+#[derive(QueryableByName)]
+struct RowCount {
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+#[derive(QueryableByName)]
+struct MarketplaceRowCount {
+    #[diesel(sql_type = Text)]
+    marketplace_addr: String,
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+/// Print the number of rows of every table, and of the marketplace tables per marketplace.
+/// Rows are counted exactly, which takes a while on large tables.
+#[derive(Debug, Args)]
+pub struct StatsArgs {}
+
+impl StatsArgs {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        let pool = operator_db_pool(&config).await?;
+        let mut conn = get_db_connection(&pool).await?;
+
+        println!("{:<30} {:>15}", "table", "rows");
+        for table in TABLES {
+            let row_count = sql_query(format!("SELECT count(*) AS count FROM {}", table))
+                .get_result::<RowCount>(&mut conn)
+                .await
+                .with_context(|| format!("Failed to count rows of {}", table))?;
+            println!("{:<30} {:>15}", table, row_count.count);
+        }
+
+        for table in MARKETPLACE_TABLES {
+            let row_counts = sql_query(format!(
+                "SELECT marketplace_addr, count(*) AS count FROM {} \
+                 GROUP BY marketplace_addr ORDER BY marketplace_addr",
+                table
+            ))
+            .load::<MarketplaceRowCount>(&mut conn)
+            .await
+            .with_context(|| format!("Failed to count rows of {} per marketplace", table))?;
+            println!();
+            println!("{:<68} {:>15}", format!("{} by marketplace", table), "rows");
+            for row_count in row_counts {
+                println!("{:<68} {:>15}", row_count.marketplace_addr, row_count.count);
+            }
+        }
+        Ok(())
+    }
+}
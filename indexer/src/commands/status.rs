@@ -0,0 +1,69 @@
+use anyhow::{Context, Result};
+use clap::Args;
+
+use super::operator_db_pool;
+use crate::{
+    config::indexer_processor_config::IndexerProcessorConfig,
+    db_models::processor_status::ProcessorStatusQuery,
+    utils::database_connection::get_db_connection,
+};
+
+/// List every processor in processor_status with its last processed version, the timestamp of
+/// that transaction and how far behind the chain that is.
+#[derive(Debug, Args)]
+pub struct StatusArgs {}
+
+impl StatusArgs {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        let pool = operator_db_pool(&config).await?;
+        let mut conn = get_db_connection(&pool).await?;
+        let statuses = ProcessorStatusQuery::get_all(&mut conn)
+            .await
+            .context("Failed to read processor_status")?;
+        if statuses.is_empty() {
+            println!("No processor has recorded its progress yet");
+            return Ok(());
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        println!(
+            "{:<50} {:>20} {:<26} {:>12} {:<26}",
+            "processor", "version", "transaction timestamp", "lag", "last updated"
+        );
+        for status in statuses {
+            let (timestamp, lag) = match status.last_transaction_timestamp {
+                Some(timestamp) => (
+                    timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    format_lag(now - timestamp),
+                ),
+                None => ("-".to_string(), "-".to_string()),
+            };
+            println!(
+                "{:<50} {:>20} {:<26} {:>12} {:<26}",
+                status.processor,
+                status.last_success_version,
+                timestamp,
+                lag,
+                status.last_updated.format("%Y-%m-%d %H:%M:%S"),
+            );
+        }
+        Ok(())
+    }
+}
+
+/// e.g. 1d 2h 3m 4s, leaving out the leading zero units.
+fn format_lag(lag: chrono::Duration) -> String {
+    let secs = lag.num_seconds().max(0);
+    let (days, hours, minutes, secs) = (
+        secs / 86_400,
+        secs % 86_400 / 3_600,
+        secs % 3_600 / 60,
+        secs % 60,
+    );
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, _) => format!("{}m {}s", minutes, secs),
+        (0, _, _) => format!("{}h {}m {}s", hours, minutes, secs),
+        _ => format!("{}d {}h {}m {}s", days, hours, minutes, secs),
+    }
+}
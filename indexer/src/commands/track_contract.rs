@@ -0,0 +1,197 @@
+use anyhow::{Context, Result};
+use aptos_indexer_processor_sdk::utils::convert::standardize_address;
+use clap::Args;
+use diesel_async::AsyncConnection;
+use serde_json::json;
+use std::time::Duration;
+
+use super::{operator_db_pool, reindex::reindex};
+use crate::{
+    config::indexer_processor_config::{CustomConfig, IndexerProcessorConfig},
+    db_models::{
+        operator_audit_log::NewOperatorAuditLog, processor_status::ProcessorStatusQuery,
+        tracked_contract_activations::TrackedContractActivation,
+        tracked_contracts::TrackedContract,
+    },
+    indexers::marketplace_indexer::{
+        event_parsers::ParserFamily, processor::MarketplaceProcessor, reindex::ReindexRange,
+    },
+    utils::{database_connection::get_db_connection, tracked_contracts::TrackedContractKind},
+};
+
+/// Add a contract to tracked_contracts, or change it if it's tracked already. Processors with a
+/// tracked_contracts_config pick it up within their poll interval, without a restart.
+#[derive(Debug, Args)]
+pub struct TrackContractArgs {
+    /// Contract address
+    pub contract_address: String,
+    /// Processors that track the contract
+    #[clap(long, value_enum)]
+    pub kind: TrackedContractKind,
+    /// Only parse the marketplace's events with this parser, every parser is tried otherwise
+    #[clap(long, value_enum)]
+    pub parser_family: Option<ParserFamily>,
+    /// First version to track the contract from
+    #[clap(long, default_value_t = 0)]
+    pub start_version: u64,
+    /// Also process the versions from --start-version the processor of the config is past
+    /// already, for this marketplace alone
+    #[clap(long)]
+    pub backfill: bool,
+}
+
+impl TrackContractArgs {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        anyhow::ensure!(
+            self.kind == TrackedContractKind::Marketplace || self.parser_family.is_none(),
+            "--parser-family only applies to marketplaces"
+        );
+        anyhow::ensure!(
+            !self.backfill
+                || (self.kind == TrackedContractKind::Marketplace
+                    && matches!(config.custom_config, CustomConfig::MarketplaceIndexer(_))),
+            "--backfill only supports marketplaces, with the marketplace indexer config"
+        );
+        let contract_address = standardize_address(&self.contract_address.to_lowercase());
+        let tracked_contract = TrackedContract {
+            contract_address: contract_address.clone(),
+            kind: self.kind.to_string(),
+            parser_family: self.parser_family.map(|family| family.to_string()),
+            start_version: self.start_version as i64,
+        };
+
+        let pool = operator_db_pool(&config).await?;
+        let mut conn = get_db_connection(&pool).await?;
+        conn.transaction(|conn| {
+            Box::pin(async move {
+                let details = json!({
+                    "contract_address": tracked_contract.contract_address,
+                    "kind": tracked_contract.kind,
+                    "parser_family": tracked_contract.parser_family,
+                    "start_version": tracked_contract.start_version,
+                });
+                // Processors record when they pick up the change again
+                TrackedContractActivation::delete_for_contract(
+                    &tracked_contract.contract_address,
+                    &tracked_contract.kind,
+                    conn,
+                )
+                .await?;
+                tracked_contract.upsert(conn).await?;
+                NewOperatorAuditLog::new("track-contract", details)
+                    .insert(conn)
+                    .await
+            })
+        })
+        .await
+        .context("Failed to track contract")?;
+        println!(
+            "Tracking {} {} from version {}",
+            self.kind, contract_address, self.start_version
+        );
+
+        if self.backfill {
+            self.backfill(config, contract_address).await?;
+        }
+        Ok(())
+    }
+
+    /// Process [start_version, the version the running processor tracks the marketplace from)
+    /// for the marketplace. That's where the processor picked up the change to tracked_contracts,
+    /// which it records in tracked_contract_activations.
+    async fn backfill(
+        &self,
+        config: IndexerProcessorConfig,
+        contract_address: String,
+    ) -> Result<()> {
+        let processor_name = config.processor_config.name().to_string();
+        let poll_interval =
+            config
+                .tracked_contracts_config
+                .as_ref()
+                .map(|tracked_contracts_config| {
+                    Duration::from_secs(tracked_contracts_config.poll_interval_secs)
+                });
+        let processor = MarketplaceProcessor::new(config).await?;
+
+        let Some(poll_interval) = poll_interval else {
+            // The processor only tracks the marketplace once it's restarted, so up to the
+            // version it's at
+            let status = ProcessorStatusQuery::get_by_processor(
+                &processor_name,
+                &mut get_db_connection(&processor.db_pool).await?,
+            )
+            .await
+            .context("Failed to read processor_status")?;
+            match status {
+                Some(status) if status.last_success_version >= self.start_version as i64 => {
+                    let range = (self.start_version as i64, status.last_success_version);
+                    backfill_range(&processor, &contract_address, range).await?;
+                    println!("Backfill finished");
+                }
+                _ => println!(
+                    "{} didn't process version {} yet, nothing to backfill",
+                    processor_name, self.start_version
+                ),
+            }
+            return Ok(());
+        };
+
+        let kind = TrackedContractKind::Marketplace.to_string();
+        let mut next_version = self.start_version as i64;
+        loop {
+            // Wait for the processor to pick up the marketplace and store the versions before it
+            let activated_version = loop {
+                let mut conn = get_db_connection(&processor.db_pool).await?;
+                let activated_version = TrackedContractActivation::get(
+                    &processor_name,
+                    &contract_address,
+                    &kind,
+                    &mut conn,
+                )
+                .await
+                .context("Failed to read tracked_contract_activations")?;
+                if let Some(activated_version) = activated_version {
+                    let status = ProcessorStatusQuery::get_by_processor(&processor_name, &mut conn)
+                        .await
+                        .context("Failed to read processor_status")?;
+                    if status
+                        .is_some_and(|status| status.last_success_version >= activated_version - 1)
+                    {
+                        break activated_version;
+                    }
+                }
+                println!("Waiting for {} to pick up the marketplace", processor_name);
+                tokio::time::sleep(poll_interval).await;
+            };
+            // The activation only grows if a later filter of the processor picks it up after
+            // the one that recorded it first
+            if activated_version <= next_version {
+                break;
+            }
+            let range = (next_version, activated_version - 1);
+            backfill_range(&processor, &contract_address, range).await?;
+            next_version = activated_version;
+        }
+        println!("Backfill finished");
+        Ok(())
+    }
+}
+
+/// Reindex the versions of `range` for the marketplace alone.
+async fn backfill_range(
+    processor: &MarketplaceProcessor,
+    contract_address: &str,
+    (start_version, end_version): (i64, i64),
+) -> Result<()> {
+    println!(
+        "Backfilling {} for versions [{}, {}]",
+        contract_address, start_version, end_version
+    );
+    let range = ReindexRange {
+        start_version,
+        end_version,
+        marketplace_addr: Some(contract_address.to_string()),
+    };
+    reindex(processor, range, vec![contract_address.to_string()], false).await
+}
@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use aptos_indexer_processor_sdk::utils::convert::standardize_address;
+use clap::Args;
+use diesel_async::AsyncConnection;
+use serde_json::json;
+
+use super::operator_db_pool;
+use crate::{
+    config::indexer_processor_config::IndexerProcessorConfig,
+    db_models::{
+        operator_audit_log::NewOperatorAuditLog,
+        tracked_contract_activations::TrackedContractActivation,
+        tracked_contracts::TrackedContract,
+    },
+    utils::{database_connection::get_db_connection, tracked_contracts::TrackedContractKind},
+};
+
+/// Remove a contract from tracked_contracts. What was derived from its events so far is kept.
+#[derive(Debug, Args)]
+pub struct UntrackContractArgs {
+    /// Contract address
+    pub contract_address: String,
+    /// Processors that track the contract
+    #[clap(long, value_enum)]
+    pub kind: TrackedContractKind,
+}
+
+impl UntrackContractArgs {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        let contract_address = standardize_address(&self.contract_address.to_lowercase());
+        let kind = self.kind.to_string();
+        let pool = operator_db_pool(&config).await?;
+        let mut conn = get_db_connection(&pool).await?;
+        let removed = conn
+            .transaction(|conn| {
+                Box::pin(async move {
+                    let removed = TrackedContract::delete(&contract_address, &kind, conn).await?;
+                    TrackedContractActivation::delete_for_contract(&contract_address, &kind, conn)
+                        .await?;
+                    if let Some(removed) = &removed {
+                        NewOperatorAuditLog::new(
+                            "untrack-contract",
+                            json!({
+                                "contract_address": removed.contract_address,
+                                "kind": removed.kind,
+                                "parser_family": removed.parser_family,
+                                "start_version": removed.start_version,
+                            }),
+                        )
+                        .insert(conn)
+                        .await?;
+                    }
+                    Ok::<_, diesel::result::Error>(removed)
+                })
+            })
+            .await
+            .context("Failed to untrack contract")?;
+        match removed {
+            Some(removed) => println!(
+                "Stopped tracking {} {}",
+                self.kind, removed.contract_address
+            ),
+            None => println!("{} {} wasn't tracked", self.kind, self.contract_address),
+        }
+        Ok(())
+    }
+}
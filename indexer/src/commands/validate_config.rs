@@ -0,0 +1,38 @@
+use anyhow::Result;
+use clap::Args;
+
+use crate::config::{
+    indexer_processor_config::IndexerProcessorConfig,
+    validation::{validate_config, validate_database},
+};
+
+/// Check the config before deploying it: that the custom_config fits the processor type, the
+/// contract addresses are valid, and the database is reachable, migrated and on the same chain
+/// as the transaction source. Every problem is reported, not only the first.
+#[derive(Debug, Args)]
+pub struct ValidateConfigArgs {
+    /// Only check the config itself, without connecting to the database or transaction source
+    #[clap(long)]
+    pub skip_connections: bool,
+}
+
+impl ValidateConfigArgs {
+    pub async fn run(self, config: IndexerProcessorConfig) -> Result<()> {
+        let mut problems = validate_config(&config);
+        if !self.skip_connections {
+            problems.extend(validate_database(&config).await);
+        }
+
+        if problems.is_empty() {
+            println!("Config is valid");
+            return Ok(());
+        }
+        for problem in &problems {
+            println!("- {}", problem);
+        }
+        Err(anyhow::anyhow!(
+            "Found {} problem(s) in the config",
+            problems.len()
+        ))
+    }
+}
@@ -1,29 +1,120 @@
+use ahash::AHashMap;
 use anyhow::Result;
 use aptos_indexer_processor_sdk::aptos_indexer_transaction_stream::TransactionStreamConfig;
 use aptos_indexer_processor_sdk_server_framework::RunnableConfig;
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, path::PathBuf, str::FromStr, time::Duration};
 
-use super::processor_config::ProcessorConfig;
-use crate::indexers::{
-    contract_upgrade_indexer::processor::ContractUpgradeProcessor,
-    marketplace_indexer::processor::MarketplaceProcessor,
+use super::{interpolation::resolve_config_values, processor_config::ProcessorConfig};
+use crate::{
+    indexers::{
+        composite_indexer::processor::CompositeProcessor,
+        contract_upgrade_indexer::processor::ContractUpgradeProcessor,
+        marketplace_indexer::{
+            backfill_coordinator::BackfillCoordinator, processor::MarketplaceProcessor,
+        },
+    },
+    utils::{
+        latest_processed_version_tracker::UPDATE_PROCESSOR_STATUS_SECS,
+        leader_election::LeaderLock, shutdown::shutdown_deadline,
+    },
 };
 
 pub const QUERY_DEFAULT_RETRIES: u32 = 5;
 pub const QUERY_DEFAULT_RETRY_DELAY_MS: u64 = 500;
 
+// Deserialize resolves ${env:...}, ${file:...} and *_file secrets first, see `interpolation`
 #[derive(Clone, Debug, Deserialize, Serialize)]
-#[serde(deny_unknown_fields)]
+#[serde(remote = "Self", deny_unknown_fields)]
 pub struct IndexerProcessorConfig {
     pub processor_config: ProcessorConfig,
     pub transaction_stream_config: TransactionStreamConfig,
     pub db_config: DbConfig,
     pub custom_config: CustomConfig,
+    // Only used by the sharded backfill processor
+    #[serde(default)]
+    pub backfill_config: Option<BackfillConfig>,
+    // Store the raw events of tracked marketplace contracts in raw_marketplace_events,
+    // so marketplace tables can be re-derived later without the transaction stream
+    #[serde(default)]
+    pub archive_raw_events: bool,
+    // Read transactions from recorded files instead of the transaction stream
+    #[serde(default)]
+    pub file_source_config: Option<FileSourceConfig>,
+    #[serde(default)]
+    pub pipeline_config: PipelineConfig,
+    // Only one replica with the same processor name processes at a time, the others wait as
+    // standby and take over when it stops
+    #[serde(default)]
+    pub leader_election_config: Option<LeaderElectionConfig>,
+    // Also track the contracts in the tracked_contracts table, which is reloaded while the
+    // processor runs
+    #[serde(default)]
+    pub tracked_contracts_config: Option<TrackedContractsConfig>,
+}
+
+impl<'de> Deserialize<'de> for IndexerProcessorConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = serde_json::Value::deserialize(deserializer)?;
+        resolve_config_values(&mut value).map_err(|e| D::Error::custom(format!("{:#}", e)))?;
+        IndexerProcessorConfig::deserialize(value).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for IndexerProcessorConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        IndexerProcessorConfig::serialize(self, serializer)
+    }
 }
 
 #[async_trait::async_trait]
 impl RunnableConfig for IndexerProcessorConfig {
     async fn run(&self) -> Result<()> {
+        let shutdown_timeout = Duration::from_secs(self.pipeline_config.shutdown_timeout_secs);
+        tokio::select! {
+            res = self.run_as_leader() => res,
+            _ = shutdown_deadline(shutdown_timeout) => Err(anyhow::anyhow!(
+                "Processor did not finish its in-flight batches within {}s of the shutdown",
+                shutdown_timeout.as_secs()
+            )),
+        }
+    }
+
+    fn get_server_name(&self) -> String {
+        // Get the part before the first _ and trim to 12 characters.
+        let before_underscore = self
+            .processor_config
+            .name()
+            .split('_')
+            .next()
+            .unwrap_or("unknown");
+        before_underscore[..before_underscore.len().min(12)].to_string()
+    }
+}
+
+impl IndexerProcessorConfig {
+    /// Run the processor once this replica is the leader, or right away without leader election.
+    async fn run_as_leader(&self) -> Result<()> {
+        let Some(leader_election_config) = &self.leader_election_config else {
+            return self.run_processor().await;
+        };
+        let leader_lock = LeaderLock::acquire(
+            &self.db_config.connection_string()?,
+            self.processor_config.name(),
+            Duration::from_secs(leader_election_config.check_interval_secs),
+        )
+        .await?;
+        let Some(leader_lock) = leader_lock else {
+            // Shut down while waiting as standby
+            return Ok(());
+        };
+        tokio::select! {
+            res = self.run_processor() => res,
+            e = leader_lock.lost() => Err(e),
+        }
+    }
+
+    async fn run_processor(&self) -> Result<()> {
         match self.processor_config {
             ProcessorConfig::ContractUpgradeIndexer => {
                 let processor = ContractUpgradeProcessor::new(self.clone()).await?;
@@ -45,19 +136,16 @@ impl RunnableConfig for IndexerProcessorConfig {
                 let processor = MarketplaceProcessor::new(self.clone()).await?;
                 processor.run_processor().await
             }
+            ProcessorConfig::ShardedBackfillMarketplaceIndexer => {
+                let coordinator = BackfillCoordinator::new(self.clone()).await?;
+                coordinator.run().await
+            }
+            ProcessorConfig::CompositeIndexer => {
+                let processor = CompositeProcessor::new(self.clone()).await?;
+                processor.run_processor().await
+            }
         }
     }
-
-    fn get_server_name(&self) -> String {
-        // Get the part before the first _ and trim to 12 characters.
-        let before_underscore = self
-            .processor_config
-            .name()
-            .split('_')
-            .next()
-            .unwrap_or("unknown");
-        before_underscore[..before_underscore.len().min(12)].to_string()
-    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -67,12 +155,144 @@ pub struct DbConfig {
     // Size of the pool for writes/reads to the DB. Limits maximum number of queries in flight
     #[serde(default = "DbConfig::default_db_pool_size")]
     pub db_pool_size: u32,
+    // Overrides sslmode of the connection string, which defaults to prefer like libpq
+    #[serde(default)]
+    pub ssl_mode: Option<SslMode>,
+    // PEM CA certificate to verify the server with, overrides sslrootcert of the connection string
+    #[serde(default)]
+    pub ssl_root_cert: Option<PathBuf>,
+    // PEM client certificate and PKCS#8 key, override sslcert and sslkey of the connection string
+    #[serde(default)]
+    pub ssl_client_cert: Option<PathBuf>,
+    #[serde(default)]
+    pub ssl_client_key: Option<PathBuf>,
+    // Postgres schema the tables are in, e.g. mainnet or testnet, so processors of different
+    // networks can share a database. Sets search_path, which overrides options of the connection
+    // string. Defaults to the search_path of the server, usually public
+    #[serde(default)]
+    pub schema: Option<String>,
 }
 
 impl DbConfig {
     pub const fn default_db_pool_size() -> u32 {
         50
     }
+
+    /// Connection string with the TLS settings and schema of the config applied as libpq style
+    /// parameters, which is what the database connection reads them from.
+    pub fn connection_string(&self) -> Result<String> {
+        let mut url = url::Url::parse(&self.postgres_connection_string)
+            .map_err(|e| anyhow::anyhow!("Invalid postgres_connection_string: {}", e))?;
+        if let Some(schema) = &self.schema {
+            // It ends up unquoted in the connection options
+            anyhow::ensure!(
+                is_schema_name(schema),
+                "Invalid schema \"{}\", expected lowercase letters, digits and underscores",
+                schema
+            );
+        }
+        let overrides = [
+            ("sslmode", self.ssl_mode.map(|mode| mode.to_string())),
+            (
+                "sslrootcert",
+                self.ssl_root_cert
+                    .as_ref()
+                    .map(|path| path.display().to_string()),
+            ),
+            (
+                "sslcert",
+                self.ssl_client_cert
+                    .as_ref()
+                    .map(|path| path.display().to_string()),
+            ),
+            (
+                "sslkey",
+                self.ssl_client_key
+                    .as_ref()
+                    .map(|path| path.display().to_string()),
+            ),
+            (
+                "options",
+                self.schema
+                    .as_ref()
+                    .map(|schema| format!("-csearch_path={}", schema)),
+            ),
+        ];
+        if overrides.iter().all(|(_, value)| value.is_none()) {
+            return Ok(self.postgres_connection_string.clone());
+        }
+
+        let params =
+            url.query_pairs()
+                .filter(|(key, _)| {
+                    !overrides
+                        .iter()
+                        .any(|(name, value)| key == name && value.is_some())
+                })
+                .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                .chain(overrides.iter().filter_map(|(name, value)| {
+                    value.clone().map(|value| (name.to_string(), value))
+                }))
+                .collect::<Vec<_>>();
+        url.query_pairs_mut().clear().extend_pairs(params);
+        Ok(url.to_string())
+    }
+}
+
+fn is_schema_name(schema: &str) -> bool {
+    schema.len() <= 63
+        && schema.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && schema
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// TLS mode of database connections, with the semantics of libpq's sslmode. Unlike libpq,
+/// prefer also verifies the CA when a root certificate is given, like require does.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    // Plain connections only
+    Disable,
+    // TLS if the server supports it. Any server certificate is accepted, so by default the
+    // server isn't authenticated. With a root certificate it must be signed by that CA, like
+    // with verify-ca.
+    #[default]
+    Prefer,
+    // Always TLS, the certificate is verified like with prefer
+    Require,
+    // Always TLS and the certificate must be signed by a trusted CA
+    VerifyCa,
+    // Like verify-ca and the certificate must also match the host name
+    VerifyFull,
+}
+
+impl FromStr for SslMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "disable" => Ok(Self::Disable),
+            "prefer" => Ok(Self::Prefer),
+            "require" => Ok(Self::Require),
+            "verify-ca" => Ok(Self::VerifyCa),
+            "verify-full" => Ok(Self::VerifyFull),
+            _ => Err(anyhow::anyhow!("Unsupported sslmode: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for SslMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self {
+            Self::Disable => "disable",
+            Self::Prefer => "prefer",
+            Self::Require => "require",
+            Self::VerifyCa => "verify-ca",
+            Self::VerifyFull => "verify-full",
+        };
+        write!(f, "{}", mode)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -80,4 +300,188 @@ impl DbConfig {
 pub enum CustomConfig {
     ContractUpgradeIndexer(Vec<String>),
     MarketplaceIndexer(Vec<String>),
+    // Processors of the composite indexer, which share its transaction stream
+    CompositeIndexer(Vec<SubProcessorConfig>),
+}
+
+impl CustomConfig {
+    /// Contract addresses of the config, of every sub-processor for the composite indexer.
+    pub fn contract_addresses(&self) -> Vec<String> {
+        match self {
+            CustomConfig::ContractUpgradeIndexer(contract_addresses)
+            | CustomConfig::MarketplaceIndexer(contract_addresses) => contract_addresses.clone(),
+            CustomConfig::CompositeIndexer(sub_processors) => sub_processors
+                .iter()
+                .flat_map(|sub_processor| sub_processor.custom_config.contract_addresses())
+                .collect(),
+        }
+    }
+}
+
+/// A processor run by the composite indexer. It records its progress in processor_status under
+/// its own name, like it does when it runs alone.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubProcessorConfig {
+    pub processor_config: ProcessorConfig,
+    pub custom_config: CustomConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackfillConfig {
+    // Workers only claim shards of the backfill with the same name, also used for shard trackers
+    pub backfill_name: String,
+    // Both start and end versions are inclusive
+    pub start_version: u64,
+    pub end_version: u64,
+    #[serde(default = "BackfillConfig::default_shard_size")]
+    pub shard_size: u64,
+    // Number of workers in this process, run more processes with the same config to scale out
+    #[serde(default = "BackfillConfig::default_num_workers")]
+    pub num_workers: usize,
+    // A shard is marked as failed after it's been claimed this many times without completing
+    #[serde(default = "BackfillConfig::default_max_attempts")]
+    pub max_attempts: i32,
+    // An in progress shard without heartbeat for this long is considered abandoned and reclaimed
+    #[serde(default = "BackfillConfig::default_lease_secs")]
+    pub lease_secs: u64,
+}
+
+impl BackfillConfig {
+    pub const fn default_shard_size() -> u64 {
+        1_000_000
+    }
+
+    pub const fn default_num_workers() -> usize {
+        4
+    }
+
+    pub const fn default_max_attempts() -> i32 {
+        3
+    }
+
+    pub const fn default_lease_secs() -> u64 {
+        300
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileSourceConfig {
+    // Directory with .json (array of transactions) or .pb (TransactionsInStorage) files,
+    // files are read in name order and must have ascending versions
+    pub directory: PathBuf,
+    // Max number of transactions per batch sent to the extractor
+    #[serde(default = "FileSourceConfig::default_batch_size")]
+    pub batch_size: usize,
+}
+
+impl FileSourceConfig {
+    pub const fn default_batch_size() -> usize {
+        1000
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineConfig {
+    // Max rows per insert, keyed by table name. Tables without an entry insert as many rows as
+    // fit in the Postgres bind parameter limit
+    #[serde(default)]
+    pub per_table_chunk_sizes: AHashMap<String, usize>,
+    #[serde(default)]
+    pub channel_sizes: ChannelSizes,
+    // How often the version tracker writes processor_status
+    #[serde(default = "PipelineConfig::default_processor_status_update_interval_secs")]
+    pub processor_status_update_interval_secs: u64,
+    // How long in-flight batches get to finish after SIGTERM before the processor exits anyway
+    #[serde(default = "PipelineConfig::default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+}
+
+impl PipelineConfig {
+    pub const fn default_processor_status_update_interval_secs() -> u64 {
+        UPDATE_PROCESSOR_STATUS_SECS
+    }
+
+    // Cloud Run kills the container 10 seconds after SIGTERM
+    pub const fn default_shutdown_timeout_secs() -> u64 {
+        8
+    }
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            per_table_chunk_sizes: AHashMap::new(),
+            channel_sizes: ChannelSizes::default(),
+            processor_status_update_interval_secs:
+                Self::default_processor_status_update_interval_secs(),
+            shutdown_timeout_secs: Self::default_shutdown_timeout_secs(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LeaderElectionConfig {
+    // How often a standby tries to become the leader and the leader checks it still is, a
+    // standby takes over within about this long after the leader stops
+    #[serde(default = "LeaderElectionConfig::default_check_interval_secs")]
+    pub check_interval_secs: u64,
+}
+
+impl LeaderElectionConfig {
+    pub const fn default_check_interval_secs() -> u64 {
+        2
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrackedContractsConfig {
+    // How often tracked_contracts is reloaded, contracts added to it are tracked within about
+    // this long
+    #[serde(default = "TrackedContractsConfig::default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+}
+
+impl TrackedContractsConfig {
+    pub const fn default_poll_interval_secs() -> u64 {
+        10
+    }
+}
+
+/// Number of batches that can wait in the channel in front of each step. Larger channels let a
+/// backfill buffer more batches while the storer catches up, at the cost of memory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelSizes {
+    #[serde(default = "ChannelSizes::default_channel_size")]
+    pub extractor: usize,
+    #[serde(default = "ChannelSizes::default_channel_size")]
+    pub storer: usize,
+    #[serde(default = "ChannelSizes::default_channel_size")]
+    pub version_tracker: usize,
+    // Channel of the pipeline output
+    #[serde(default = "ChannelSizes::default_channel_size")]
+    pub output: usize,
+}
+
+impl ChannelSizes {
+    pub const fn default_channel_size() -> usize {
+        10
+    }
+}
+
+impl Default for ChannelSizes {
+    fn default() -> Self {
+        Self {
+            extractor: Self::default_channel_size(),
+            storer: Self::default_channel_size(),
+            version_tracker: Self::default_channel_size(),
+            output: Self::default_channel_size(),
+        }
+    }
 }
@@ -1,2 +1,4 @@
 pub mod indexer_processor_config;
+pub mod interpolation;
 pub mod processor_config;
+pub mod validation;
@@ -42,6 +42,10 @@ pub enum ProcessorConfig {
     TradeportMarketplaceIndexer,
     // For backfilling data
     BackfillMarketplaceIndexer,
+    // For backfilling data with shards coordinated through the database
+    ShardedBackfillMarketplaceIndexer,
+    // For running several of the above from one transaction stream
+    CompositeIndexer,
 }
 
 impl ProcessorConfig {
@@ -71,6 +75,8 @@ pub enum Processor {
     WapalMarketplaceIndexer,
     TradeportMarketplaceIndexer,
     BackfillMarketplaceIndexer,
+    ShardedBackfillMarketplaceIndexer,
+    CompositeIndexer,
 }
 
 #[cfg(test)]
@@ -0,0 +1,310 @@
+use ahash::AHashSet;
+use aptos_indexer_processor_sdk::utils::convert::standardize_address;
+use diesel::{
+    migration::MigrationSource,
+    pg::Pg,
+    sql_query,
+    sql_types::{Nullable, Text},
+    QueryableByName,
+};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use std::time::Duration;
+
+use super::{
+    indexer_processor_config::{CustomConfig, IndexerProcessorConfig},
+    processor_config::ProcessorConfig,
+};
+use crate::{
+    db_models::ledger_info::LedgerInfo,
+    indexers::composite_indexer::processor::is_supported_sub_processor,
+    utils::{
+        database_connection::establish_connection, database_migrations::MIGRATIONS,
+        transaction_source::TransactionSourceStep,
+    },
+};
+
+// How long to wait for the database before reporting it as unreachable
+const CONNECT_TIMEOUT_SECS: u64 = 10;
+
+#[derive(QueryableByName)]
+struct AppliedMigration {
+    #[diesel(sql_type = Text)]
+    version: String,
+}
+
+#[derive(QueryableByName)]
+struct CurrentSchema {
+    #[diesel(sql_type = Nullable<Text>)]
+    schema: Option<String>,
+}
+
+/// Check the parts of a config that don't need the database or the transaction stream, returns
+/// every problem found.
+pub fn validate_config(config: &IndexerProcessorConfig) -> Vec<String> {
+    let mut problems = vec![];
+
+    match (&config.processor_config, &config.custom_config) {
+        (ProcessorConfig::CompositeIndexer, CustomConfig::CompositeIndexer(sub_processors)) => {
+            if sub_processors.is_empty() {
+                problems.push("custom_config composite_indexer has no processors".to_string());
+            }
+            let mut seen_names = AHashSet::new();
+            for sub_processor in sub_processors {
+                let name = sub_processor.processor_config.name();
+                // Sub-processors record their progress under their name
+                if !seen_names.insert(name) {
+                    problems.push(format!(
+                        "composite_indexer runs {} more than once, its processor_status would be shared",
+                        name
+                    ));
+                }
+                if !is_supported_sub_processor(&sub_processor.processor_config) {
+                    problems.push(format!("{} can't run in the composite indexer", name));
+                    continue;
+                }
+                validate_processor(
+                    &sub_processor.processor_config,
+                    &sub_processor.custom_config,
+                    config.tracked_contracts_config.is_some(),
+                    &mut problems,
+                );
+            }
+        }
+        (processor_config, custom_config) => validate_processor(
+            processor_config,
+            custom_config,
+            config.tracked_contracts_config.is_some(),
+            &mut problems,
+        ),
+    }
+
+    if matches!(
+        config.processor_config,
+        ProcessorConfig::ShardedBackfillMarketplaceIndexer
+    ) && config.backfill_config.is_none()
+    {
+        problems.push(format!(
+            "processor_config type {} needs a backfill_config",
+            config.processor_config.name()
+        ));
+    }
+
+    if let Err(e) = config.db_config.connection_string() {
+        problems.push(format!("Invalid db_config: {:#}", e));
+    }
+    problems
+}
+
+/// Check that the database is reachable, its migrations are the ones of this build and its
+/// chain id matches the transaction source, returns every problem found.
+pub async fn validate_database(config: &IndexerProcessorConfig) -> Vec<String> {
+    let mut problems = vec![];
+    let mut conn = match connect(config).await {
+        Ok(conn) => conn,
+        Err(problem) => return vec![problem],
+    };
+
+    if let Some(schema) = &config.db_config.schema {
+        // current_schema is null when no schema of the search_path exists
+        match sql_query("SELECT current_schema() AS schema")
+            .get_result::<CurrentSchema>(&mut conn)
+            .await
+        {
+            Ok(CurrentSchema { schema: Some(_) }) => {}
+            Ok(CurrentSchema { schema: None }) => {
+                return vec![format!(
+                    "Schema {} doesn't exist, create it and run the migrations in it",
+                    schema
+                )];
+            }
+            Err(e) => return vec![format!("Failed to read the current schema: {}", e)],
+        }
+    }
+
+    match pending_migrations(&mut conn).await {
+        Ok((pending, unknown)) => {
+            if !pending.is_empty() {
+                problems.push(format!(
+                    "The database is missing migrations {}, run them before starting the processor",
+                    pending.join(", ")
+                ));
+            }
+            if !unknown.is_empty() {
+                problems.push(format!(
+                    "The database has migrations {} this build doesn't know, it was migrated by a newer build",
+                    unknown.join(", ")
+                ));
+            }
+        }
+        Err(e) => problems.push(format!("Failed to read the applied migrations: {}", e)),
+    }
+
+    let db_chain_id = match LedgerInfo::get(&mut conn).await {
+        Ok(ledger_info) => ledger_info.map(|ledger_info| ledger_info.chain_id),
+        Err(e) => {
+            problems.push(format!(
+                "Failed to read the chain id from ledger_infos: {}",
+                e
+            ));
+            None
+        }
+    };
+    match TransactionSourceStep::get_chain_id(config).await {
+        Ok(Some(chain_id)) => {
+            if let Some(db_chain_id) =
+                db_chain_id.filter(|db_chain_id| *db_chain_id != chain_id as i64)
+            {
+                problems.push(format!(
+                    "ledger_infos has chain id {} but the transactions are from chain {}, the database belongs to another network",
+                    db_chain_id, chain_id
+                ));
+            }
+        }
+        // Recorded files without a chain id are not checked by the processor either
+        Ok(None) => {}
+        Err(e) => problems.push(format!(
+            "Failed to get the chain id of the transaction source: {:#}",
+            e
+        )),
+    }
+    problems
+}
+
+/// Check that the custom_config fits the processor type and its contract addresses are valid.
+/// Without any, the processor only indexes something if it also tracks tracked_contracts.
+fn validate_processor(
+    processor_config: &ProcessorConfig,
+    custom_config: &CustomConfig,
+    tracks_table: bool,
+    problems: &mut Vec<String>,
+) {
+    let expected_custom_config = match processor_config {
+        ProcessorConfig::ContractUpgradeIndexer => "contract_upgrade_indexer",
+        ProcessorConfig::RaribleMarketplaceIndexer
+        | ProcessorConfig::WapalMarketplaceIndexer
+        | ProcessorConfig::TradeportMarketplaceIndexer
+        | ProcessorConfig::BackfillMarketplaceIndexer
+        | ProcessorConfig::ShardedBackfillMarketplaceIndexer => "marketplace_indexer",
+        ProcessorConfig::CompositeIndexer => "composite_indexer",
+    };
+    let (custom_config, contract_addresses) = match custom_config {
+        CustomConfig::ContractUpgradeIndexer(addresses) => {
+            ("contract_upgrade_indexer", Some(addresses))
+        }
+        CustomConfig::MarketplaceIndexer(addresses) => ("marketplace_indexer", Some(addresses)),
+        CustomConfig::CompositeIndexer(_) => ("composite_indexer", None),
+    };
+    if custom_config != expected_custom_config {
+        problems.push(format!(
+            "processor_config type {} needs a custom_config of {}, found {}",
+            processor_config.name(),
+            expected_custom_config,
+            custom_config
+        ));
+    }
+    let Some(contract_addresses) = contract_addresses else {
+        return;
+    };
+
+    if contract_addresses.is_empty() && !tracks_table {
+        problems.push(format!(
+            "custom_config {} has no contract addresses, nothing would be indexed",
+            custom_config
+        ));
+    }
+    let mut seen_addresses = AHashSet::new();
+    for addr in contract_addresses {
+        match normalize_address(addr) {
+            Ok(normalized) => {
+                if !seen_addresses.insert(normalized.clone()) {
+                    problems.push(format!(
+                        "Contract address {} is listed more than once (as {})",
+                        normalized, addr
+                    ));
+                }
+            }
+            Err(problem) => problems.push(problem),
+        }
+    }
+}
+
+/// The address in the form the extractors compare event addresses in, or why it isn't valid.
+fn normalize_address(addr: &str) -> Result<String, String> {
+    let hex = addr.strip_prefix("0x").unwrap_or(addr);
+    if hex.is_empty() || hex.len() > 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "Contract address \"{}\" is not a hex address of at most 64 digits",
+            addr
+        ));
+    }
+    Ok(standardize_address(&addr.to_lowercase()))
+}
+
+async fn connect(config: &IndexerProcessorConfig) -> Result<AsyncPgConnection, String> {
+    let url = config
+        .db_config
+        .connection_string()
+        .map_err(|e| format!("Invalid db_config: {:#}", e))?;
+    match tokio::time::timeout(
+        Duration::from_secs(CONNECT_TIMEOUT_SECS),
+        establish_connection(&url),
+    )
+    .await
+    {
+        Ok(Ok(conn)) => Ok(conn),
+        Ok(Err(e)) => Err(format!("Failed to connect to the database: {}", e)),
+        Err(_) => Err(format!(
+            "Failed to connect to the database within {}s",
+            CONNECT_TIMEOUT_SECS
+        )),
+    }
+}
+
+/// Migrations of this build the database hasn't run, and applied migrations this build doesn't
+/// have.
+async fn pending_migrations(
+    conn: &mut AsyncPgConnection,
+) -> anyhow::Result<(Vec<String>, Vec<String>)> {
+    let known = MigrationSource::<Pg>::migrations(&MIGRATIONS)
+        .map_err(|e| anyhow::anyhow!("{}", e))?
+        .iter()
+        .map(|migration| migration.name().version().to_string())
+        .collect::<Vec<_>>();
+    let applied = sql_query("SELECT version FROM __diesel_schema_migrations")
+        .load::<AppliedMigration>(conn)
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect::<AHashSet<_>>();
+
+    let pending = known
+        .iter()
+        .filter(|version| !applied.contains(*version))
+        .cloned()
+        .collect();
+    let known = known.into_iter().collect::<AHashSet<_>>();
+    let mut unknown = applied
+        .into_iter()
+        .filter(|version| !known.contains(version))
+        .collect::<Vec<_>>();
+    unknown.sort();
+    Ok((pending, unknown))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_addresses_are_normalized_like_event_addresses() {
+        assert_eq!(normalize_address("0x1").unwrap(), format!("0x{:0>64}", "1"));
+        assert_eq!(
+            normalize_address("0xE11C12EC495F3989C35E1C6A0AF414451223305B579291FC8F3D9D0575A23C26")
+                .unwrap(),
+            "0xe11c12ec495f3989c35e1c6a0af414451223305b579291fc8f3d9d0575a23c26"
+        );
+        assert!(normalize_address("0x").is_err());
+        assert!(normalize_address("0xmarketplace").is_err());
+        assert!(normalize_address(&format!("0x{}", "1".repeat(65))).is_err());
+    }
+}
@@ -0,0 +1,26 @@
+-- Your SQL goes here
+-- Shards of a version range that sharded backfill workers claim and process independently
+CREATE TABLE
+    backfill_shards (
+        shard_id BIGSERIAL PRIMARY KEY,
+        -- name of the backfill job, workers only claim shards of the job they are configured with
+        backfill_name VARCHAR(50) NOT NULL,
+        -- both start and end versions are inclusive
+        start_version BIGINT NOT NULL,
+        end_version BIGINT NOT NULL,
+        -- 1 is pending, 2 is in progress, 3 is completed, 4 is failed
+        status INT NOT NULL,
+        -- number of times the shard has been claimed
+        attempts INT NOT NULL DEFAULT 0,
+        -- worker that currently holds the shard, null when it's not claimed
+        worker_id VARCHAR(100),
+        last_error TEXT,
+        claimed_at TIMESTAMP,
+        -- workers bump this while processing, an in progress shard with a stale value can be reclaimed
+        last_updated TIMESTAMP NOT NULL DEFAULT NOW(),
+        UNIQUE (backfill_name, start_version),
+        CHECK (start_version <= end_version),
+        CHECK (status IN (1, 2, 3, 4))
+    );
+
+CREATE INDEX idx_backfill_shards_backfill_name_status ON backfill_shards (backfill_name, status);
\ No newline at end of file
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+-- Raw events emitted by tracked marketplace contracts, only written when archive_raw_events is enabled.
+-- Lets marketplace tables be re-derived offline after a parser change without re-streaming from gRPC
+CREATE TABLE
+    raw_marketplace_events (
+        txn_version BIGINT NOT NULL,
+        event_idx BIGINT NOT NULL,
+        -- account address of the event key, 0x0 for module events
+        account_address VARCHAR(300) NOT NULL,
+        type_str TEXT NOT NULL,
+        data JSON NOT NULL,
+        block_timestamp TIMESTAMP NOT NULL,
+        PRIMARY KEY (txn_version, event_idx)
+    );
\ No newline at end of file
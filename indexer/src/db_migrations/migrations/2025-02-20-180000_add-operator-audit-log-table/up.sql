@@ -0,0 +1,13 @@
+-- Your SQL goes here
+-- Changes made to processor state by operator commands such as rewind and reset-chain-id
+CREATE TABLE
+    operator_audit_log (
+        id BIGSERIAL PRIMARY KEY,
+        -- subcommand that made the change
+        command VARCHAR(50) NOT NULL,
+        -- user that ran the command
+        operator VARCHAR(100) NOT NULL,
+        -- what was changed, e.g. the previous and new version of a rewind
+        details JSON NOT NULL,
+        created_at TIMESTAMP NOT NULL DEFAULT NOW()
+    );
@@ -0,0 +1,17 @@
+-- Your SQL goes here
+-- Contracts processors track on top of the configured contract addresses, reloaded while they run
+CREATE TABLE
+    tracked_contracts (
+        contract_address VARCHAR(300) NOT NULL,
+        -- marketplace or contract_upgrade, the processors that track the contract
+        kind VARCHAR(50) NOT NULL,
+        -- aptos_labs or tradeport_v2 to only parse marketplace events with that parser, null tries every parser
+        parser_family VARCHAR(50),
+        -- first version the contract is tracked from
+        start_version BIGINT NOT NULL,
+        inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+        PRIMARY KEY (contract_address, kind),
+        CHECK (kind IN ('marketplace', 'contract_upgrade')),
+        CHECK (parser_family IN ('aptos_labs', 'tradeport_v2')),
+        CHECK (kind = 'marketplace' OR parser_family IS NULL)
+    );
@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+DROP VIEW v_open_collection_bids;
+DROP VIEW v_open_bids;
+DROP VIEW v_open_asks;
+DROP VIEW v_activities;
+DROP TABLE marketplaces;
+DROP TABLE activity_types;
+DROP TABLE order_types;
+DROP TABLE order_statuses;
+DROP TABLE payment_token_types;
+DROP TABLE nft_standards;
\ No newline at end of file
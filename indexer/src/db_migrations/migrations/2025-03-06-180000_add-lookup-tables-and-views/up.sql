@@ -0,0 +1,137 @@
+-- Your SQL goes here
+-- Labels of the Int4 enum columns, generated from the enums in utils/aptos_utils.rs. Changing an enum
+-- needs a migration with its LookupTable::upsert_sql, lookup_tables_tests fails until there is one
+CREATE TABLE
+    nft_standards (id INT PRIMARY KEY, name VARCHAR(50) NOT NULL UNIQUE);
+
+CREATE TABLE
+    payment_token_types (id INT PRIMARY KEY, name VARCHAR(50) NOT NULL UNIQUE);
+
+CREATE TABLE
+    order_statuses (id INT PRIMARY KEY, name VARCHAR(50) NOT NULL UNIQUE);
+
+CREATE TABLE
+    order_types (id INT PRIMARY KEY, name VARCHAR(50) NOT NULL UNIQUE);
+
+CREATE TABLE
+    activity_types (id INT PRIMARY KEY, name VARCHAR(50) NOT NULL UNIQUE);
+
+INSERT INTO
+    nft_standards (id, name)
+VALUES
+    (1, 'v1'),
+    (2, 'v2')
+ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name;
+
+INSERT INTO
+    payment_token_types (id, name)
+VALUES
+    (1, 'coin'),
+    (2, 'fungible_asset')
+ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name;
+
+INSERT INTO
+    order_statuses (id, name)
+VALUES
+    (1, 'open'),
+    (2, 'filled'),
+    (3, 'cancelled')
+ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name;
+
+INSERT INTO
+    order_types (id, name)
+VALUES
+    (1, 'fixed_price'),
+    (2, 'auction')
+ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name;
+
+INSERT INTO
+    activity_types (id, name)
+VALUES
+    (1, 'collection_bid_placed'),
+    (2, 'collection_bid_filled'),
+    (3, 'collection_bid_cancelled'),
+    (4, 'nft_bid_placed'),
+    (5, 'nft_bid_filled'),
+    (6, 'nft_bid_cancelled'),
+    (7, 'nft_ask_placed'),
+    (8, 'nft_ask_filled'),
+    (9, 'nft_ask_cancelled')
+ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name;
+
+-- Readable names of marketplace contracts, add a row for every new marketplace
+CREATE TABLE
+    marketplaces (
+        marketplace_addr VARCHAR(300) PRIMARY KEY,
+        name VARCHAR(100) NOT NULL
+    );
+
+INSERT INTO
+    marketplaces (marketplace_addr, name)
+VALUES
+    ('0x584b50b999c78ade62f8359c91b5165ff390338d45f8e55969a04e65d76258c9', 'wapal'),
+    ('0xe11c12ec495f3989c35e1c6a0af414451223305b579291fc8f3d9d0575a23c26', 'tradeport'),
+    ('0x465a0051e8535859d4794f0af24dbf35c5349bedadab26404b20b825035ee790', 'rarible');
+
+-- Views with the labels and marketplace names next to the values, for dashboards and ad hoc queries
+CREATE VIEW
+    v_activities AS
+SELECT
+    a.*,
+    at.name AS activity_type_name,
+    ns.name AS nft_standard_name,
+    ptt.name AS payment_token_type_name,
+    m.name AS marketplace_name
+FROM
+    activities a
+    LEFT JOIN activity_types at ON at.id = a.activity_type
+    LEFT JOIN nft_standards ns ON ns.id = a.nft_standard
+    LEFT JOIN payment_token_types ptt ON ptt.id = a.payment_token_type
+    LEFT JOIN marketplaces m ON m.marketplace_addr = a.marketplace_addr;
+
+CREATE VIEW
+    v_open_asks AS
+SELECT
+    a.*,
+    ot.name AS order_type_name,
+    ns.name AS nft_standard_name,
+    ptt.name AS payment_token_type_name,
+    m.name AS marketplace_name
+FROM
+    nft_asks a
+    LEFT JOIN order_types ot ON ot.id = a.order_type
+    LEFT JOIN nft_standards ns ON ns.id = a.nft_standard
+    LEFT JOIN payment_token_types ptt ON ptt.id = a.payment_token_type
+    LEFT JOIN marketplaces m ON m.marketplace_addr = a.marketplace_addr
+WHERE
+    a.order_status = 1;
+
+CREATE VIEW
+    v_open_bids AS
+SELECT
+    b.*,
+    ns.name AS nft_standard_name,
+    ptt.name AS payment_token_type_name,
+    m.name AS marketplace_name
+FROM
+    nft_bids b
+    LEFT JOIN nft_standards ns ON ns.id = b.nft_standard
+    LEFT JOIN payment_token_types ptt ON ptt.id = b.payment_token_type
+    LEFT JOIN marketplaces m ON m.marketplace_addr = b.marketplace_addr
+WHERE
+    b.order_status = 1;
+
+CREATE VIEW
+    v_open_collection_bids AS
+SELECT
+    b.*,
+    ns.name AS nft_standard_name,
+    ptt.name AS payment_token_type_name,
+    m.name AS marketplace_name
+FROM
+    collection_bids b
+    LEFT JOIN nft_standards ns ON ns.id = b.nft_standard
+    LEFT JOIN payment_token_types ptt ON ptt.id = b.payment_token_type
+    LEFT JOIN marketplaces m ON m.marketplace_addr = b.marketplace_addr
+WHERE
+    b.order_status = 1;
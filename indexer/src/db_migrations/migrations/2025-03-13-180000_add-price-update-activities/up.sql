@@ -0,0 +1,42 @@
+-- Your SQL goes here
+-- Price of the cancelled order of a price update, the price column holds the new one
+ALTER TABLE activities
+ADD COLUMN previous_price BIGINT;
+
+ALTER TABLE activities
+DROP CONSTRAINT activities_activity_type_check,
+ADD CONSTRAINT activities_activity_type_check CHECK (activity_type IN (1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11));
+
+INSERT INTO
+    activity_types (id, name)
+VALUES
+    (1, 'collection_bid_placed'),
+    (2, 'collection_bid_filled'),
+    (3, 'collection_bid_cancelled'),
+    (4, 'nft_bid_placed'),
+    (5, 'nft_bid_filled'),
+    (6, 'nft_bid_cancelled'),
+    (7, 'nft_ask_placed'),
+    (8, 'nft_ask_filled'),
+    (9, 'nft_ask_cancelled'),
+    (10, 'nft_ask_price_updated'),
+    (11, 'nft_bid_price_updated')
+ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name;
+
+-- a.* is expanded when a view is created, so v_activities is created again to include previous_price
+DROP VIEW v_activities;
+
+CREATE VIEW
+    v_activities AS
+SELECT
+    a.*,
+    at.name AS activity_type_name,
+    ns.name AS nft_standard_name,
+    ptt.name AS payment_token_type_name,
+    m.name AS marketplace_name
+FROM
+    activities a
+    LEFT JOIN activity_types at ON at.id = a.activity_type
+    LEFT JOIN nft_standards ns ON ns.id = a.nft_standard
+    LEFT JOIN payment_token_types ptt ON ptt.id = a.payment_token_type
+    LEFT JOIN marketplaces m ON m.marketplace_addr = a.marketplace_addr;
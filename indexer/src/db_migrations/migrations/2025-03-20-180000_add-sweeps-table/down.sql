@@ -0,0 +1,22 @@
+-- This file should undo anything in `up.sql`
+DROP VIEW v_activities;
+
+ALTER TABLE activities
+DROP COLUMN sweep_event_idx;
+
+DROP TABLE sweeps;
+
+CREATE VIEW
+    v_activities AS
+SELECT
+    a.*,
+    at.name AS activity_type_name,
+    ns.name AS nft_standard_name,
+    ptt.name AS payment_token_type_name,
+    m.name AS marketplace_name
+FROM
+    activities a
+    LEFT JOIN activity_types at ON at.id = a.activity_type
+    LEFT JOIN nft_standards ns ON ns.id = a.nft_standard
+    LEFT JOIN payment_token_types ptt ON ptt.id = a.payment_token_type
+    LEFT JOIN marketplaces m ON m.marketplace_addr = a.marketplace_addr;
\ No newline at end of file
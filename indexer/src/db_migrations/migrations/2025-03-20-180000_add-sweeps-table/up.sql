@@ -0,0 +1,49 @@
+-- Your SQL goes here
+-- Several NFTs of one collection bought by the same buyer in one transaction, derived from the
+-- nft_ask_filled activities of the transaction
+CREATE TABLE
+    sweeps (
+        txn_version BIGINT NOT NULL,
+        -- activity_event_idx of the first fill of the sweep
+        event_idx BIGINT NOT NULL,
+        buyer_addr VARCHAR(300) NOT NULL,
+        -- empty for v1 tokens, their sweeps are grouped by collection creator and name
+        collection_addr VARCHAR(300) NOT NULL,
+        collection_creator_addr VARCHAR(300) NOT NULL,
+        collection_name VARCHAR(300) NOT NULL,
+        nft_count BIGINT NOT NULL,
+        total_price BIGINT NOT NULL,
+        -- distinct marketplaces the NFTs were bought from, in address order
+        marketplace_addrs TEXT[] NOT NULL,
+        sweep_timestamp BIGINT NOT NULL,
+        PRIMARY KEY (txn_version, event_idx),
+        UNIQUE (
+            txn_version,
+            buyer_addr,
+            collection_addr,
+            collection_creator_addr,
+            collection_name
+        )
+    );
+
+-- event_idx of the sweep the fill is part of, the sweep is in the same transaction
+ALTER TABLE activities
+ADD COLUMN sweep_event_idx BIGINT;
+
+-- a.* is expanded when a view is created, so v_activities is created again to include sweep_event_idx
+DROP VIEW v_activities;
+
+CREATE VIEW
+    v_activities AS
+SELECT
+    a.*,
+    at.name AS activity_type_name,
+    ns.name AS nft_standard_name,
+    ptt.name AS payment_token_type_name,
+    m.name AS marketplace_name
+FROM
+    activities a
+    LEFT JOIN activity_types at ON at.id = a.activity_type
+    LEFT JOIN nft_standards ns ON ns.id = a.nft_standard
+    LEFT JOIN payment_token_types ptt ON ptt.id = a.payment_token_type
+    LEFT JOIN marketplaces m ON m.marketplace_addr = a.marketplace_addr;
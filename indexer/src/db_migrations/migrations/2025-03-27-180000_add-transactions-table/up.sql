@@ -0,0 +1,40 @@
+-- Your SQL goes here
+-- Transactions that emitted marketplace events, joined to activities on the version
+CREATE TABLE
+    transactions (
+        txn_version BIGINT PRIMARY KEY,
+        txn_hash VARCHAR(66) NOT NULL,
+        -- empty for transactions not sent by a user
+        sender VARCHAR(300) NOT NULL,
+        -- null if the payload isn't an entry function, e.g. a script
+        entry_function_id TEXT,
+        gas_used BIGINT NOT NULL,
+        gas_unit_price BIGINT NOT NULL,
+        -- gas_used * gas_unit_price, in octas
+        gas_fee BIGINT NOT NULL,
+        block_timestamp TIMESTAMP NOT NULL
+    );
+
+CREATE INDEX idx_transactions_sender ON transactions (sender);
+
+DROP VIEW v_activities;
+
+CREATE VIEW
+    v_activities AS
+SELECT
+    a.*,
+    at.name AS activity_type_name,
+    ns.name AS nft_standard_name,
+    ptt.name AS payment_token_type_name,
+    m.name AS marketplace_name,
+    t.txn_hash,
+    t.sender,
+    t.entry_function_id,
+    t.gas_fee
+FROM
+    activities a
+    LEFT JOIN activity_types at ON at.id = a.activity_type
+    LEFT JOIN nft_standards ns ON ns.id = a.nft_standard
+    LEFT JOIN payment_token_types ptt ON ptt.id = a.payment_token_type
+    LEFT JOIN marketplaces m ON m.marketplace_addr = a.marketplace_addr
+    LEFT JOIN transactions t ON t.txn_version = a.activity_tx_version;
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE tracked_contract_activations;
\ No newline at end of file
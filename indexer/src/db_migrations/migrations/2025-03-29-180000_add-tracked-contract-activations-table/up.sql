@@ -0,0 +1,12 @@
+-- Your SQL goes here
+-- First version each processor tracks a tracked contract from, recorded when it picks up the
+-- contract. Versions before it are left to a backfill.
+CREATE TABLE
+    tracked_contract_activations (
+        -- processor_status.processor of the processor
+        processor VARCHAR(50) NOT NULL,
+        contract_address VARCHAR(300) NOT NULL,
+        kind VARCHAR(50) NOT NULL,
+        activated_version BIGINT NOT NULL,
+        PRIMARY KEY (processor, contract_address, kind)
+    );
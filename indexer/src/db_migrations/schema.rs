@@ -29,6 +29,33 @@ diesel::table! {
         activity_tx_version -> Int8,
         activity_timestamp -> Int8,
         activity_event_idx -> Int8,
+        previous_price -> Nullable<Int8>,
+        sweep_event_idx -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    activity_types (id) {
+        id -> Int4,
+        #[max_length = 50]
+        name -> Varchar,
+    }
+}
+
+diesel::table! {
+    backfill_shards (shard_id) {
+        shard_id -> Int8,
+        #[max_length = 50]
+        backfill_name -> Varchar,
+        start_version -> Int8,
+        end_version -> Int8,
+        status -> Int4,
+        attempts -> Int4,
+        #[max_length = 100]
+        worker_id -> Nullable<Varchar>,
+        last_error -> Nullable<Text>,
+        claimed_at -> Nullable<Timestamp>,
+        last_updated -> Timestamp,
     }
 }
 
@@ -91,6 +118,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    marketplaces (marketplace_addr) {
+        #[max_length = 300]
+        marketplace_addr -> Varchar,
+        #[max_length = 100]
+        name -> Varchar,
+    }
+}
+
 diesel::table! {
     module_upgrade_history (module_addr, module_name, package_name, upgrade_number) {
         #[max_length = 300]
@@ -189,6 +225,42 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    nft_standards (id) {
+        id -> Int4,
+        #[max_length = 50]
+        name -> Varchar,
+    }
+}
+
+diesel::table! {
+    operator_audit_log (id) {
+        id -> Int8,
+        #[max_length = 50]
+        command -> Varchar,
+        #[max_length = 100]
+        operator -> Varchar,
+        details -> Json,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    order_statuses (id) {
+        id -> Int4,
+        #[max_length = 50]
+        name -> Varchar,
+    }
+}
+
+diesel::table! {
+    order_types (id) {
+        id -> Int4,
+        #[max_length = 50]
+        name -> Varchar,
+    }
+}
+
 diesel::table! {
     package_upgrade_history (package_addr, package_name, upgrade_number) {
         #[max_length = 300]
@@ -203,6 +275,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    payment_token_types (id) {
+        id -> Int4,
+        #[max_length = 50]
+        name -> Varchar,
+    }
+}
+
 diesel::table! {
     processor_status (processor) {
         #[max_length = 50]
@@ -213,16 +293,100 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    raw_marketplace_events (txn_version, event_idx) {
+        txn_version -> Int8,
+        event_idx -> Int8,
+        #[max_length = 300]
+        account_address -> Varchar,
+        type_str -> Text,
+        data -> Json,
+        block_timestamp -> Timestamp,
+    }
+}
+
+diesel::table! {
+    sweeps (txn_version, event_idx) {
+        txn_version -> Int8,
+        event_idx -> Int8,
+        #[max_length = 300]
+        buyer_addr -> Varchar,
+        #[max_length = 300]
+        collection_addr -> Varchar,
+        #[max_length = 300]
+        collection_creator_addr -> Varchar,
+        #[max_length = 300]
+        collection_name -> Varchar,
+        nft_count -> Int8,
+        total_price -> Int8,
+        marketplace_addrs -> Array<Nullable<Text>>,
+        sweep_timestamp -> Int8,
+    }
+}
+
+diesel::table! {
+    tracked_contract_activations (processor, contract_address, kind) {
+        #[max_length = 50]
+        processor -> Varchar,
+        #[max_length = 300]
+        contract_address -> Varchar,
+        #[max_length = 50]
+        kind -> Varchar,
+        activated_version -> Int8,
+    }
+}
+
+diesel::table! {
+    tracked_contracts (contract_address, kind) {
+        #[max_length = 300]
+        contract_address -> Varchar,
+        #[max_length = 50]
+        kind -> Varchar,
+        #[max_length = 50]
+        parser_family -> Nullable<Varchar>,
+        start_version -> Int8,
+        inserted_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    transactions (txn_version) {
+        txn_version -> Int8,
+        #[max_length = 66]
+        txn_hash -> Varchar,
+        #[max_length = 300]
+        sender -> Varchar,
+        entry_function_id -> Nullable<Text>,
+        gas_used -> Int8,
+        gas_unit_price -> Int8,
+        gas_fee -> Int8,
+        block_timestamp -> Timestamp,
+    }
+}
+
 diesel::joinable!(filled_collection_bids -> collection_bids (bid_obj_addr));
 
 diesel::allow_tables_to_appear_in_same_query!(
     activities,
+    activity_types,
+    backfill_shards,
     collection_bids,
     filled_collection_bids,
     ledger_infos,
+    marketplaces,
     module_upgrade_history,
     nft_asks,
     nft_bids,
+    nft_standards,
+    operator_audit_log,
+    order_statuses,
+    order_types,
     package_upgrade_history,
+    payment_token_types,
     processor_status,
+    raw_marketplace_events,
+    sweeps,
+    tracked_contract_activations,
+    tracked_contracts,
+    transactions,
 );
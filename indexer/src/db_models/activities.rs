@@ -26,4 +26,6 @@ pub struct Activity {
     pub activity_tx_version: i64,
     pub activity_event_idx: i64,
     pub activity_type: i32,
+    // Price before a price update, None for every other activity type
+    pub previous_price: Option<i64>,
 }
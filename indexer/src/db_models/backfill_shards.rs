@@ -0,0 +1,226 @@
+use diesel::{
+    dsl::{now, IntervalDsl},
+    BoolExpressionMethods, ExpressionMethods, Insertable, NullableExpressionMethods,
+    OptionalExtension, QueryDsl, Queryable, Selectable, SelectableHelper,
+};
+use diesel_async::{AsyncConnection, RunQueryDsl};
+
+use crate::{
+    schema::backfill_shards,
+    utils::{aptos_utils::BackfillShardStatus, database_utils::DbPoolConnection},
+};
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = backfill_shards)]
+/// A new shard of a backfill job, shards are created once and then claimed by workers
+pub struct NewBackfillShard {
+    pub backfill_name: String,
+    pub start_version: i64,
+    pub end_version: i64,
+    pub status: i32,
+}
+
+#[derive(Clone, Debug, Queryable, Selectable)]
+#[diesel(table_name = backfill_shards)]
+/// Database representation of a backfill shard
+pub struct BackfillShard {
+    pub shard_id: i64,
+    pub backfill_name: String,
+    pub start_version: i64,
+    pub end_version: i64,
+    pub status: i32,
+    pub attempts: i32,
+    pub worker_id: Option<String>,
+    pub last_error: Option<String>,
+    pub claimed_at: Option<chrono::NaiveDateTime>,
+    pub last_updated: chrono::NaiveDateTime,
+}
+
+impl BackfillShard {
+    /// Split [start_version, end_version] into shards of shard_size versions.
+    /// Shards that already exist are left untouched so every worker can call this on startup.
+    pub async fn create_shards(
+        backfill_name: &str,
+        start_version: u64,
+        end_version: u64,
+        shard_size: u64,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<usize> {
+        let shards = (start_version..=end_version)
+            .step_by(shard_size as usize)
+            .map(|shard_start| NewBackfillShard {
+                backfill_name: backfill_name.to_string(),
+                start_version: shard_start as i64,
+                end_version: (shard_start + shard_size - 1).min(end_version) as i64,
+                status: BackfillShardStatus::Pending as i32,
+            })
+            .collect::<Vec<_>>();
+        diesel::insert_into(backfill_shards::table)
+            .values(shards)
+            .on_conflict((
+                backfill_shards::backfill_name,
+                backfill_shards::start_version,
+            ))
+            .do_nothing()
+            .execute(conn)
+            .await
+    }
+
+    /// Claim the next pending shard, or an in progress shard whose worker stopped heartbeating.
+    /// The row lock with skip locked makes sure concurrent workers never claim the same shard.
+    /// A shard whose lease ran out max_attempts times is marked as failed instead, its workers
+    /// died without releasing it (e.g. out of memory).
+    pub async fn claim_next(
+        backfill_name: &str,
+        worker_id: &str,
+        lease_secs: u64,
+        max_attempts: i32,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Option<Self>> {
+        let backfill_name = backfill_name.to_string();
+        let worker_id = worker_id.to_string();
+        conn.transaction(|conn| {
+            Box::pin(async move {
+                diesel::update(
+                    backfill_shards::table
+                        .filter(backfill_shards::backfill_name.eq(&backfill_name))
+                        .filter(backfill_shards::status.eq(BackfillShardStatus::InProgress as i32))
+                        .filter(
+                            backfill_shards::last_updated.lt(now - (lease_secs as i64).seconds()),
+                        )
+                        .filter(backfill_shards::attempts.ge(max_attempts)),
+                )
+                .set((
+                    backfill_shards::status.eq(BackfillShardStatus::Failed as i32),
+                    backfill_shards::worker_id.eq(None::<String>),
+                    backfill_shards::last_error.eq(Some(format!(
+                        "Lease expired on each of {} attempts",
+                        max_attempts
+                    ))),
+                    backfill_shards::last_updated.eq(now),
+                ))
+                .execute(conn)
+                .await?;
+
+                let maybe_shard = backfill_shards::table
+                    .filter(backfill_shards::backfill_name.eq(&backfill_name))
+                    .filter(
+                        backfill_shards::status
+                            .eq(BackfillShardStatus::Pending as i32)
+                            .or(backfill_shards::status
+                                .eq(BackfillShardStatus::InProgress as i32)
+                                .and(
+                                    backfill_shards::last_updated
+                                        .lt(now - (lease_secs as i64).seconds()),
+                                )),
+                    )
+                    .order(backfill_shards::start_version.asc())
+                    .select(BackfillShard::as_select())
+                    .for_update()
+                    .skip_locked()
+                    .first::<Self>(conn)
+                    .await
+                    .optional()?;
+
+                match maybe_shard {
+                    Some(shard) => diesel::update(backfill_shards::table.find(shard.shard_id))
+                        .set((
+                            backfill_shards::status.eq(BackfillShardStatus::InProgress as i32),
+                            backfill_shards::attempts.eq(backfill_shards::attempts + 1),
+                            backfill_shards::worker_id.eq(Some(worker_id)),
+                            backfill_shards::claimed_at.eq(now.nullable()),
+                            backfill_shards::last_updated.eq(now),
+                        ))
+                        .returning(BackfillShard::as_returning())
+                        .get_result::<Self>(conn)
+                        .await
+                        .map(Some),
+                    None => Ok(None),
+                }
+            })
+        })
+        .await
+    }
+
+    /// Keep the claim alive while the shard is being processed.
+    pub async fn heartbeat(
+        shard_id: i64,
+        worker_id: &str,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<usize> {
+        diesel::update(
+            backfill_shards::table
+                .find(shard_id)
+                .filter(backfill_shards::worker_id.eq(worker_id)),
+        )
+        .set(backfill_shards::last_updated.eq(now))
+        .execute(conn)
+        .await
+    }
+
+    /// Mark the shard completed if the worker still holds it. Returns 0 if the worker lost its
+    /// lease, another worker claimed the shard since and processes it again.
+    pub async fn mark_completed(
+        shard_id: i64,
+        worker_id: &str,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<usize> {
+        diesel::update(
+            backfill_shards::table
+                .find(shard_id)
+                .filter(backfill_shards::worker_id.eq(worker_id)),
+        )
+        .set((
+            backfill_shards::status.eq(BackfillShardStatus::Completed as i32),
+            backfill_shards::worker_id.eq(None::<String>),
+            backfill_shards::last_error.eq(None::<String>),
+            backfill_shards::last_updated.eq(now),
+        ))
+        .execute(conn)
+        .await
+    }
+
+    /// Release the shard so it can be retried, or mark it failed once it ran out of attempts.
+    /// Like `mark_completed`, returns 0 if the worker that claimed it lost its lease.
+    pub async fn mark_failed(
+        &self,
+        error: String,
+        max_attempts: i32,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<usize> {
+        let status = if self.attempts >= max_attempts {
+            BackfillShardStatus::Failed
+        } else {
+            BackfillShardStatus::Pending
+        };
+        diesel::update(
+            backfill_shards::table
+                .find(self.shard_id)
+                .filter(backfill_shards::worker_id.eq(&self.worker_id)),
+        )
+        .set((
+            backfill_shards::status.eq(status as i32),
+            backfill_shards::worker_id.eq(None::<String>),
+            backfill_shards::last_error.eq(Some(error)),
+            backfill_shards::last_updated.eq(now),
+        ))
+        .execute(conn)
+        .await
+    }
+
+    /// Number of shards that are neither completed nor failed.
+    pub async fn count_remaining(
+        backfill_name: &str,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<i64> {
+        backfill_shards::table
+            .filter(backfill_shards::backfill_name.eq(backfill_name))
+            .filter(backfill_shards::status.eq_any(vec![
+                BackfillShardStatus::Pending as i32,
+                BackfillShardStatus::InProgress as i32,
+            ]))
+            .count()
+            .get_result(conn)
+            .await
+    }
+}
@@ -1,7 +1,7 @@
 use diesel::{Identifiable, Insertable, OptionalExtension, QueryDsl, Queryable};
-use diesel_async::RunQueryDsl;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
 
-use crate::{schema::ledger_infos, utils::database_utils::DbPoolConnection};
+use crate::schema::ledger_infos;
 
 #[derive(Debug, Identifiable, Insertable, Queryable)]
 #[diesel(table_name = ledger_infos)]
@@ -11,7 +11,7 @@ pub struct LedgerInfo {
 }
 
 impl LedgerInfo {
-    pub async fn get(conn: &mut DbPoolConnection<'_>) -> diesel::QueryResult<Option<Self>> {
+    pub async fn get(conn: &mut AsyncPgConnection) -> diesel::QueryResult<Option<Self>> {
         ledger_infos::table
             .select(ledger_infos::all_columns)
             .first::<Self>(conn)
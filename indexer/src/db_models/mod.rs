@@ -1,9 +1,16 @@
 pub mod activities;
+pub mod backfill_shards;
 pub mod collection_bids;
 pub mod filled_collection_bids;
 pub mod ledger_info;
 pub mod module_upgrade;
 pub mod nft_asks;
 pub mod nft_bids;
+pub mod operator_audit_log;
 pub mod package_upgrade;
 pub mod processor_status;
+pub mod raw_marketplace_events;
+pub mod sweeps;
+pub mod tracked_contract_activations;
+pub mod tracked_contracts;
+pub mod transactions;
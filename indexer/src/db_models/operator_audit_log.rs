@@ -0,0 +1,33 @@
+use diesel::Insertable;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use serde_json::Value;
+
+use crate::schema::operator_audit_log;
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = operator_audit_log)]
+/// A change made by an operator command, written in the same transaction as the change
+pub struct NewOperatorAuditLog {
+    pub command: String,
+    pub operator: String,
+    pub details: Value,
+}
+
+impl NewOperatorAuditLog {
+    /// Entry for the user running the process, taken from $USER.
+    pub fn new(command: &str, details: Value) -> Self {
+        Self {
+            command: command.to_string(),
+            operator: std::env::var("USER").unwrap_or_else(|_| "unknown".to_string()),
+            details,
+        }
+    }
+
+    pub async fn insert(self, conn: &mut AsyncPgConnection) -> diesel::QueryResult<()> {
+        diesel::insert_into(operator_audit_log::table)
+            .values(self)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+}
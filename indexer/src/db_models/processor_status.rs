@@ -1,9 +1,9 @@
 use diesel::{AsChangeset, ExpressionMethods, Insertable, OptionalExtension, QueryDsl, Queryable};
-use diesel_async::RunQueryDsl;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
 
 use crate::{schema::processor_status, utils::database_utils::DbPoolConnection};
 
-#[derive(AsChangeset, Debug, Insertable)]
+#[derive(AsChangeset, Clone, Debug, Insertable)]
 #[diesel(table_name = processor_status)]
 /// Only tracking the latest version successfully processed
 pub struct ProcessorStatus {
@@ -33,4 +33,12 @@ impl ProcessorStatusQuery {
             .await
             .optional()
     }
+
+    /// Every processor in processor_status, by name.
+    pub async fn get_all(conn: &mut AsyncPgConnection) -> diesel::QueryResult<Vec<Self>> {
+        processor_status::table
+            .order(processor_status::processor)
+            .load::<Self>(conn)
+            .await
+    }
 }
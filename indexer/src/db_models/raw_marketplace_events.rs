@@ -0,0 +1,67 @@
+use aptos_indexer_processor_sdk::{
+    aptos_protos::transaction::v1::{Event as EventPB, EventKey},
+    utils::convert::standardize_address,
+};
+use diesel::{AsChangeset, Insertable, Queryable, Selectable};
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::raw_marketplace_events;
+
+#[derive(
+    AsChangeset, Clone, Debug, Deserialize, FieldCount, Insertable, Queryable, Selectable, Serialize,
+)]
+#[diesel(table_name = raw_marketplace_events)]
+/// Database representation of a raw event emitted by a tracked marketplace contract
+pub struct RawMarketplaceEvent {
+    pub txn_version: i64,
+    pub event_idx: i64,
+    pub account_address: String,
+    pub type_str: String,
+    pub data: serde_json::Value,
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl RawMarketplaceEvent {
+    pub fn from_event(
+        event: &EventPB,
+        txn_version: i64,
+        event_idx: i64,
+        block_timestamp: chrono::NaiveDateTime,
+    ) -> Self {
+        Self {
+            txn_version,
+            event_idx,
+            account_address: standardize_address(
+                event
+                    .key
+                    .as_ref()
+                    .map(|key| key.account_address.as_str())
+                    .unwrap_or_default(),
+            ),
+            type_str: event.type_str.clone(),
+            data: serde_json::from_str(event.data.as_str()).unwrap_or_else(|_| {
+                panic!(
+                    "Failed to parse event data at version {} event {}: {}",
+                    txn_version,
+                    event_idx,
+                    event.data.as_str()
+                )
+            }),
+            block_timestamp,
+        }
+    }
+
+    /// Rebuild the event with the fields the event parsers read
+    pub fn to_event(&self) -> EventPB {
+        EventPB {
+            key: Some(EventKey {
+                account_address: self.account_address.clone(),
+                ..Default::default()
+            }),
+            type_str: self.type_str.clone(),
+            data: self.data.to_string(),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use diesel::{Queryable, Selectable};
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::sweeps;
+
+#[derive(Clone, Debug, Deserialize, FieldCount, Queryable, Selectable, Serialize)]
+#[diesel(table_name = sweeps)]
+/// Database representation of a sweep, several NFTs of a collection bought by the same buyer in
+/// one transaction. Derived from the activities by the sweep storer.
+pub struct Sweep {
+    pub txn_version: i64,
+    pub event_idx: i64,
+    pub buyer_addr: String,
+    // Empty for v1 tokens, which are grouped by creator and name
+    pub collection_addr: String,
+    pub collection_creator_addr: String,
+    pub collection_name: String,
+    pub nft_count: i64,
+    pub total_price: i64,
+    pub marketplace_addrs: Vec<Option<String>>,
+    pub sweep_timestamp: i64,
+}
@@ -0,0 +1,79 @@
+use diesel::{
+    dsl::sql, sql_types::BigInt, ExpressionMethods, Insertable, OptionalExtension, QueryDsl,
+    Queryable, Selectable,
+};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::schema::tracked_contract_activations;
+
+#[derive(Clone, Debug, Insertable, Queryable, Selectable)]
+#[diesel(table_name = tracked_contract_activations)]
+/// The first version a processor tracks a tracked contract from
+pub struct TrackedContractActivation {
+    pub processor: String,
+    pub contract_address: String,
+    pub kind: String,
+    pub activated_version: i64,
+}
+
+impl TrackedContractActivation {
+    /// Record the activations, keeping the later version if a contract is recorded again. Every
+    /// step that filters by the tracked contracts records when it picks them up, the processor
+    /// only tracks a contract once the last of them did.
+    pub async fn record(
+        activations: Vec<Self>,
+        conn: &mut AsyncPgConnection,
+    ) -> diesel::QueryResult<()> {
+        diesel::insert_into(tracked_contract_activations::table)
+            .values(activations)
+            .on_conflict((
+                tracked_contract_activations::processor,
+                tracked_contract_activations::contract_address,
+                tracked_contract_activations::kind,
+            ))
+            .do_update()
+            .set(
+                tracked_contract_activations::activated_version.eq(sql::<BigInt>(
+                    "GREATEST(tracked_contract_activations.activated_version, \
+                     excluded.activated_version)",
+                )),
+            )
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// The version the processor tracks the contract from, None if it didn't pick it up yet.
+    pub async fn get(
+        processor: &str,
+        contract_address: &str,
+        kind: &str,
+        conn: &mut AsyncPgConnection,
+    ) -> diesel::QueryResult<Option<i64>> {
+        tracked_contract_activations::table
+            .filter(tracked_contract_activations::processor.eq(processor))
+            .filter(tracked_contract_activations::contract_address.eq(contract_address))
+            .filter(tracked_contract_activations::kind.eq(kind))
+            .select(tracked_contract_activations::activated_version)
+            .first(conn)
+            .await
+            .optional()
+    }
+
+    /// Forget every processor's activation of the contract, so they record it again when they
+    /// pick up a change to it.
+    pub async fn delete_for_contract(
+        contract_address: &str,
+        kind: &str,
+        conn: &mut AsyncPgConnection,
+    ) -> diesel::QueryResult<()> {
+        diesel::delete(
+            tracked_contract_activations::table
+                .filter(tracked_contract_activations::contract_address.eq(contract_address))
+                .filter(tracked_contract_activations::kind.eq(kind)),
+        )
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
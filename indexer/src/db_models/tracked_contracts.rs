@@ -0,0 +1,60 @@
+use diesel::{
+    upsert::excluded, ExpressionMethods, Insertable, QueryDsl, Queryable, Selectable,
+    SelectableHelper,
+};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::schema::tracked_contracts;
+
+#[derive(Clone, Debug, Insertable, Queryable, Selectable)]
+#[diesel(table_name = tracked_contracts)]
+/// A contract processors track on top of their configured contract addresses
+pub struct TrackedContract {
+    pub contract_address: String,
+    pub kind: String,
+    pub parser_family: Option<String>,
+    pub start_version: i64,
+}
+
+impl TrackedContract {
+    pub async fn load_all(conn: &mut AsyncPgConnection) -> diesel::QueryResult<Vec<Self>> {
+        tracked_contracts::table
+            .select(Self::as_select())
+            .order((tracked_contracts::kind, tracked_contracts::contract_address))
+            .load(conn)
+            .await
+    }
+
+    /// Track the contract, or change its parser family and start version if it's tracked
+    /// already.
+    pub async fn upsert(self, conn: &mut AsyncPgConnection) -> diesel::QueryResult<()> {
+        diesel::insert_into(tracked_contracts::table)
+            .values(self)
+            .on_conflict((tracked_contracts::contract_address, tracked_contracts::kind))
+            .do_update()
+            .set((
+                tracked_contracts::parser_family.eq(excluded(tracked_contracts::parser_family)),
+                tracked_contracts::start_version.eq(excluded(tracked_contracts::start_version)),
+            ))
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Stop tracking the contract, returns the removed row if it was tracked.
+    pub async fn delete(
+        contract_address: &str,
+        kind: &str,
+        conn: &mut AsyncPgConnection,
+    ) -> diesel::QueryResult<Option<Self>> {
+        let mut removed = diesel::delete(
+            tracked_contracts::table
+                .filter(tracked_contracts::contract_address.eq(contract_address))
+                .filter(tracked_contracts::kind.eq(kind)),
+        )
+        .returning(Self::as_returning())
+        .get_results(conn)
+        .await?;
+        Ok(removed.pop())
+    }
+}
@@ -0,0 +1,83 @@
+use aptos_indexer_processor_sdk::{
+    aptos_protos::transaction::v1::{
+        multisig_transaction_payload, transaction::TxnData, transaction_payload, Transaction,
+        TransactionPayload,
+    },
+    utils::{convert::standardize_address, time::parse_timestamp},
+};
+use diesel::{AsChangeset, Insertable, Queryable, Selectable};
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::transactions;
+
+#[derive(
+    AsChangeset, Clone, Debug, Deserialize, FieldCount, Insertable, Queryable, Selectable, Serialize,
+)]
+#[diesel(table_name = transactions)]
+/// Database representation of a transaction that emitted marketplace events
+pub struct MarketplaceTransaction {
+    pub txn_version: i64,
+    pub txn_hash: String,
+    // Empty for transactions not sent by a user
+    pub sender: String,
+    // None if the payload isn't an entry function
+    pub entry_function_id: Option<String>,
+    pub gas_used: i64,
+    pub gas_unit_price: i64,
+    // gas_used * gas_unit_price, in octas
+    pub gas_fee: i64,
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl MarketplaceTransaction {
+    pub fn from_transaction(txn: &Transaction) -> Self {
+        let txn_version = txn.version as i64;
+        let (hash, gas_used) = txn
+            .info
+            .as_ref()
+            .map(|info| (info.hash.as_slice(), info.gas_used))
+            .unwrap_or_default();
+        let request = match txn.txn_data.as_ref() {
+            Some(TxnData::User(user_txn)) => user_txn.request.as_ref(),
+            _ => None,
+        };
+        let gas_unit_price = request
+            .map(|request| request.gas_unit_price)
+            .unwrap_or_default();
+        Self {
+            txn_version,
+            txn_hash: format!("0x{}", hex::encode(hash)),
+            sender: request
+                .map(|request| standardize_address(&request.sender))
+                .unwrap_or_default(),
+            entry_function_id: request
+                .and_then(|request| request.payload.as_ref())
+                .and_then(entry_function_id),
+            gas_used: gas_used as i64,
+            gas_unit_price: gas_unit_price as i64,
+            gas_fee: gas_used.saturating_mul(gas_unit_price) as i64,
+            block_timestamp: txn
+                .timestamp
+                .as_ref()
+                .map(|t| parse_timestamp(t, txn_version).naive_utc())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Id of the entry function called by the payload, or by the transaction a multisig executes.
+fn entry_function_id(payload: &TransactionPayload) -> Option<String> {
+    let entry_function = match payload.payload.as_ref()? {
+        transaction_payload::Payload::EntryFunctionPayload(entry_function) => entry_function,
+        transaction_payload::Payload::MultisigPayload(multisig) => {
+            match multisig.transaction_payload.as_ref()?.payload.as_ref()? {
+                multisig_transaction_payload::Payload::EntryFunctionPayload(entry_function) => {
+                    entry_function
+                }
+            }
+        }
+        _ => return None,
+    };
+    Some(entry_function.entry_function_id_str.clone()).filter(|id| !id.is_empty())
+}
@@ -1,6 +1,7 @@
 //! This contains the health server, a basic server that for now always returns 200.
 //! This is necessary to run the processor in Cloud Run, which expects to be able to
-//! query a HTTP server to check for liveness.
+//! query a HTTP server to check for liveness. `/role` reports whether this replica is the
+//! leader or a standby, see `leader_election`.
 
 use anyhow::{Context, Result};
 use poem::{
@@ -9,6 +10,8 @@ use poem::{
 use serde::{Deserialize, Serialize};
 use std::net::{Ipv4Addr, SocketAddrV4};
 
+use crate::utils::leader_election;
+
 /// This configures the health server.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(default)]
@@ -27,7 +30,10 @@ impl Default for HealthServerConfig {
 pub async fn run(config: HealthServerConfig) -> Result<()> {
     tracing::info!("Health server starting at {}", config.listen_address);
     let cors = Cors::new().allow_methods(vec![Method::GET, Method::POST]);
-    let route = Route::new().nest("/", get(root)).with(cors);
+    let route = Route::new()
+        .at("/role", get(role))
+        .nest("/", get(root))
+        .with(cors);
     Server::new(TcpListener::bind(config.listen_address))
         .name("health-server")
         .run(route)
@@ -39,3 +45,8 @@ pub async fn run(config: HealthServerConfig) -> Result<()> {
 async fn root() -> String {
     "Hello from the root!!".to_string()
 }
+
+#[handler]
+async fn role() -> String {
+    leader_election::role().to_string()
+}
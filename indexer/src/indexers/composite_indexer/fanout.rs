@@ -0,0 +1,156 @@
+use aptos_indexer_processor_sdk::{
+    aptos_protos::transaction::v1::Transaction,
+    traits::{
+        async_step::AsyncRunType, AsyncStep, NamedStep, PollableAsyncRunType, PollableAsyncStep,
+        Processable,
+    },
+    types::transaction_context::TransactionContext,
+    utils::errors::ProcessorError,
+};
+use async_trait::async_trait;
+use prost::Message;
+use tokio::sync::mpsc;
+
+pub type TransactionBatch = TransactionContext<Vec<Transaction>>;
+
+/// FanoutStep is the last step of the composite indexer's stream pipeline, it sends every batch
+/// to the FanoutSource of each sub-processor. A full sub-processor channel holds back the stream,
+/// so the slowest sub-processor sets the pace.
+pub struct FanoutStep
+where
+    Self: Sized + Send + 'static,
+{
+    // Sub-processor name and the sender to its FanoutSource
+    senders: Vec<(String, mpsc::Sender<TransactionBatch>)>,
+}
+
+impl FanoutStep {
+    pub fn new(senders: Vec<(String, mpsc::Sender<TransactionBatch>)>) -> Self {
+        Self { senders }
+    }
+}
+
+impl AsyncStep for FanoutStep {}
+
+impl NamedStep for FanoutStep {
+    fn name(&self) -> String {
+        "FanoutStep".to_string()
+    }
+}
+
+#[async_trait]
+impl Processable for FanoutStep {
+    type Input = Vec<Transaction>;
+    type Output = ();
+    type RunType = AsyncRunType;
+
+    async fn process(
+        &mut self,
+        batch: TransactionBatch,
+    ) -> Result<Option<TransactionContext<()>>, ProcessorError> {
+        let metadata = batch.metadata.clone();
+        let mut batch = Some(batch);
+        let num_senders = self.senders.len();
+        for (i, (name, sender)) in self.senders.iter().enumerate() {
+            // The last sub-processor gets the batch itself instead of a copy
+            let sub_processor_batch = if i + 1 == num_senders {
+                batch.take()
+            } else {
+                batch.clone()
+            };
+            if let Some(sub_processor_batch) = sub_processor_batch {
+                sender.send(sub_processor_batch).await.map_err(|_| {
+                    ProcessorError::ProcessError {
+                        message: format!(
+                            "Sub-processor {} stopped before versions [{}, {}]",
+                            name, metadata.start_version, metadata.end_version
+                        ),
+                    }
+                })?;
+            }
+        }
+        Ok(Some(TransactionContext { data: (), metadata }))
+    }
+}
+
+/// FanoutSource reads the batches a FanoutStep sends to one sub-processor, in place of the
+/// transaction stream. Versions before the sub-processor's starting version are left out, so it
+/// resumes where it stopped even when the stream starts earlier for another sub-processor.
+pub struct FanoutSource
+where
+    Self: Sized + Send + 'static,
+{
+    receiver: mpsc::Receiver<TransactionBatch>,
+    starting_version: u64,
+    finished: bool,
+}
+
+impl FanoutSource {
+    pub fn new(receiver: mpsc::Receiver<TransactionBatch>, starting_version: u64) -> Self {
+        Self {
+            receiver,
+            starting_version,
+            finished: false,
+        }
+    }
+
+    /// The part of the batch from the starting version on, None if it's all before it.
+    fn skip_processed_versions(&self, mut batch: TransactionBatch) -> Option<TransactionBatch> {
+        if batch.metadata.end_version < self.starting_version {
+            return None;
+        }
+        if batch.metadata.start_version < self.starting_version {
+            batch
+                .data
+                .retain(|txn| txn.version >= self.starting_version);
+            batch.metadata.start_version = self.starting_version;
+            batch.metadata.start_transaction_timestamp =
+                batch.data.first().and_then(|txn| txn.timestamp.clone());
+            batch.metadata.total_size_in_bytes =
+                batch.data.iter().map(|txn| txn.encoded_len() as u64).sum();
+        }
+        Some(batch)
+    }
+}
+
+impl NamedStep for FanoutSource {
+    fn name(&self) -> String {
+        "FanoutSource".to_string()
+    }
+}
+
+#[async_trait]
+impl Processable for FanoutSource {
+    type Input = ();
+    type Output = Vec<Transaction>;
+    type RunType = PollableAsyncRunType;
+
+    async fn process(
+        &mut self,
+        _item: TransactionContext<()>,
+    ) -> Result<Option<TransactionBatch>, ProcessorError> {
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl PollableAsyncStep for FanoutSource {
+    fn poll_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(0)
+    }
+
+    async fn poll(&mut self) -> Result<Option<Vec<TransactionBatch>>, ProcessorError> {
+        match self.receiver.recv().await {
+            Some(batch) => Ok(self.skip_processed_versions(batch).map(|batch| vec![batch])),
+            // The stream pipeline ended
+            None => {
+                self.finished = true;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn should_continue_polling(&mut self) -> bool {
+        !self.finished
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::Result;
+use aptos_indexer_processor_sdk::{builder::ProcessorBuilder, traits::IntoRunnableStep};
+use futures_util::future::{try_join, try_join_all, BoxFuture};
+use tokio::sync::mpsc;
+
+use super::fanout::{FanoutSource, FanoutStep};
+use crate::{
+    config::{
+        indexer_processor_config::{CustomConfig, IndexerProcessorConfig, SubProcessorConfig},
+        processor_config::ProcessorConfig,
+    },
+    indexers::{
+        contract_upgrade_indexer::processor::ContractUpgradeProcessor,
+        marketplace_indexer::processor::MarketplaceProcessor,
+    },
+    utils::{
+        chain_id::check_or_update_chain_id,
+        database_connection::new_db_pool,
+        database_utils::ArcDbPool,
+        starting_version::get_starting_version,
+        tracked_contracts::{TrackedContractKind, TrackedContracts},
+        transaction_filter::{TransactionFilter, TransactionFilterStep},
+        transaction_source::TransactionSourceStep,
+    },
+};
+
+/// Whether a processor can run in the composite indexer, which needs it to read the shared
+/// transaction stream from its own starting version on.
+pub fn is_supported_sub_processor(processor_config: &ProcessorConfig) -> bool {
+    match processor_config {
+        ProcessorConfig::ContractUpgradeIndexer
+        | ProcessorConfig::RaribleMarketplaceIndexer
+        | ProcessorConfig::WapalMarketplaceIndexer
+        | ProcessorConfig::TradeportMarketplaceIndexer
+        | ProcessorConfig::BackfillMarketplaceIndexer => true,
+        ProcessorConfig::ShardedBackfillMarketplaceIndexer | ProcessorConfig::CompositeIndexer => {
+            false
+        }
+    }
+}
+
+/// CompositeProcessor runs several processors from one transaction stream, so they don't each
+/// read the same versions. Every sub-processor keeps its own pipeline and processor_status row.
+pub struct CompositeProcessor {
+    pub config: IndexerProcessorConfig,
+    pub db_pool: ArcDbPool,
+}
+
+impl CompositeProcessor {
+    pub async fn new(config: IndexerProcessorConfig) -> Result<Self> {
+        let conn_pool = new_db_pool(
+            &config.db_config.connection_string()?,
+            config.db_config.db_pool_size,
+        )
+        .await;
+
+        Ok(Self {
+            config,
+            db_pool: conn_pool,
+        })
+    }
+
+    pub async fn run_processor(self) -> Result<()> {
+        let sub_processors = match &self.config.custom_config {
+            CustomConfig::CompositeIndexer(sub_processors) => sub_processors.clone(),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Invalid custom config, expected composite_indexer, run validate-config to check the config"
+                ));
+            }
+        };
+        anyhow::ensure!(
+            !sub_processors.is_empty(),
+            "composite_indexer has no processors to run"
+        );
+        if let Some(sub_processor) = sub_processors
+            .iter()
+            .find(|sub_processor| !is_supported_sub_processor(&sub_processor.processor_config))
+        {
+            return Err(anyhow::anyhow!(
+                "{} can't run in the composite indexer",
+                sub_processor.processor_config.name()
+            ));
+        }
+
+        // Check and update the ledger chain id to ensure we're indexing the correct chain
+        if let Some(chain_id) = TransactionSourceStep::get_chain_id(&self.config).await? {
+            check_or_update_chain_id(chain_id as i64, self.db_pool.clone()).await?;
+        }
+
+        // Each sub-processor starts from its own processor_status row, the stream from the
+        // earliest of them
+        let mut sub_processor_configs = vec![];
+        for sub_processor in &sub_processors {
+            let config = self.sub_processor_config(sub_processor);
+            let starting_version = get_starting_version(&config, self.db_pool.clone()).await?;
+            tracing::info!(
+                "Starting {} with starting version: {:?}",
+                config.processor_config.name(),
+                starting_version
+            );
+            sub_processor_configs.push((config, starting_version));
+        }
+        let starting_version = sub_processor_configs
+            .iter()
+            .map(|(_, starting_version)| *starting_version)
+            .min()
+            .unwrap_or_default();
+        tracing::info!(
+            "Starting {} with starting version: {:?}",
+            self.config.processor_config.name(),
+            starting_version
+        );
+
+        let channel_sizes = &self.config.pipeline_config.channel_sizes;
+        let mut senders = vec![];
+        let mut sub_pipelines = vec![];
+        for (config, sub_processor_starting_version) in sub_processor_configs {
+            let (sender, receiver) = mpsc::channel(channel_sizes.extractor);
+            senders.push((config.processor_config.name().to_string(), sender));
+            let transaction_stream = TransactionSourceStep::Fanout(FanoutSource::new(
+                receiver,
+                sub_processor_starting_version,
+            ));
+            sub_pipelines.push(run_sub_processor(
+                config,
+                self.db_pool.clone(),
+                transaction_stream,
+                sub_processor_starting_version,
+            ));
+        }
+
+        let transaction_stream = TransactionSourceStep::new(
+            &self.config,
+            starting_version,
+            self.config.transaction_stream_config.request_ending_version,
+        )
+        .await?;
+        // Only what some sub-processor needs is sent on, each of them filters again for its own.
+        // The sub-processors only see a tracked contract once this filter picked it up.
+        let marketplace_sub_processors = sub_processors
+            .iter()
+            .filter(|sub_processor| {
+                matches!(
+                    sub_processor.custom_config,
+                    CustomConfig::MarketplaceIndexer(_)
+                )
+            })
+            .map(|sub_processor| {
+                (
+                    sub_processor.processor_config.name().to_string(),
+                    TrackedContractKind::Marketplace,
+                )
+            })
+            .collect();
+        let transaction_filter =
+            TransactionFilterStep::new(TransactionFilter::new(&self.config.custom_config))
+                .with_tracked_contracts(
+                    TrackedContracts::watch_if_configured(&self.config, self.db_pool.clone())
+                        .await?,
+                )
+                .with_activations(self.db_pool.clone(), marketplace_sub_processors);
+        let (_, buffer_receiver) = ProcessorBuilder::new_with_inputless_first_step(
+            transaction_stream.into_runnable_step(),
+        )
+        .connect_to(
+            transaction_filter.into_runnable_step(),
+            channel_sizes.extractor,
+        )
+        .connect_to(
+            FanoutStep::new(senders).into_runnable_step(),
+            channel_sizes.extractor,
+        )
+        .end_and_return_output_receiver(channel_sizes.output);
+
+        // The sub-processors end once the stream ends and drops the fan-out step
+        let stream = async {
+            while buffer_receiver.recv().await.is_ok() {}
+            Ok(())
+        };
+        try_join(stream, try_join_all(sub_pipelines)).await?;
+        Ok(())
+    }
+
+    /// Config of a sub-processor, the composite indexer's config with the sub-processor's type
+    /// and contract addresses.
+    fn sub_processor_config(&self, sub_processor: &SubProcessorConfig) -> IndexerProcessorConfig {
+        IndexerProcessorConfig {
+            processor_config: sub_processor.processor_config.clone(),
+            custom_config: sub_processor.custom_config.clone(),
+            ..self.config.clone()
+        }
+    }
+}
+
+fn run_sub_processor(
+    config: IndexerProcessorConfig,
+    db_pool: ArcDbPool,
+    transaction_stream: TransactionSourceStep,
+    starting_version: u64,
+) -> BoxFuture<'static, Result<()>> {
+    Box::pin(async move {
+        match config.processor_config {
+            ProcessorConfig::ContractUpgradeIndexer => {
+                let processor = ContractUpgradeProcessor { config, db_pool };
+                processor
+                    .run_pipeline(transaction_stream, starting_version)
+                    .await
+            }
+            ProcessorConfig::RaribleMarketplaceIndexer
+            | ProcessorConfig::WapalMarketplaceIndexer
+            | ProcessorConfig::TradeportMarketplaceIndexer
+            | ProcessorConfig::BackfillMarketplaceIndexer => {
+                let tracker_name = config.processor_config.name().to_string();
+                let processor = MarketplaceProcessor { config, db_pool };
+                processor
+                    .run_pipeline_from(transaction_stream, starting_version, tracker_name)
+                    .await
+            }
+            ProcessorConfig::ShardedBackfillMarketplaceIndexer
+            | ProcessorConfig::CompositeIndexer => Err(anyhow::anyhow!(
+                "{} can't run in the composite indexer",
+                config.processor_config.name()
+            )),
+        }
+    })
+}
@@ -11,9 +11,12 @@ use aptos_indexer_processor_sdk::{
 use async_trait::async_trait;
 use rayon::prelude::*;
 
-use crate::db_models::{
-    module_upgrade::ModuleUpgrade,
-    package_upgrade::{PackageUpgrade, PackageUpgradeChangeOnChain},
+use crate::{
+    db_models::{
+        module_upgrade::ModuleUpgrade,
+        package_upgrade::{PackageUpgrade, PackageUpgradeChangeOnChain},
+    },
+    utils::tracked_contracts::{TrackedContractKind, TrackedContracts},
 };
 
 /// Extractor is a step that extracts events and their metadata from transactions.
@@ -22,14 +25,26 @@ where
     Self: Sized + Send + 'static,
 {
     contract_addresses: AHashSet<String>,
+    // Contracts of tracked_contracts, tracked on top of the configured ones
+    tracked_contracts: Option<TrackedContracts>,
 }
 
 impl Extractor {
     pub fn new(contract_addresses: Vec<String>) -> Self {
         Self {
-            contract_addresses: contract_addresses.into_iter().collect(),
+            // Event addresses are compared padded and lowercase, so the configured ones must be too
+            contract_addresses: contract_addresses
+                .iter()
+                .map(|addr| standardize_address(&addr.to_lowercase()))
+                .collect(),
+            tracked_contracts: None,
         }
     }
+
+    pub fn with_tracked_contracts(mut self, tracked_contracts: Option<TrackedContracts>) -> Self {
+        self.tracked_contracts = tracked_contracts;
+        self
+    }
 }
 
 impl AsyncStep for Extractor {}
@@ -50,6 +65,17 @@ impl Processable for Extractor {
         &mut self,
         item: TransactionContext<Vec<Transaction>>,
     ) -> Result<Option<TransactionContext<TransactionContextData>>, ProcessorError> {
+        let tracked_contract_addresses = self.tracked_contracts.as_mut().map(|tracked_contracts| {
+            tracked_contracts.current().contract_addresses_by_version(
+                TrackedContractKind::ContractUpgrade,
+                &self.contract_addresses,
+                item.metadata.start_version,
+                item.metadata.end_version,
+                |contract_addresses, contract| {
+                    contract_addresses.insert(contract.contract_address.clone());
+                },
+            )
+        });
         let results: Vec<(Vec<_>, Vec<ContractUpgradeChange>)> = item
             .data
             .par_iter()
@@ -72,8 +98,12 @@ impl Processable for Extractor {
                     }
                 };
 
+                let contract_addresses = match &tracked_contract_addresses {
+                    Some(tracked_contract_addresses) => tracked_contract_addresses.at(txn.version),
+                    None => &self.contract_addresses,
+                };
                 let txn_changes = ContractUpgradeChange::from_changes(
-                    &self.contract_addresses,
+                    contract_addresses,
                     txn_version,
                     txn_info.changes.as_slice(),
                 );
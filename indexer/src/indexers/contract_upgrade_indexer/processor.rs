@@ -1,18 +1,20 @@
 use anyhow::Result;
-use aptos_indexer_processor_sdk::{
-    aptos_indexer_transaction_stream::{TransactionStream, TransactionStreamConfig},
-    builder::ProcessorBuilder,
-    common_steps::TransactionStreamStep,
-    traits::IntoRunnableStep,
-};
+use aptos_indexer_processor_sdk::{builder::ProcessorBuilder, traits::IntoRunnableStep};
+use std::time::Duration;
 
 use super::{extractor::Extractor, storer::Storer};
 use crate::{
     config::indexer_processor_config::{CustomConfig, IndexerProcessorConfig},
     utils::{
-        chain_id::check_or_update_chain_id, database_connection::new_db_pool,
-        database_utils::ArcDbPool, latest_processed_version_tracker::LatestVersionProcessedTracker,
+        chain_id::check_or_update_chain_id,
+        database_connection::new_db_pool,
+        database_utils::ArcDbPool,
+        latest_processed_version_tracker::LatestVersionProcessedTracker,
+        shutdown::is_shutdown_requested,
         starting_version::get_starting_version,
+        tracked_contracts::TrackedContracts,
+        transaction_filter::{TransactionFilter, TransactionFilterStep},
+        transaction_source::TransactionSourceStep,
     },
 };
 
@@ -24,7 +26,7 @@ pub struct ContractUpgradeProcessor {
 impl ContractUpgradeProcessor {
     pub async fn new(config: IndexerProcessorConfig) -> Result<Self> {
         let conn_pool = new_db_pool(
-            &config.db_config.postgres_connection_string,
+            &config.db_config.connection_string()?,
             config.db_config.db_pool_size,
         )
         .await;
@@ -46,40 +48,73 @@ impl ContractUpgradeProcessor {
         );
 
         // Check and update the ledger chain id to ensure we're indexing the correct chain
-        let grpc_chain_id = TransactionStream::new(self.config.transaction_stream_config.clone())
-            .await?
-            .get_chain_id()
-            .await?;
-        check_or_update_chain_id(grpc_chain_id as i64, self.db_pool.clone()).await?;
+        if let Some(chain_id) = TransactionSourceStep::get_chain_id(&self.config).await? {
+            check_or_update_chain_id(chain_id as i64, self.db_pool.clone()).await?;
+        }
 
-        // Define processor steps
-        let transaction_stream = TransactionStreamStep::new(TransactionStreamConfig {
-            starting_version: Some(starting_version),
-            ..self.config.transaction_stream_config
-        })
+        let transaction_stream = TransactionSourceStep::new(
+            &self.config,
+            starting_version,
+            self.config.transaction_stream_config.request_ending_version,
+        )
         .await?;
-        let events_extractor = Extractor::new(match self.config.custom_config {
-            CustomConfig::ContractUpgradeIndexer(contract_addresses) => contract_addresses,
+        self.run_pipeline(transaction_stream, starting_version)
+            .await
+    }
+
+    /// Run the processor steps over the transactions read by `transaction_stream` and record
+    /// progress in processor_status. Returns when the stream ends.
+    pub async fn run_pipeline(
+        &self,
+        transaction_stream: TransactionSourceStep,
+        starting_version: u64,
+    ) -> Result<()> {
+        // Define processor steps
+        let tracked_contracts =
+            TrackedContracts::watch_if_configured(&self.config, self.db_pool.clone()).await?;
+        let transaction_filter =
+            TransactionFilterStep::new(TransactionFilter::new(&self.config.custom_config))
+                .with_tracked_contracts(tracked_contracts.clone());
+        let events_extractor = Extractor::new(match &self.config.custom_config {
+            CustomConfig::ContractUpgradeIndexer(contract_addresses) => contract_addresses.clone(),
             _ => {
-                return Err(anyhow::anyhow!("Invalid custom config"));
+                return Err(anyhow::anyhow!(
+                    "Invalid custom config, expected contract_upgrade_indexer, run validate-config to check the config"
+                ));
             }
-        });
-        let events_storer = Storer::new(self.db_pool.clone());
-        let version_tracker = LatestVersionProcessedTracker::new(
-            self.config.db_config,
+        })
+        .with_tracked_contracts(tracked_contracts);
+        let pipeline_config = &self.config.pipeline_config;
+        let events_storer = Storer::new(self.db_pool.clone())
+            .with_per_table_chunk_sizes(pipeline_config.per_table_chunk_sizes.clone());
+        let version_tracker = LatestVersionProcessedTracker::new_with_pool(
+            self.db_pool.clone(),
             starting_version,
             self.config.processor_config.name().to_string(),
         )
-        .await?;
+        .with_poll_interval(Duration::from_secs(
+            pipeline_config.processor_status_update_interval_secs,
+        ));
+        let channel_sizes = &pipeline_config.channel_sizes;
 
         // Connect processor steps together
         let (_, buffer_receiver) = ProcessorBuilder::new_with_inputless_first_step(
             transaction_stream.into_runnable_step(),
         )
-        .connect_to(events_extractor.into_runnable_step(), 10)
-        .connect_to(events_storer.into_runnable_step(), 10)
-        .connect_to(version_tracker.into_runnable_step(), 10)
-        .end_and_return_output_receiver(10);
+        .connect_to(
+            transaction_filter.into_runnable_step(),
+            channel_sizes.extractor,
+        )
+        .connect_to(
+            events_extractor.into_runnable_step(),
+            channel_sizes.extractor,
+        )
+        .connect_to(events_storer.into_runnable_step(), channel_sizes.storer)
+        .connect_to(
+            version_tracker.into_runnable_step(),
+            channel_sizes.version_tracker,
+        )
+        .end_and_return_output_receiver(channel_sizes.output);
 
         // (Optional) Parse the results
         loop {
@@ -94,6 +129,10 @@ impl ContractUpgradeProcessor {
                         txn_context.metadata.end_version,
                     );
                 }
+                Err(_) if is_shutdown_requested() => {
+                    tracing::info!("Finished in-flight batches after shutdown");
+                    return Ok(());
+                }
                 Err(_) => {
                     tracing::error!("Channel is closed");
                     return Ok(());
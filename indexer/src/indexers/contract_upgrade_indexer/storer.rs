@@ -22,6 +22,8 @@ where
     Self: Sized + Send + 'static,
 {
     pool: ArcDbPool,
+    // Overrides of the default insert chunk size, keyed by table name
+    per_table_chunk_sizes: AHashMap<String, usize>,
 }
 
 impl AsyncStep for Storer {}
@@ -34,7 +36,18 @@ impl NamedStep for Storer {
 
 impl Storer {
     pub fn new(pool: ArcDbPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            per_table_chunk_sizes: AHashMap::new(),
+        }
+    }
+
+    pub fn with_per_table_chunk_sizes(
+        mut self,
+        per_table_chunk_sizes: AHashMap<String, usize>,
+    ) -> Self {
+        self.per_table_chunk_sizes = per_table_chunk_sizes;
+        self
     }
 }
 
@@ -48,7 +61,7 @@ impl Processable for Storer {
         &mut self,
         transaction_context_data: TransactionContext<TransactionContextData>,
     ) -> Result<Option<TransactionContext<TransactionContextData>>, ProcessorError> {
-        let per_table_chunk_sizes: AHashMap<String, usize> = AHashMap::new();
+        let per_table_chunk_sizes = &self.per_table_chunk_sizes;
         let data = transaction_context_data.data.clone();
 
         let (module_upgrades, package_upgrades) = data.changes.into_iter().fold(
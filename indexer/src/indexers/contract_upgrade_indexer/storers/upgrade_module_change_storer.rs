@@ -8,7 +8,7 @@ use crate::{
     db_models::module_upgrade::ModuleUpgrade,
     schema::module_upgrade_history,
     utils::{
-        database_connection::get_db_connection,
+        database_execution::{execute_with_retries, handle_db_execution},
         database_utils::{get_config_table_chunk_size, ArcDbPool},
     },
 };
@@ -50,24 +50,13 @@ pub async fn process_upgrade_module_changes(
             let pool = pool.clone();
             let items = chunk.to_vec();
             tokio::spawn(async move {
-                let conn = &mut get_db_connection(&pool).await.expect(
-                    "Failed to get connection from pool while processing upgrade module changes",
-                );
-                execute_upgrade_module_changes_sql(conn, items).await
+                execute_with_retries(&pool, |conn| {
+                    Box::pin(execute_upgrade_module_changes_sql(conn, items.clone()))
+                })
+                .await
             })
         })
         .collect::<Vec<_>>();
 
-    let results = futures_util::future::try_join_all(tasks)
-        .await
-        .expect("Task panicked executing in chunks");
-    for res in results {
-        res.map_err(|e| {
-            tracing::warn!("Error running query: {:?}", e);
-            ProcessorError::ProcessError {
-                message: e.to_string(),
-            }
-        })?;
-    }
-    Ok(())
+    handle_db_execution(tasks).await
 }
@@ -0,0 +1,257 @@
+use anyhow::{Context, Result};
+use std::{sync::Arc, time::Duration};
+
+use super::processor::MarketplaceProcessor;
+use crate::{
+    config::indexer_processor_config::{BackfillConfig, IndexerProcessorConfig},
+    db_models::{backfill_shards::BackfillShard, processor_status::ProcessorStatusQuery},
+    utils::{
+        chain_id::check_or_update_chain_id, database_connection::get_db_connection,
+        database_utils::ArcDbPool, transaction_source::TransactionSourceStep,
+    },
+};
+
+// How long an idle worker waits before checking again for shards released by other workers
+const CLAIM_RETRY_SECS: u64 = 10;
+
+/// BackfillCoordinator splits the configured version range into shards stored in the
+/// backfill_shards table and runs workers that claim and process them.
+/// Each shard records its progress in processor_status under its own tracker name, so a
+/// worker that picks up an abandoned shard resumes from where the previous worker stopped.
+pub struct BackfillCoordinator {
+    processor: Arc<MarketplaceProcessor>,
+    backfill_config: BackfillConfig,
+}
+
+impl BackfillCoordinator {
+    pub async fn new(config: IndexerProcessorConfig) -> Result<Self> {
+        let backfill_config = config
+            .backfill_config
+            .clone()
+            .context("backfill_config is required for the sharded backfill processor")?;
+        anyhow::ensure!(
+            backfill_config.shard_size > 0,
+            "backfill_config.shard_size must be greater than 0"
+        );
+        anyhow::ensure!(
+            backfill_config.start_version <= backfill_config.end_version,
+            "backfill_config.start_version must not be greater than end_version"
+        );
+        let processor = MarketplaceProcessor::new(config).await?;
+        Ok(Self {
+            processor: Arc::new(processor),
+            backfill_config,
+        })
+    }
+
+    pub async fn run(self) -> Result<()> {
+        let db_pool = self.processor.db_pool.clone();
+
+        // Check and update the ledger chain id to ensure we're indexing the correct chain
+        if let Some(chain_id) = TransactionSourceStep::get_chain_id(&self.processor.config).await? {
+            check_or_update_chain_id(chain_id as i64, db_pool.clone()).await?;
+        }
+
+        let created_shards = BackfillShard::create_shards(
+            &self.backfill_config.backfill_name,
+            self.backfill_config.start_version,
+            self.backfill_config.end_version,
+            self.backfill_config.shard_size,
+            &mut get_db_connection(&db_pool).await?,
+        )
+        .await
+        .context("Failed to create backfill shards")?;
+        tracing::info!(
+            backfill_name = self.backfill_config.backfill_name,
+            created_shards,
+            "Starting sharded backfill with {} workers",
+            self.backfill_config.num_workers
+        );
+
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string());
+        let workers = (0..self.backfill_config.num_workers)
+            .map(|worker_idx| {
+                let worker = BackfillWorker {
+                    worker_id: format!("{}-{}-{}", host, std::process::id(), worker_idx),
+                    processor: self.processor.clone(),
+                    backfill_config: self.backfill_config.clone(),
+                    db_pool: db_pool.clone(),
+                };
+                tokio::spawn(async move { worker.run().await })
+            })
+            .collect::<Vec<_>>();
+
+        for res in futures_util::future::try_join_all(workers)
+            .await
+            .context("Backfill worker panicked")?
+        {
+            res?;
+        }
+
+        tracing::info!(
+            backfill_name = self.backfill_config.backfill_name,
+            "No shards left to claim, sharded backfill finished"
+        );
+        Ok(())
+    }
+}
+
+struct BackfillWorker {
+    worker_id: String,
+    processor: Arc<MarketplaceProcessor>,
+    backfill_config: BackfillConfig,
+    db_pool: ArcDbPool,
+}
+
+impl BackfillWorker {
+    async fn run(self) -> Result<()> {
+        loop {
+            let maybe_shard = BackfillShard::claim_next(
+                &self.backfill_config.backfill_name,
+                &self.worker_id,
+                self.backfill_config.lease_secs,
+                self.backfill_config.max_attempts,
+                &mut get_db_connection(&self.db_pool).await?,
+            )
+            .await
+            .context("Failed to claim backfill shard")?;
+
+            let shard = match maybe_shard {
+                Some(shard) => shard,
+                None => {
+                    let remaining = BackfillShard::count_remaining(
+                        &self.backfill_config.backfill_name,
+                        &mut get_db_connection(&self.db_pool).await?,
+                    )
+                    .await?;
+                    if remaining == 0 {
+                        return Ok(());
+                    }
+                    // Other workers hold the remaining shards, wait in case one of them dies
+                    tokio::time::sleep(Duration::from_secs(CLAIM_RETRY_SECS)).await;
+                    continue;
+                }
+            };
+
+            tracing::info!(
+                worker_id = self.worker_id,
+                shard_id = shard.shard_id,
+                attempt = shard.attempts,
+                "Claimed shard [{}, {}]",
+                shard.start_version,
+                shard.end_version
+            );
+
+            match self.process_shard(&shard).await {
+                Ok(()) => {
+                    let updated = BackfillShard::mark_completed(
+                        shard.shard_id,
+                        &self.worker_id,
+                        &mut get_db_connection(&self.db_pool).await?,
+                    )
+                    .await?;
+                    if updated == 0 {
+                        self.warn_lost_lease(&shard);
+                        continue;
+                    }
+                    tracing::info!(
+                        worker_id = self.worker_id,
+                        shard_id = shard.shard_id,
+                        "Completed shard"
+                    );
+                }
+                Err(e) => {
+                    tracing::error!(
+                        worker_id = self.worker_id,
+                        shard_id = shard.shard_id,
+                        "Failed to process shard: {:?}",
+                        e
+                    );
+                    let updated = shard
+                        .mark_failed(
+                            format!("{:?}", e),
+                            self.backfill_config.max_attempts,
+                            &mut get_db_connection(&self.db_pool).await?,
+                        )
+                        .await?;
+                    if updated == 0 {
+                        self.warn_lost_lease(&shard);
+                    }
+                }
+            }
+        }
+    }
+
+    /// The shard's lease ran out while it was processed, the worker that claimed it since owns
+    /// its status now.
+    fn warn_lost_lease(&self, shard: &BackfillShard) {
+        tracing::warn!(
+            worker_id = self.worker_id,
+            shard_id = shard.shard_id,
+            "Lost the lease on shard, another worker claimed it"
+        );
+    }
+
+    async fn process_shard(&self, shard: &BackfillShard) -> Result<()> {
+        let tracker_name = format!("backfill_shard_{}", shard.shard_id);
+        let end_version = shard.end_version as u64;
+        // Resume from the last version a previous attempt finished
+        let starting_version = self
+            .get_last_success_version(&tracker_name)
+            .await?
+            .map(|version| version + 1)
+            .unwrap_or(shard.start_version as u64)
+            .max(shard.start_version as u64);
+        if starting_version > end_version {
+            return Ok(());
+        }
+
+        let heartbeat = self.spawn_heartbeat(shard.shard_id);
+        let res = self
+            .processor
+            .run_pipeline(starting_version, Some(end_version), tracker_name.clone())
+            .await;
+        heartbeat.abort();
+        res?;
+
+        // The pipeline also returns when a step dies, so only trust the tracker's progress
+        let last_success_version = self.get_last_success_version(&tracker_name).await?;
+        match last_success_version {
+            Some(version) if version >= end_version => Ok(()),
+            _ => Err(anyhow::anyhow!(
+                "Stream ended at version {:?} before reaching the end of the shard {}",
+                last_success_version,
+                end_version
+            )),
+        }
+    }
+
+    async fn get_last_success_version(&self, tracker_name: &str) -> Result<Option<u64>> {
+        let mut conn = get_db_connection(&self.db_pool).await?;
+        Ok(
+            ProcessorStatusQuery::get_by_processor(tracker_name, &mut conn)
+                .await?
+                .map(|status| status.last_success_version as u64),
+        )
+    }
+
+    fn spawn_heartbeat(&self, shard_id: i64) -> tokio::task::JoinHandle<()> {
+        let db_pool = self.db_pool.clone();
+        let worker_id = self.worker_id.clone();
+        let interval = Duration::from_secs((self.backfill_config.lease_secs / 3).max(1));
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let res = match get_db_connection(&db_pool).await {
+                    Ok(mut conn) => BackfillShard::heartbeat(shard_id, &worker_id, &mut conn)
+                        .await
+                        .map_err(anyhow::Error::from),
+                    Err(e) => Err(e.into()),
+                };
+                if let Err(e) = res {
+                    tracing::warn!(shard_id, "Failed to heartbeat backfill shard: {:?}", e);
+                }
+            }
+        })
+    }
+}
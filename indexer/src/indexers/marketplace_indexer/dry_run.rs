@@ -0,0 +1,456 @@
+use std::{collections::BTreeMap, fmt};
+
+use ahash::AHashSet;
+use anyhow::{Context, Result};
+use aptos_indexer_processor_sdk::{
+    traits::{async_step::AsyncRunType, AsyncStep, NamedStep, Processable},
+    types::transaction_context::TransactionContext,
+    utils::errors::ProcessorError,
+};
+use async_trait::async_trait;
+use diesel::{
+    sql_query,
+    sql_types::{Array, BigInt, Integer, Json, Nullable, Text},
+    QueryResult, QueryableByName,
+};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use super::{
+    extractor::{ContractEvent, TransactionContextData},
+    storer::Storer,
+};
+use crate::utils::{
+    aptos_utils::OrderStatus,
+    database_connection::{get_db_connection, new_single_connection_db_pool},
+    database_utils::ArcDbPool,
+};
+
+// Tables the storers write to, with the columns of their primary key
+const DRY_RUN_TABLES: [(&str, &[&str]); 7] = [
+    ("nft_asks", &["ask_obj_addr"]),
+    ("nft_bids", &["bid_obj_addr"]),
+    ("collection_bids", &["bid_obj_addr"]),
+    (
+        "filled_collection_bids",
+        &["bid_obj_addr", "nft_id", "nft_name"],
+    ),
+    ("activities", &["activity_tx_version", "activity_event_idx"]),
+    ("sweeps", &["txn_version", "event_idx"]),
+    ("transactions", &["txn_version"]),
+];
+
+/// Session setup of the dry run connection. Temporary tables shadow the tables of the same name
+/// for the session, so the storers write to empty copies. The session is read only afterwards,
+/// which Postgres enforces for every table except temporary ones.
+fn dry_run_setup_sql() -> String {
+    let mut sql = DRY_RUN_TABLES
+        .iter()
+        .map(|(table, _)| format!("CREATE TEMP TABLE {table} (LIKE {table} INCLUDING ALL);"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    sql.push_str("\nSET default_transaction_read_only = on;");
+    sql
+}
+
+#[derive(QueryableByName)]
+struct SessionInfo {
+    #[diesel(sql_type = Text)]
+    schema: String,
+    #[diesel(sql_type = Integer)]
+    backend_pid: i32,
+}
+
+#[derive(QueryableByName)]
+struct ComparedRow {
+    #[diesel(sql_type = Json)]
+    dry_run_row: Value,
+    #[diesel(sql_type = Nullable<Json>)]
+    current_row: Option<Value>,
+}
+
+/// Connection to run a version range through the storers without writing anything. Rows the
+/// range touches are copied to the session's temporary tables before each batch is stored, so
+/// the upserts see the same rows they would in the real tables.
+pub struct DryRunSession {
+    pool: ArcDbPool,
+    // Schema of the real tables
+    schema: String,
+    backend_pid: i32,
+}
+
+impl DryRunSession {
+    pub async fn start(database_url: &str) -> Result<Self> {
+        let pool = new_single_connection_db_pool(database_url, dry_run_setup_sql()).await;
+        let mut conn = get_db_connection(&pool).await?;
+        let session =
+            sql_query("SELECT current_schema() AS schema, pg_backend_pid() AS backend_pid")
+                .get_result::<SessionInfo>(&mut conn)
+                .await
+                .context("Failed to set up dry run session")?;
+        drop(conn);
+
+        Ok(Self {
+            pool,
+            schema: session.schema,
+            backend_pid: session.backend_pid,
+        })
+    }
+
+    /// Step that stores the extracted events in the session's temporary tables.
+    pub fn storer(&self) -> DryRunStorer {
+        DryRunStorer {
+            pool: self.pool.clone(),
+            schema: self.schema.clone(),
+            storer: Storer::new(self.pool.clone()),
+        }
+    }
+
+    /// Compare the temporary tables with the current rows of the real tables.
+    pub async fn finish(self, start_version: u64, end_version: u64) -> Result<DryRunReport> {
+        let mut conn = get_db_connection(&self.pool).await?;
+        let session =
+            sql_query("SELECT current_schema() AS schema, pg_backend_pid() AS backend_pid")
+                .get_result::<SessionInfo>(&mut conn)
+                .await?;
+        // A new connection would come with empty temporary tables
+        anyhow::ensure!(
+            session.backend_pid == self.backend_pid,
+            "Dry run connection was reset while processing, run the dry run again"
+        );
+
+        let mut tables = vec![];
+        for (table, key_columns) in DRY_RUN_TABLES {
+            let rows = compare_rows(&mut conn, &self.schema, table, key_columns)
+                .await
+                .with_context(|| format!("Failed to compare dry run rows of {}", table))?;
+            tables.push(diff_rows(table, key_columns, rows));
+        }
+
+        Ok(DryRunReport {
+            start_version,
+            end_version,
+            tables,
+        })
+    }
+}
+
+/// Copy the current rows the events are about to update into the temporary tables, rows that
+/// were already copied are kept since they include the changes of earlier batches.
+async fn copy_current_rows(
+    conn: &mut AsyncPgConnection,
+    schema: &str,
+    events: &[ContractEvent],
+) -> QueryResult<()> {
+    let mut asks = AHashSet::new();
+    let mut bids = AHashSet::new();
+    let mut collection_bids = AHashSet::new();
+    let mut activity_versions = AHashSet::new();
+    for event in events {
+        let activity = match event {
+            ContractEvent::BidPlacedEvent((bid, activity))
+            | ContractEvent::BidFilledEvent((bid, activity))
+            | ContractEvent::BidCancelledEvent((bid, activity)) => {
+                bids.insert(bid.bid_obj_addr.clone());
+                activity
+            }
+            ContractEvent::AskPlacedEvent((ask, activity))
+            | ContractEvent::AskFilledEvent((ask, activity))
+            | ContractEvent::AskCancelledEvent((ask, activity)) => {
+                asks.insert(ask.ask_obj_addr.clone());
+                activity
+            }
+            ContractEvent::CollectionBidPlacedEvent((bid, activity))
+            | ContractEvent::CollectionBidFilledEvent((bid, _, activity))
+            | ContractEvent::CollectionBidCancelledEvent((bid, activity)) => {
+                collection_bids.insert(bid.bid_obj_addr.clone());
+                activity
+            }
+            ContractEvent::AskPriceUpdatedEvent((cancelled_ask, placed_ask, activity)) => {
+                asks.insert(cancelled_ask.ask_obj_addr.clone());
+                asks.insert(placed_ask.ask_obj_addr.clone());
+                activity
+            }
+            ContractEvent::BidPriceUpdatedEvent((cancelled_bid, placed_bid, activity)) => {
+                bids.insert(cancelled_bid.bid_obj_addr.clone());
+                bids.insert(placed_bid.bid_obj_addr.clone());
+                activity
+            }
+        };
+        activity_versions.insert(activity.activity_tx_version);
+    }
+
+    let copies = [
+        ("nft_asks", "ask_obj_addr", asks),
+        ("nft_bids", "bid_obj_addr", bids),
+        ("collection_bids", "bid_obj_addr", collection_bids.clone()),
+        ("filled_collection_bids", "bid_obj_addr", collection_bids),
+    ];
+    for (table, key_column, keys) in copies {
+        if keys.is_empty() {
+            continue;
+        }
+        sql_query(format!(
+            "INSERT INTO pg_temp.{table} SELECT * FROM \"{schema}\".{table} \
+             WHERE {key_column} = ANY($1) ON CONFLICT DO NOTHING"
+        ))
+        .bind::<Array<Text>, _>(keys.into_iter().collect::<Vec<_>>())
+        .execute(conn)
+        .await?;
+    }
+
+    if !activity_versions.is_empty() {
+        let activity_versions = activity_versions.into_iter().collect::<Vec<_>>();
+        sql_query(format!(
+            "INSERT INTO pg_temp.activities SELECT * FROM \"{schema}\".activities \
+             WHERE activity_tx_version = ANY($1) ON CONFLICT DO NOTHING"
+        ))
+        .bind::<Array<BigInt>, _>(&activity_versions)
+        .execute(conn)
+        .await?;
+        for table in ["sweeps", "transactions"] {
+            sql_query(format!(
+                "INSERT INTO pg_temp.{table} SELECT * FROM \"{schema}\".{table} \
+                 WHERE txn_version = ANY($1) ON CONFLICT DO NOTHING"
+            ))
+            .bind::<Array<BigInt>, _>(&activity_versions)
+            .execute(conn)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Every row of a temporary table next to the current row with the same key, if there is one.
+async fn compare_rows(
+    conn: &mut AsyncPgConnection,
+    schema: &str,
+    table: &str,
+    key_columns: &[&str],
+) -> QueryResult<Vec<(Value, Option<Value>)>> {
+    let key_filter = key_columns
+        .iter()
+        .map(|column| format!("r.{column} = d.{column}"))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let order_by = key_columns
+        .iter()
+        .map(|column| format!("d.{column}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let rows = sql_query(format!(
+        "SELECT row_to_json(d) AS dry_run_row, \
+         (SELECT row_to_json(r) FROM \"{schema}\".{table} r WHERE {key_filter}) AS current_row \
+         FROM pg_temp.{table} d ORDER BY {order_by}"
+    ))
+    .load::<ComparedRow>(conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.dry_run_row, row.current_row))
+        .collect())
+}
+
+fn order_status_name(status: &Value) -> String {
+    match status.as_i64() {
+        Some(status) if status == OrderStatus::Open as i64 => "open".to_string(),
+        Some(status) if status == OrderStatus::Filled as i64 => "filled".to_string(),
+        Some(status) if status == OrderStatus::Cancelled as i64 => "cancelled".to_string(),
+        _ => status.to_string(),
+    }
+}
+
+fn diff_rows(table: &str, key_columns: &[&str], rows: Vec<(Value, Option<Value>)>) -> TableDiff {
+    let mut new_rows = vec![];
+    let mut changed_rows = vec![];
+    let mut status_transitions = BTreeMap::<(Option<String>, String), usize>::new();
+
+    for (dry_run_row, current_row) in rows {
+        let Value::Object(dry_run_row) = dry_run_row else {
+            continue;
+        };
+        let to_status = dry_run_row.get("order_status").map(order_status_name);
+        let current_row = match current_row {
+            Some(Value::Object(current_row)) => current_row,
+            _ => {
+                if let Some(to_status) = to_status {
+                    *status_transitions.entry((None, to_status)).or_default() += 1;
+                }
+                new_rows.push(Value::Object(dry_run_row));
+                continue;
+            }
+        };
+
+        let columns = dry_run_row
+            .iter()
+            .filter(|(column, value)| current_row.get(*column) != Some(*value))
+            .map(|(column, value)| ColumnChange {
+                column: column.clone(),
+                current: current_row.get(column).cloned().unwrap_or(Value::Null),
+                dry_run: value.clone(),
+            })
+            .collect::<Vec<_>>();
+        if columns.is_empty() {
+            continue;
+        }
+        if let Some(to_status) = to_status {
+            let from_status = current_row.get("order_status").map(order_status_name);
+            if from_status.as_ref() != Some(&to_status) {
+                *status_transitions
+                    .entry((from_status, to_status))
+                    .or_default() += 1;
+            }
+        }
+        let key = key_columns
+            .iter()
+            .map(|column| {
+                (
+                    column.to_string(),
+                    dry_run_row.get(*column).cloned().unwrap_or(Value::Null),
+                )
+            })
+            .collect::<Map<_, _>>();
+        changed_rows.push(ChangedRow { key, columns });
+    }
+
+    TableDiff {
+        table: table.to_string(),
+        new_rows,
+        changed_rows,
+        status_transitions: status_transitions
+            .into_iter()
+            .map(|((from, to), count)| StatusTransition { from, to, count })
+            .collect(),
+    }
+}
+
+/// What processing a version range would change in the marketplace tables.
+#[derive(Debug, Serialize)]
+pub struct DryRunReport {
+    pub start_version: u64,
+    pub end_version: u64,
+    pub tables: Vec<TableDiff>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TableDiff {
+    pub table: String,
+    pub new_rows: Vec<Value>,
+    pub changed_rows: Vec<ChangedRow>,
+    pub status_transitions: Vec<StatusTransition>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedRow {
+    pub key: Map<String, Value>,
+    pub columns: Vec<ColumnChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnChange {
+    pub column: String,
+    pub current: Value,
+    pub dry_run: Value,
+}
+
+/// Number of orders that would move from one status to another, `from` is empty for new orders.
+#[derive(Debug, Serialize)]
+pub struct StatusTransition {
+    pub from: Option<String>,
+    pub to: String,
+    pub count: usize,
+}
+
+impl fmt::Display for DryRunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Dry run of versions [{}, {}]",
+            self.start_version, self.end_version
+        )?;
+        for table in &self.tables {
+            writeln!(
+                f,
+                "{}: {} new, {} changed",
+                table.table,
+                table.new_rows.len(),
+                table.changed_rows.len()
+            )?;
+            for transition in &table.status_transitions {
+                writeln!(
+                    f,
+                    "  status {} -> {}: {}",
+                    transition.from.as_deref().unwrap_or("none"),
+                    transition.to,
+                    transition.count
+                )?;
+            }
+            for row in &table.changed_rows {
+                let columns = row
+                    .columns
+                    .iter()
+                    .map(|change| {
+                        format!(
+                            "{}: {} -> {}",
+                            change.column, change.current, change.dry_run
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                writeln!(f, "  {}: {}", Value::Object(row.key.clone()), columns)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// DryRunStorer is a step that stores events in the temporary tables of a dry run session.
+pub struct DryRunStorer
+where
+    Self: Sized + Send + 'static,
+{
+    pool: ArcDbPool,
+    schema: String,
+    storer: Storer,
+}
+
+impl AsyncStep for DryRunStorer {}
+
+impl NamedStep for DryRunStorer {
+    fn name(&self) -> String {
+        "DryRunStorer".to_string()
+    }
+}
+
+#[async_trait]
+impl Processable for DryRunStorer {
+    type Input = TransactionContextData;
+    type Output = TransactionContextData;
+    type RunType = AsyncRunType;
+
+    async fn process(
+        &mut self,
+        transaction_context_data: TransactionContext<TransactionContextData>,
+    ) -> Result<Option<TransactionContext<TransactionContextData>>, ProcessorError> {
+        let mut conn = get_db_connection(&self.pool).await?;
+        copy_current_rows(
+            &mut conn,
+            &self.schema,
+            &transaction_context_data.data.events,
+        )
+        .await
+        .map_err(|e| ProcessorError::DBStoreError {
+            message: format!("Failed to copy current rows for the dry run: {:?}", e),
+            query: None,
+        })?;
+        // The pool only has one connection, which the storer needs back
+        drop(conn);
+
+        self.storer
+            .store(transaction_context_data.data.clone())
+            .await?;
+        Ok(Some(transaction_context_data))
+    }
+}
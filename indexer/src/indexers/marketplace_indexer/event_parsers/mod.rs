@@ -1,2 +1,73 @@
+use aptos_indexer_processor_sdk::aptos_protos::transaction::v1::Event as EventPB;
+use std::{fmt, str::FromStr};
+
+use self::{
+    aptos_labs_contract_event_parser::parse_from_aptos_labs_contract_event,
+    tradeport_contract_v2_event_parser::parse_from_tradeport_v2_contract_event,
+};
+use super::extractor::ContractEvent;
+
 pub mod aptos_labs_contract_event_parser;
 pub mod tradeport_contract_v2_event_parser;
+
+/// Marketplace contracts whose events one of the parsers understands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum ParserFamily {
+    AptosLabs,
+    TradeportV2,
+}
+
+impl ParserFamily {
+    pub const ALL: [ParserFamily; 2] = [ParserFamily::AptosLabs, ParserFamily::TradeportV2];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ParserFamily::AptosLabs => "aptos_labs",
+            ParserFamily::TradeportV2 => "tradeport_v2",
+        }
+    }
+
+    pub fn parse(
+        &self,
+        event_idx: i64,
+        event: &EventPB,
+        txn_version: i64,
+        event_addr: String,
+        event_type: String,
+    ) -> Option<ContractEvent> {
+        match self {
+            ParserFamily::AptosLabs => parse_from_aptos_labs_contract_event(
+                event_idx,
+                event,
+                txn_version,
+                event_addr,
+                event_type,
+            ),
+            ParserFamily::TradeportV2 => parse_from_tradeport_v2_contract_event(
+                event_idx,
+                event,
+                txn_version,
+                event_addr,
+                event_type,
+            ),
+        }
+    }
+}
+
+impl FromStr for ParserFamily {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        ParserFamily::ALL
+            .into_iter()
+            .find(|family| family.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported parser family: {}", s))
+    }
+}
+
+impl fmt::Display for ParserFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
@@ -1,4 +1,4 @@
-use ahash::AHashSet;
+use ahash::{AHashMap, AHashSet};
 use anyhow::Result;
 use aptos_indexer_processor_sdk::{
     aptos_protos::transaction::v1::{
@@ -6,34 +6,54 @@ use aptos_indexer_processor_sdk::{
     },
     traits::{async_step::AsyncRunType, AsyncStep, NamedStep, Processable},
     types::transaction_context::TransactionContext,
-    utils::{convert::standardize_address, errors::ProcessorError},
+    utils::{convert::standardize_address, errors::ProcessorError, time::parse_timestamp},
 };
 use async_trait::async_trait;
 use rayon::prelude::*;
+use serde::Serialize;
 
-use crate::db_models::{
-    activities::Activity, collection_bids::CollectionBid, filled_collection_bids::FilledCollectionBid, nft_asks::NftAsk, nft_bids::NftBid
+use crate::{
+    db_models::{
+        activities::Activity, collection_bids::CollectionBid, filled_collection_bids::FilledCollectionBid, nft_asks::NftAsk, nft_bids::NftBid,
+        raw_marketplace_events::RawMarketplaceEvent, transactions::MarketplaceTransaction,
+    },
+    utils::{
+        aptos_utils::ActivityType,
+        tracked_contracts::{TrackedContractKind, TrackedContracts},
+    },
 };
 
-use super::event_parsers::{
-    aptos_labs_contract_event_parser::parse_from_aptos_labs_contract_event,
-    tradeport_contract_v2_event_parser::parse_from_tradeport_v2_contract_event,
-};
+use super::event_parsers::ParserFamily;
 
 /// Extractor is a step that extracts events and their metadata from transactions.
 pub struct Extractor
 where
     Self: Sized + Send + 'static,
 {
-    contract_addresses: AHashSet<String>,
+    contract_addresses: AHashMap<String, Option<ParserFamily>>,
+    // Also emit the raw events of tracked contracts so the storer archives them
+    archive_raw_events: bool,
+    // Marketplaces of tracked_contracts, tracked on top of the configured ones
+    tracked_contracts: Option<TrackedContracts>,
 }
 
 impl Extractor {
-    pub fn new(contract_addresses: Vec<String>) -> Self {
+    pub fn new(contract_addresses: Vec<String>, archive_raw_events: bool) -> Self {
         Self {
-            contract_addresses: contract_addresses.into_iter().collect(),
+            // Event addresses are compared padded and lowercase, so the configured ones must be too
+            contract_addresses: contract_addresses
+                .iter()
+                .map(|addr| (standardize_address(&addr.to_lowercase()), None))
+                .collect(),
+            archive_raw_events,
+            tracked_contracts: None,
         }
     }
+
+    pub fn with_tracked_contracts(mut self, tracked_contracts: Option<TrackedContracts>) -> Self {
+        self.tracked_contracts = tracked_contracts;
+        self
+    }
 }
 
 impl AsyncStep for Extractor {}
@@ -54,73 +74,144 @@ impl Processable for Extractor {
         &mut self,
         item: TransactionContext<Vec<Transaction>>,
     ) -> Result<Option<TransactionContext<TransactionContextData>>, ProcessorError> {
-        let results: Vec<(Vec<ContractEvent>, Vec<WriteSetChange>)> = item
+        let tracked_contract_addresses = self.tracked_contracts.as_mut().map(|tracked_contracts| {
+            tracked_contracts.current().contract_addresses_by_version(
+                TrackedContractKind::Marketplace,
+                &self.contract_addresses,
+                item.metadata.start_version,
+                item.metadata.end_version,
+                |contract_addresses, contract| {
+                    contract_addresses
+                        .insert(contract.contract_address.clone(), contract.parser_family);
+                },
+            )
+        });
+        let results: Vec<(
+            Vec<ContractEvent>,
+            Vec<WriteSetChange>,
+            Vec<RawMarketplaceEvent>,
+            Option<MarketplaceTransaction>,
+        )> = item
             .data
             .par_iter()
             .map(|txn| {
                 let txn_version = txn.version as i64;
-                match txn.info.as_ref() {
-                    Some(info) => {
-                        if !info.success {
-                            return (vec![], vec![]);
-                        }
-                    }
-                    None => {
-                        tracing::warn!(
-                            transaction_version = txn_version,
-                            "Transaction info doesn't exist"
-                        );
-                        return (vec![], vec![]);
-                    }
-                };
-                let txn_data = match txn.txn_data.as_ref() {
-                    Some(data) => data,
-                    None => {
-                        tracing::warn!(
-                            transaction_version = txn_version,
-                            "Transaction data doesn't exist"
-                        );
-                        return (vec![], vec![]);
-                    }
-                };
-                let raw_events = match txn_data {
-                    TxnData::BlockMetadata(tx_inner) => &tx_inner.events,
-                    TxnData::Genesis(tx_inner) => &tx_inner.events,
-                    TxnData::User(tx_inner) => &tx_inner.events,
-                    _ => &vec![],
+                let raw_events = successful_transaction_events(txn);
+                let contract_addresses = match &tracked_contract_addresses {
+                    Some(tracked_contract_addresses) => tracked_contract_addresses.at(txn.version),
+                    None => &self.contract_addresses,
                 };
 
                 let txn_events =
-                    ContractEvent::from_events(&self.contract_addresses, raw_events, txn_version);
+                    ContractEvent::from_events(contract_addresses, raw_events, txn_version);
+
+                let txn_raw_events = if self.archive_raw_events {
+                    let block_timestamp = txn
+                        .timestamp
+                        .as_ref()
+                        .map(|t| parse_timestamp(t, txn_version).naive_utc())
+                        .unwrap_or_default();
+                    raw_events
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, event)| {
+                            ContractEvent::is_from_contracts(contract_addresses, event)
+                        })
+                        .map(|(idx, event)| {
+                            RawMarketplaceEvent::from_event(
+                                event,
+                                txn_version,
+                                idx as i64,
+                                block_timestamp,
+                            )
+                        })
+                        .collect()
+                } else {
+                    vec![]
+                };
 
-                (txn_events, vec![])
+                // Only transactions with activities are kept, the others aren't joined to anything
+                let txn = (!txn_events.is_empty())
+                    .then(|| MarketplaceTransaction::from_transaction(txn));
+
+                (txn_events, vec![], txn_raw_events, txn)
             })
-            .collect::<Vec<(Vec<ContractEvent>, Vec<WriteSetChange>)>>();
-
-        let (events, changes): (Vec<ContractEvent>, Vec<WriteSetChange>) =
-            results.into_iter().fold(
-                (Vec::new(), Vec::new()),
-                |(mut events_acc, mut changes_acc), (events, changes)| {
-                    events_acc.extend(events);
-                    changes_acc.extend(changes);
-                    (events_acc, changes_acc)
-                },
-            );
+            .collect();
+
+        let (events, changes, raw_events, transactions): (
+            Vec<ContractEvent>,
+            Vec<WriteSetChange>,
+            Vec<RawMarketplaceEvent>,
+            Vec<MarketplaceTransaction>,
+        ) = results.into_iter().fold(
+            (Vec::new(), Vec::new(), Vec::new(), Vec::new()),
+            |(mut events_acc, mut changes_acc, mut raw_events_acc, mut transactions_acc),
+             (events, changes, raw_events, txn)| {
+                events_acc.extend(events);
+                changes_acc.extend(changes);
+                raw_events_acc.extend(raw_events);
+                transactions_acc.extend(txn);
+                (events_acc, changes_acc, raw_events_acc, transactions_acc)
+            },
+        );
 
         Ok(Some(TransactionContext {
-            data: TransactionContextData { events, changes },
+            data: TransactionContextData {
+                events,
+                changes,
+                raw_events,
+                transactions,
+            },
             metadata: item.metadata,
         }))
     }
 }
 
+/// Events emitted by a transaction, none if it failed since it then had no effect.
+pub(crate) fn successful_transaction_events(txn: &Transaction) -> &[EventPB] {
+    let txn_version = txn.version as i64;
+    match txn.info.as_ref() {
+        Some(info) => {
+            if !info.success {
+                return &[];
+            }
+        }
+        None => {
+            tracing::warn!(
+                transaction_version = txn_version,
+                "Transaction info doesn't exist"
+            );
+            return &[];
+        }
+    };
+    let txn_data = match txn.txn_data.as_ref() {
+        Some(data) => data,
+        None => {
+            tracing::warn!(
+                transaction_version = txn_version,
+                "Transaction data doesn't exist"
+            );
+            return &[];
+        }
+    };
+    match txn_data {
+        TxnData::BlockMetadata(tx_inner) => &tx_inner.events,
+        TxnData::Genesis(tx_inner) => &tx_inner.events,
+        TxnData::User(tx_inner) => &tx_inner.events,
+        _ => &[],
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TransactionContextData {
     pub events: Vec<ContractEvent>,
     pub changes: Vec<WriteSetChange>,
+    pub raw_events: Vec<RawMarketplaceEvent>,
+    // Transactions the events were emitted by
+    pub transactions: Vec<MarketplaceTransaction>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum ContractEvent {
     BidPlacedEvent((NftBid, Activity)),
     BidFilledEvent((NftBid, Activity)),
@@ -131,11 +222,44 @@ pub enum ContractEvent {
     CollectionBidPlacedEvent((CollectionBid, Activity)),
     CollectionBidFilledEvent((CollectionBid, FilledCollectionBid, Activity)),
     CollectionBidCancelledEvent((CollectionBid, Activity)),
+    // The cancelled ask, the ask placed instead of it and the price update activity
+    AskPriceUpdatedEvent((NftAsk, NftAsk, Activity)),
+    // The cancelled bid, the bid placed instead of it and the price update activity
+    BidPriceUpdatedEvent((NftBid, NftBid, Activity)),
+}
+
+/// Marketplace contract addresses to parse the events of, padded and lowercase.
+pub trait ContractAddresses: Sync {
+    /// None if the address isn't tracked, otherwise the parser family its events are limited
+    /// to, if any.
+    fn parser_family(&self, contract_address: &str) -> Option<Option<ParserFamily>>;
+}
+
+impl ContractAddresses for AHashSet<String> {
+    fn parser_family(&self, contract_address: &str) -> Option<Option<ParserFamily>> {
+        self.contains(contract_address).then_some(None)
+    }
+}
+
+impl ContractAddresses for AHashMap<String, Option<ParserFamily>> {
+    fn parser_family(&self, contract_address: &str) -> Option<Option<ParserFamily>> {
+        self.get(contract_address).copied()
+    }
 }
 
 impl ContractEvent {
-    fn from_event(
-        contract_addresses: &AHashSet<String>,
+    pub(crate) fn is_from_contracts(
+        contract_addresses: &impl ContractAddresses,
+        event: &EventPB,
+    ) -> bool {
+        let event_addr = event.type_str.split("::").next().unwrap_or_default();
+        contract_addresses
+            .parser_family(standardize_address(event_addr).as_str())
+            .is_some()
+    }
+
+    pub(crate) fn from_event(
+        contract_addresses: &impl ContractAddresses,
         event_idx: i64,
         event: &EventPB,
         txn_version: i64,
@@ -143,40 +267,155 @@ impl ContractEvent {
         // use standardize_address to pad the address in event type before processing
         let parts = event.type_str.split("::").collect::<Vec<_>>();
         let event_addr = standardize_address(parts[0]);
-        if contract_addresses.contains(event_addr.as_str()) {
-            let event_type = event_addr.clone() + "::" + parts[1] + "::" + parts[2];
-            parse_from_aptos_labs_contract_event(
+        let parser_family = contract_addresses.parser_family(event_addr.as_str())?;
+        let parser_families = match &parser_family {
+            Some(parser_family) => std::slice::from_ref(parser_family),
+            None => &ParserFamily::ALL[..],
+        };
+        let event_type = event_addr.clone() + "::" + parts[1] + "::" + parts[2];
+        parser_families.iter().find_map(|parser_family| {
+            parser_family.parse(
                 event_idx,
                 event,
                 txn_version,
                 event_addr.clone(),
                 event_type.clone(),
             )
-            .or_else(|| {
-                parse_from_tradeport_v2_contract_event(
-                    event_idx,
-                    event,
-                    txn_version,
-                    event_addr,
-                    event_type,
-                )
-            })
-        } else {
-            None
-        }
+        })
     }
 
     pub fn from_events(
-        contract_addresses: &AHashSet<String>,
+        contract_addresses: &impl ContractAddresses,
         events: &[EventPB],
         txn_version: i64,
     ) -> Vec<Self> {
-        events
-            .iter()
-            .enumerate()
-            .filter_map(|(idx, event)| {
-                Self::from_event(contract_addresses, idx as i64, event, txn_version)
-            })
-            .collect()
+        Self::pair_price_updates(
+            events
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, event)| {
+                    Self::from_event(contract_addresses, idx as i64, event, txn_version)
+                })
+                .collect(),
+        )
+    }
+
+    /// Merge every ask or bid that's cancelled and placed again later in the same transaction,
+    /// for the same NFT by the same seller or buyer, into a price update. Some marketplaces edit
+    /// a price that way, which would otherwise count as a cancellation. Events must be in event
+    /// order.
+    pub(crate) fn pair_price_updates(events: Vec<Self>) -> Vec<Self> {
+        let mut paired = Vec::with_capacity(events.len());
+        let mut txn_events: Vec<Self> = vec![];
+        for event in events {
+            if txn_events.last().is_some_and(|last| {
+                last.activity().activity_tx_version != event.activity().activity_tx_version
+            }) {
+                paired.extend(Self::pair_price_updates_in_txn(std::mem::take(
+                    &mut txn_events,
+                )));
+            }
+            txn_events.push(event);
+        }
+        paired.extend(Self::pair_price_updates_in_txn(txn_events));
+        paired
+    }
+
+    /// `pair_price_updates` for the events of one transaction.
+    fn pair_price_updates_in_txn(events: Vec<Self>) -> Vec<Self> {
+        let mut events = events.into_iter().map(Some).collect::<Vec<_>>();
+        for placed_idx in 0..events.len() {
+            let price_update = match &events[placed_idx] {
+                Some(placed) => (0..placed_idx).find_map(|cancelled_idx| {
+                    let cancelled = events[cancelled_idx].as_ref()?;
+                    Some((cancelled_idx, Self::price_update(cancelled, placed)?))
+                }),
+                None => None,
+            };
+            if let Some((cancelled_idx, price_update)) = price_update {
+                events[cancelled_idx] = None;
+                events[placed_idx] = Some(price_update);
+            }
+        }
+        events.into_iter().flatten().collect()
+    }
+
+    pub(crate) fn activity(&self) -> &Activity {
+        match self {
+            Self::BidPlacedEvent((_, activity))
+            | Self::BidFilledEvent((_, activity))
+            | Self::BidCancelledEvent((_, activity))
+            | Self::AskPlacedEvent((_, activity))
+            | Self::AskFilledEvent((_, activity))
+            | Self::AskCancelledEvent((_, activity))
+            | Self::CollectionBidPlacedEvent((_, activity))
+            | Self::CollectionBidFilledEvent((_, _, activity))
+            | Self::CollectionBidCancelledEvent((_, activity))
+            | Self::AskPriceUpdatedEvent((_, _, activity))
+            | Self::BidPriceUpdatedEvent((_, _, activity)) => activity,
+        }
+    }
+
+    fn price_update(cancelled: &Self, placed: &Self) -> Option<Self> {
+        match (cancelled, placed) {
+            (
+                Self::AskCancelledEvent((cancelled_ask, cancelled_activity)),
+                Self::AskPlacedEvent((placed_ask, placed_activity)),
+            ) if cancelled_activity.seller_addr == placed_activity.seller_addr
+                && is_same_nft_in_txn(cancelled_activity, placed_activity) =>
+            {
+                Some(Self::AskPriceUpdatedEvent((
+                    cancelled_ask.clone(),
+                    placed_ask.clone(),
+                    price_update_activity(
+                        cancelled_activity,
+                        placed_activity,
+                        ActivityType::NFTAskPriceUpdated,
+                    ),
+                )))
+            }
+            (
+                Self::BidCancelledEvent((cancelled_bid, cancelled_activity)),
+                Self::BidPlacedEvent((placed_bid, placed_activity)),
+            ) if cancelled_activity.buyer_addr == placed_activity.buyer_addr
+                && is_same_nft_in_txn(cancelled_activity, placed_activity) =>
+            {
+                Some(Self::BidPriceUpdatedEvent((
+                    cancelled_bid.clone(),
+                    placed_bid.clone(),
+                    price_update_activity(
+                        cancelled_activity,
+                        placed_activity,
+                        ActivityType::NFTBidPriceUpdated,
+                    ),
+                )))
+            }
+            _ => None,
+        }
+    }
+}
+
+// The nft_id of a v1 token is its property version, usually 0, so the token is identified by
+// its name and collection as well
+fn is_same_nft_in_txn(cancelled: &Activity, placed: &Activity) -> bool {
+    cancelled.activity_tx_version == placed.activity_tx_version
+        && cancelled.marketplace_addr == placed.marketplace_addr
+        && cancelled.nft_id == placed.nft_id
+        && cancelled.nft_name == placed.nft_name
+        && cancelled.collection_addr == placed.collection_addr
+        && cancelled.collection_creator_addr == placed.collection_creator_addr
+        && cancelled.collection_name == placed.collection_name
+}
+
+/// The placed order's activity, with the cancelled order's price as the previous price.
+fn price_update_activity(
+    cancelled: &Activity,
+    placed: &Activity,
+    activity_type: ActivityType,
+) -> Activity {
+    Activity {
+        activity_type: activity_type as i32,
+        previous_price: Some(cancelled.price),
+        ..placed.clone()
     }
 }
@@ -1,5 +1,9 @@
+pub mod backfill_coordinator;
+pub mod dry_run;
 pub mod event_parsers;
 pub mod extractor;
+pub mod parser_comparison;
 pub mod processor;
+pub mod reindex;
 pub mod storer;
 pub mod storers;
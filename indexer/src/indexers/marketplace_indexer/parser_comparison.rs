@@ -0,0 +1,541 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use ahash::AHashSet;
+use anyhow::Result;
+use aptos_indexer_processor_sdk::{
+    aptos_protos::transaction::v1::{Event as EventPB, Transaction},
+    traits::{async_step::AsyncRunType, AsyncStep, NamedStep, Processable},
+    types::transaction_context::TransactionContext,
+    utils::{convert::standardize_address, errors::ProcessorError},
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::extractor::{successful_transaction_events, ContractEvent};
+
+/// What one build of the parsers made of an event of a tracked contract. Events that no parser
+/// handles are kept with an empty `kind`, so they show up when another build parses them.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ParsedEvent {
+    pub txn_version: i64,
+    pub event_idx: i64,
+    pub event_type: String,
+    // ContractEvent variant, None if the event wasn't parsed
+    pub kind: Option<String>,
+    // Serialized models keyed by model name, e.g. NftAsk and Activity
+    pub models: BTreeMap<String, Value>,
+    // Block time of the transaction in seconds
+    #[serde(default)]
+    pub block_timestamp: i64,
+}
+
+impl ParsedEvent {
+    fn new(
+        txn_version: i64,
+        event_idx: i64,
+        event: &EventPB,
+        parsed: Option<ContractEvent>,
+        block_timestamp: i64,
+    ) -> Self {
+        let (kind, models) = match parsed {
+            Some(parsed) => (Some(kind_name(&parsed).to_string()), model_values(&parsed)),
+            None => (None, BTreeMap::new()),
+        };
+        Self {
+            txn_version,
+            event_idx,
+            event_type: event_type(&event.type_str),
+            kind,
+            models,
+            block_timestamp,
+        }
+    }
+}
+
+/// Parsed events of a version range, written by `parse-events` and compared by
+/// `compare-parsers`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ParsedEvents {
+    pub start_version: u64,
+    pub end_version: u64,
+    pub events: Vec<ParsedEvent>,
+}
+
+// Event type with a padded address and without generic parameters, so builds group events alike
+fn event_type(type_str: &str) -> String {
+    let parts = type_str.split("::").collect::<Vec<_>>();
+    match parts.as_slice() {
+        [addr, module, name, ..] => {
+            let name = name.split('<').next().unwrap_or_default();
+            format!("{}::{}::{}", standardize_address(addr), module, name)
+        }
+        _ => type_str.to_string(),
+    }
+}
+
+fn kind_name(event: &ContractEvent) -> &'static str {
+    match event {
+        ContractEvent::BidPlacedEvent(_) => "BidPlacedEvent",
+        ContractEvent::BidFilledEvent(_) => "BidFilledEvent",
+        ContractEvent::BidCancelledEvent(_) => "BidCancelledEvent",
+        ContractEvent::AskPlacedEvent(_) => "AskPlacedEvent",
+        ContractEvent::AskFilledEvent(_) => "AskFilledEvent",
+        ContractEvent::AskCancelledEvent(_) => "AskCancelledEvent",
+        ContractEvent::CollectionBidPlacedEvent(_) => "CollectionBidPlacedEvent",
+        ContractEvent::CollectionBidFilledEvent(_) => "CollectionBidFilledEvent",
+        ContractEvent::CollectionBidCancelledEvent(_) => "CollectionBidCancelledEvent",
+        ContractEvent::AskPriceUpdatedEvent(_) => "AskPriceUpdatedEvent",
+        ContractEvent::BidPriceUpdatedEvent(_) => "BidPriceUpdatedEvent",
+    }
+}
+
+fn model_values(event: &ContractEvent) -> BTreeMap<String, Value> {
+    let to_value = |model: Result<Value, serde_json::Error>| {
+        model.expect("Marketplace models should serialize to JSON")
+    };
+    let models = match event {
+        ContractEvent::BidPlacedEvent((bid, activity))
+        | ContractEvent::BidFilledEvent((bid, activity))
+        | ContractEvent::BidCancelledEvent((bid, activity)) => vec![
+            ("NftBid", to_value(serde_json::to_value(bid))),
+            ("Activity", to_value(serde_json::to_value(activity))),
+        ],
+        ContractEvent::AskPlacedEvent((ask, activity))
+        | ContractEvent::AskFilledEvent((ask, activity))
+        | ContractEvent::AskCancelledEvent((ask, activity)) => vec![
+            ("NftAsk", to_value(serde_json::to_value(ask))),
+            ("Activity", to_value(serde_json::to_value(activity))),
+        ],
+        ContractEvent::CollectionBidPlacedEvent((bid, activity))
+        | ContractEvent::CollectionBidCancelledEvent((bid, activity)) => vec![
+            ("CollectionBid", to_value(serde_json::to_value(bid))),
+            ("Activity", to_value(serde_json::to_value(activity))),
+        ],
+        ContractEvent::CollectionBidFilledEvent((bid, filled_bid, activity)) => vec![
+            ("CollectionBid", to_value(serde_json::to_value(bid))),
+            (
+                "FilledCollectionBid",
+                to_value(serde_json::to_value(filled_bid)),
+            ),
+            ("Activity", to_value(serde_json::to_value(activity))),
+        ],
+        ContractEvent::AskPriceUpdatedEvent((cancelled_ask, placed_ask, activity)) => vec![
+            (
+                "CancelledNftAsk",
+                to_value(serde_json::to_value(cancelled_ask)),
+            ),
+            ("NftAsk", to_value(serde_json::to_value(placed_ask))),
+            ("Activity", to_value(serde_json::to_value(activity))),
+        ],
+        ContractEvent::BidPriceUpdatedEvent((cancelled_bid, placed_bid, activity)) => vec![
+            (
+                "CancelledNftBid",
+                to_value(serde_json::to_value(cancelled_bid)),
+            ),
+            ("NftBid", to_value(serde_json::to_value(placed_bid))),
+            ("Activity", to_value(serde_json::to_value(activity))),
+        ],
+    };
+    models
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+}
+
+/// Parse every event of the tracked contracts in the transactions with the parsers of this build.
+pub fn parse_transactions(
+    contract_addresses: &AHashSet<String>,
+    transactions: &[Transaction],
+) -> Vec<ParsedEvent> {
+    transactions
+        .iter()
+        .flat_map(|txn| {
+            let txn_version = txn.version as i64;
+            let block_timestamp = txn
+                .timestamp
+                .as_ref()
+                .map(|timestamp| timestamp.seconds)
+                .unwrap_or_default();
+            successful_transaction_events(txn)
+                .iter()
+                .enumerate()
+                .filter(|(_, event)| ContractEvent::is_from_contracts(contract_addresses, event))
+                .map(move |(idx, event)| {
+                    let parsed = ContractEvent::from_event(
+                        contract_addresses,
+                        idx as i64,
+                        event,
+                        txn_version,
+                    );
+                    ParsedEvent::new(txn_version, idx as i64, event, parsed, block_timestamp)
+                })
+        })
+        .collect()
+}
+
+/// Differences between the events parsed by a baseline and a candidate build, per event type.
+#[derive(Debug, Serialize)]
+pub struct ParserComparisonReport {
+    pub start_version: u64,
+    pub end_version: u64,
+    pub event_types: Vec<EventTypeComparison>,
+}
+
+impl ParserComparisonReport {
+    pub fn has_differences(&self) -> bool {
+        self.event_types.iter().any(|event_type| {
+            !event_type.only_baseline.is_empty()
+                || !event_type.only_candidate.is_empty()
+                || !event_type.differences.is_empty()
+        })
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct EventTypeComparison {
+    pub event_type: String,
+    pub matching: usize,
+    // Parsed by the baseline but not by the candidate
+    pub only_baseline: Vec<EventRef>,
+    // Parsed by the candidate but not by the baseline
+    pub only_candidate: Vec<EventRef>,
+    pub differences: Vec<EventDifference>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventRef {
+    pub txn_version: i64,
+    pub event_idx: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EventDifference {
+    pub txn_version: i64,
+    pub event_idx: i64,
+    pub fields: Vec<FieldDifference>,
+}
+
+/// A field that differs, named `<Model>.<field>`, or `kind` and `<Model>` when the event was
+/// parsed as a different variant.
+#[derive(Debug, Serialize)]
+pub struct FieldDifference {
+    pub field: String,
+    pub baseline: Value,
+    pub candidate: Value,
+}
+
+/// Aptos Labs parsers stamp models with the time they run at rather than an on-chain time, so
+/// such a field differs between any two runs. It's the only way a timestamp can be later than
+/// the block.
+fn is_processing_time(field_name: &str, values: [&Value; 2], block_timestamp: i64) -> bool {
+    field_name.ends_with("_timestamp")
+        && values
+            .iter()
+            .all(|value| value.as_i64().is_some_and(|ts| ts > block_timestamp))
+}
+
+fn field_differences(baseline: &ParsedEvent, candidate: &ParsedEvent) -> Vec<FieldDifference> {
+    let mut fields = vec![];
+    // Baselines written before the block time was recorded have none
+    let block_timestamp = baseline.block_timestamp.max(candidate.block_timestamp);
+    if baseline.kind != candidate.kind {
+        fields.push(FieldDifference {
+            field: "kind".to_string(),
+            baseline: baseline.kind.clone().into(),
+            candidate: candidate.kind.clone().into(),
+        });
+    }
+    let model_names = baseline
+        .models
+        .keys()
+        .chain(candidate.models.keys())
+        .collect::<BTreeSet<_>>();
+    for model_name in model_names {
+        match (
+            baseline.models.get(model_name),
+            candidate.models.get(model_name),
+        ) {
+            (Some(Value::Object(baseline_model)), Some(Value::Object(candidate_model))) => {
+                let field_names = baseline_model
+                    .keys()
+                    .chain(candidate_model.keys())
+                    .collect::<BTreeSet<_>>();
+                for field_name in field_names {
+                    let baseline_value = baseline_model.get(field_name).unwrap_or(&Value::Null);
+                    let candidate_value = candidate_model.get(field_name).unwrap_or(&Value::Null);
+                    if baseline_value != candidate_value
+                        && !is_processing_time(
+                            field_name,
+                            [baseline_value, candidate_value],
+                            block_timestamp,
+                        )
+                    {
+                        fields.push(FieldDifference {
+                            field: format!("{}.{}", model_name, field_name),
+                            baseline: baseline_value.clone(),
+                            candidate: candidate_value.clone(),
+                        });
+                    }
+                }
+            }
+            (baseline_model, candidate_model) if baseline_model != candidate_model => {
+                fields.push(FieldDifference {
+                    field: model_name.clone(),
+                    baseline: baseline_model.cloned().unwrap_or(Value::Null),
+                    candidate: candidate_model.cloned().unwrap_or(Value::Null),
+                });
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+fn comparison_for<'a>(
+    event_types: &'a mut BTreeMap<String, EventTypeComparison>,
+    event_type: &str,
+) -> &'a mut EventTypeComparison {
+    event_types
+        .entry(event_type.to_string())
+        .or_insert_with(|| EventTypeComparison {
+            event_type: event_type.to_string(),
+            ..Default::default()
+        })
+}
+
+/// Compare the events two builds parsed from the same transactions, matched by version and
+/// event index.
+pub fn compare_parsed_events(
+    baseline: ParsedEvents,
+    candidate: Vec<ParsedEvent>,
+) -> ParserComparisonReport {
+    let mut candidate_events = candidate
+        .into_iter()
+        .map(|event| ((event.txn_version, event.event_idx), event))
+        .collect::<BTreeMap<_, _>>();
+    let mut event_types = BTreeMap::<String, EventTypeComparison>::new();
+
+    for baseline_event in baseline.events {
+        let event_ref = EventRef {
+            txn_version: baseline_event.txn_version,
+            event_idx: baseline_event.event_idx,
+        };
+        let candidate_event =
+            candidate_events.remove(&(baseline_event.txn_version, baseline_event.event_idx));
+        let candidate_kind = candidate_event
+            .as_ref()
+            .and_then(|event| event.kind.as_ref());
+        let type_comparison = comparison_for(&mut event_types, &baseline_event.event_type);
+        match (&baseline_event.kind, candidate_kind) {
+            (None, None) => {}
+            (Some(_), None) => type_comparison.only_baseline.push(event_ref),
+            (None, Some(_)) => type_comparison.only_candidate.push(event_ref),
+            (Some(_), Some(_)) => {
+                let fields = field_differences(&baseline_event, &candidate_event.unwrap());
+                if fields.is_empty() {
+                    type_comparison.matching += 1;
+                } else {
+                    type_comparison.differences.push(EventDifference {
+                        txn_version: event_ref.txn_version,
+                        event_idx: event_ref.event_idx,
+                        fields,
+                    });
+                }
+            }
+        }
+    }
+    // Events the baseline didn't record at all, e.g. of a contract it didn't track
+    for candidate_event in candidate_events.into_values() {
+        if candidate_event.kind.is_some() {
+            comparison_for(&mut event_types, &candidate_event.event_type)
+                .only_candidate
+                .push(EventRef {
+                    txn_version: candidate_event.txn_version,
+                    event_idx: candidate_event.event_idx,
+                });
+        }
+    }
+
+    ParserComparisonReport {
+        start_version: baseline.start_version,
+        end_version: baseline.end_version,
+        event_types: event_types
+            .into_values()
+            .filter(|comparison| {
+                comparison.matching > 0
+                    || !comparison.only_baseline.is_empty()
+                    || !comparison.only_candidate.is_empty()
+                    || !comparison.differences.is_empty()
+            })
+            .collect(),
+    }
+}
+
+impl fmt::Display for ParserComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Parser comparison of versions [{}, {}]",
+            self.start_version, self.end_version
+        )?;
+        for comparison in &self.event_types {
+            writeln!(
+                f,
+                "{}: {} matching, {} only parsed by baseline, {} only parsed by candidate, {} \
+                 with differences",
+                comparison.event_type,
+                comparison.matching,
+                comparison.only_baseline.len(),
+                comparison.only_candidate.len(),
+                comparison.differences.len()
+            )?;
+            let mut field_counts = BTreeMap::<&str, usize>::new();
+            for difference in &comparison.differences {
+                for field in &difference.fields {
+                    *field_counts.entry(field.field.as_str()).or_default() += 1;
+                }
+            }
+            for (field, count) in field_counts {
+                writeln!(f, "  {}: {} events", field, count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// EventParserStep is a step that parses the events of tracked contracts for a parser comparison.
+pub struct EventParserStep
+where
+    Self: Sized + Send + 'static,
+{
+    contract_addresses: AHashSet<String>,
+}
+
+impl EventParserStep {
+    pub fn new(contract_addresses: Vec<String>) -> Self {
+        Self {
+            contract_addresses: contract_addresses.into_iter().collect(),
+        }
+    }
+}
+
+impl AsyncStep for EventParserStep {}
+
+impl NamedStep for EventParserStep {
+    fn name(&self) -> String {
+        "EventParserStep".to_string()
+    }
+}
+
+#[async_trait]
+impl Processable for EventParserStep {
+    type Input = Vec<Transaction>;
+    type Output = Vec<ParsedEvent>;
+    type RunType = AsyncRunType;
+
+    async fn process(
+        &mut self,
+        item: TransactionContext<Vec<Transaction>>,
+    ) -> Result<Option<TransactionContext<Vec<ParsedEvent>>>, ProcessorError> {
+        Ok(Some(TransactionContext {
+            data: parse_transactions(&self.contract_addresses, &item.data),
+            metadata: item.metadata,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{AptosLabsEventBuilder, TransactionBuilder, TEST_BUYER_ADDR};
+
+    const APTOS_LABS_ADDR: &str =
+        "0x584b50b999c78ade62f8359c91b5165ff390338d45f8e55969a04e65d76258c9";
+    const LISTING_ADDR: &str = "0x4e5d6c7b8a9f0e1d2c3b4a5f6e7d8c9b0a1f2e3d4c5b6a7f8e9d0c1b2a3f4e5d";
+
+    fn parse(transactions: &[Transaction]) -> Vec<ParsedEvent> {
+        let contract_addresses = [APTOS_LABS_ADDR.to_string()].into_iter().collect();
+        parse_transactions(&contract_addresses, transactions)
+    }
+
+    fn baseline(events: Vec<ParsedEvent>) -> ParsedEvents {
+        ParsedEvents {
+            start_version: 1,
+            end_version: 2,
+            events,
+        }
+    }
+
+    #[test]
+    fn test_parser_comparison_reports_differences() {
+        let transactions = vec![
+            TransactionBuilder::new(1)
+                .event(AptosLabsEventBuilder::listing_placed(
+                    APTOS_LABS_ADDR,
+                    LISTING_ADDR,
+                ))
+                .build(),
+            TransactionBuilder::new(2)
+                .event(AptosLabsEventBuilder::listing_filled(
+                    APTOS_LABS_ADDR,
+                    LISTING_ADDR,
+                ))
+                .event(
+                    AptosLabsEventBuilder::listing_cancelled(APTOS_LABS_ADDR, LISTING_ADDR)
+                        .canceled_spelling(),
+                )
+                .build(),
+        ];
+        let mut baseline_events = parse(&transactions);
+        assert_eq!(baseline_events.len(), 3);
+        // Parsed by an earlier run, the processing times it stamped differ from this run's
+        for event in &mut baseline_events {
+            let processing_time = Value::from(event.block_timestamp + 60);
+            event
+                .models
+                .get_mut("Activity")
+                .unwrap()
+                .as_object_mut()
+                .unwrap()
+                .insert("activity_timestamp".to_string(), processing_time);
+        }
+        assert!(
+            !compare_parsed_events(baseline(baseline_events.clone()), parse(&transactions))
+                .has_differences()
+        );
+
+        // Candidate that lost the cancel event, parses a different buyer for the fill and
+        // stamps it with the block time instead of the processing time
+        let mut candidate_events = baseline_events.clone();
+        let block_timestamp = candidate_events[1].block_timestamp;
+        let filled_ask = candidate_events[1]
+            .models
+            .get_mut("NftAsk")
+            .unwrap()
+            .as_object_mut()
+            .unwrap();
+        filled_ask.insert("buyer_addr".to_string(), "0x1".into());
+        filled_ask.insert("order_filled_timestamp".to_string(), block_timestamp.into());
+        candidate_events[2].kind = None;
+        candidate_events[2].models.clear();
+
+        let report = compare_parsed_events(baseline(baseline_events), candidate_events);
+        assert!(report.has_differences());
+        let cancelled = &report.event_types[0];
+        assert!(cancelled.event_type.ends_with("::events::ListingCanceled"));
+        assert_eq!(cancelled.only_baseline.len(), 1);
+        let filled = &report.event_types[1];
+        assert!(filled.event_type.ends_with("::events::ListingFilled"));
+        assert_eq!(filled.differences.len(), 1);
+        let fields = &filled.differences[0].fields;
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].field, "NftAsk.buyer_addr");
+        assert_eq!(fields[0].baseline, Value::from(TEST_BUYER_ADDR));
+        assert_eq!(fields[1].field, "NftAsk.order_filled_timestamp");
+        assert_eq!(report.event_types[2].matching, 1);
+    }
+}
@@ -1,18 +1,25 @@
 use anyhow::Result;
-use aptos_indexer_processor_sdk::{
-    aptos_indexer_transaction_stream::{TransactionStream, TransactionStreamConfig},
-    builder::ProcessorBuilder,
-    common_steps::TransactionStreamStep,
-    traits::IntoRunnableStep,
-};
+use aptos_indexer_processor_sdk::{builder::ProcessorBuilder, traits::IntoRunnableStep};
+use std::time::Duration;
 
-use super::{extractor::Extractor, storer::Storer};
+use super::{
+    dry_run::{DryRunReport, DryRunSession},
+    extractor::Extractor,
+    parser_comparison::{EventParserStep, ParsedEvent},
+    storer::Storer,
+};
 use crate::{
     config::indexer_processor_config::{CustomConfig, IndexerProcessorConfig},
     utils::{
-        chain_id::check_or_update_chain_id, database_connection::new_db_pool,
-        database_utils::ArcDbPool, latest_processed_version_tracker::LatestVersionProcessedTracker,
+        chain_id::check_or_update_chain_id,
+        database_connection::new_db_pool,
+        database_utils::ArcDbPool,
+        latest_processed_version_tracker::LatestVersionProcessedTracker,
+        shutdown::is_shutdown_requested,
         starting_version::get_starting_version,
+        tracked_contracts::{TrackedContractKind, TrackedContracts},
+        transaction_filter::{TransactionFilter, TransactionFilterStep},
+        transaction_source::TransactionSourceStep,
     },
 };
 
@@ -24,7 +31,7 @@ pub struct MarketplaceProcessor {
 impl MarketplaceProcessor {
     pub async fn new(config: IndexerProcessorConfig) -> Result<Self> {
         let conn_pool = new_db_pool(
-            &config.db_config.postgres_connection_string,
+            &config.db_config.connection_string()?,
             config.db_config.db_pool_size,
         )
         .await;
@@ -35,6 +42,12 @@ impl MarketplaceProcessor {
         })
     }
 
+    /// Storer with the chunk sizes of the pipeline config.
+    pub fn storer(&self) -> Storer {
+        Storer::new(self.db_pool.clone())
+            .with_per_table_chunk_sizes(self.config.pipeline_config.per_table_chunk_sizes.clone())
+    }
+
     pub async fn run_processor(self) -> Result<()> {
         // Merge the starting version from config and the latest processed version from the DB
         let starting_version = get_starting_version(&self.config, self.db_pool.clone()).await?;
@@ -46,40 +59,93 @@ impl MarketplaceProcessor {
         );
 
         // Check and update the ledger chain id to ensure we're indexing the correct chain
-        let grpc_chain_id = TransactionStream::new(self.config.transaction_stream_config.clone())
-            .await?
-            .get_chain_id()
-            .await?;
-        check_or_update_chain_id(grpc_chain_id as i64, self.db_pool.clone()).await?;
+        if let Some(chain_id) = TransactionSourceStep::get_chain_id(&self.config).await? {
+            check_or_update_chain_id(chain_id as i64, self.db_pool.clone()).await?;
+        }
 
-        // Define processor steps
-        let transaction_stream = TransactionStreamStep::new(TransactionStreamConfig {
-            starting_version: Some(starting_version),
-            ..self.config.transaction_stream_config
-        })
-        .await?;
-        let events_extractor = Extractor::new(match self.config.custom_config {
-            CustomConfig::MarketplaceIndexer(contract_addresses) => contract_addresses,
-            _ => {
-                return Err(anyhow::anyhow!("Invalid custom config"));
-            }
-        });
-        let events_storer = Storer::new(self.db_pool.clone());
-        let version_tracker = LatestVersionProcessedTracker::new(
-            self.config.db_config,
+        self.run_pipeline(
             starting_version,
+            self.config.transaction_stream_config.request_ending_version,
             self.config.processor_config.name().to_string(),
         )
-        .await?;
+        .await
+    }
+
+    /// Run the processor steps over [starting_version, request_ending_version] and record
+    /// progress in processor_status under tracker_name. Returns when the stream ends.
+    pub async fn run_pipeline(
+        &self,
+        starting_version: u64,
+        request_ending_version: Option<u64>,
+        tracker_name: String,
+    ) -> Result<()> {
+        let transaction_stream =
+            TransactionSourceStep::new(&self.config, starting_version, request_ending_version)
+                .await?;
+        self.run_pipeline_from(transaction_stream, starting_version, tracker_name)
+            .await
+    }
+
+    /// Like `run_pipeline`, with the transactions read by `transaction_stream`.
+    pub async fn run_pipeline_from(
+        &self,
+        transaction_stream: TransactionSourceStep,
+        starting_version: u64,
+        tracker_name: String,
+    ) -> Result<()> {
+        // Define processor steps
+        let tracked_contracts =
+            TrackedContracts::watch_if_configured(&self.config, self.db_pool.clone()).await?;
+        let transaction_filter =
+            TransactionFilterStep::new(TransactionFilter::new(&self.config.custom_config))
+                .with_tracked_contracts(tracked_contracts.clone())
+                .with_activations(
+                    self.db_pool.clone(),
+                    vec![(tracker_name.clone(), TrackedContractKind::Marketplace)],
+                );
+        let events_extractor = Extractor::new(
+            match &self.config.custom_config {
+                CustomConfig::MarketplaceIndexer(contract_addresses) => contract_addresses.clone(),
+                _ => {
+                    return Err(anyhow::anyhow!(
+                        "Invalid custom config, expected marketplace_indexer, run validate-config to check the config"
+                    ));
+                }
+            },
+            self.config.archive_raw_events,
+        )
+        .with_tracked_contracts(tracked_contracts);
+        let events_storer = self.storer();
+        let version_tracker = LatestVersionProcessedTracker::new_with_pool(
+            self.db_pool.clone(),
+            starting_version,
+            tracker_name,
+        )
+        .with_poll_interval(Duration::from_secs(
+            self.config
+                .pipeline_config
+                .processor_status_update_interval_secs,
+        ));
+        let channel_sizes = &self.config.pipeline_config.channel_sizes;
 
         // Connect processor steps together
         let (_, buffer_receiver) = ProcessorBuilder::new_with_inputless_first_step(
             transaction_stream.into_runnable_step(),
         )
-        .connect_to(events_extractor.into_runnable_step(), 10)
-        .connect_to(events_storer.into_runnable_step(), 10)
-        .connect_to(version_tracker.into_runnable_step(), 10)
-        .end_and_return_output_receiver(10);
+        .connect_to(
+            transaction_filter.into_runnable_step(),
+            channel_sizes.extractor,
+        )
+        .connect_to(
+            events_extractor.into_runnable_step(),
+            channel_sizes.extractor,
+        )
+        .connect_to(events_storer.into_runnable_step(), channel_sizes.storer)
+        .connect_to(
+            version_tracker.into_runnable_step(),
+            channel_sizes.version_tracker,
+        )
+        .end_and_return_output_receiver(channel_sizes.output);
 
         // (Optional) Parse the results
         loop {
@@ -94,6 +160,10 @@ impl MarketplaceProcessor {
                         txn_context.metadata.end_version,
                     );
                 }
+                Err(_) if is_shutdown_requested() => {
+                    tracing::info!("Finished in-flight batches after shutdown");
+                    return Ok(());
+                }
                 Err(_) => {
                     tracing::error!("Channel is closed");
                     return Ok(());
@@ -101,4 +171,162 @@ impl MarketplaceProcessor {
             }
         }
     }
+
+    /// Run the extractor and storer over [starting_version, ending_version] for the given
+    /// contract addresses without recording progress, used to re-derive a range that was
+    /// already processed.
+    pub async fn replay_range(
+        &self,
+        starting_version: u64,
+        ending_version: u64,
+        contract_addresses: Vec<String>,
+    ) -> Result<()> {
+        let transaction_stream =
+            TransactionSourceStep::new(&self.config, starting_version, Some(ending_version))
+                .await?;
+        let transaction_filter = TransactionFilterStep::new(TransactionFilter::new(
+            &CustomConfig::MarketplaceIndexer(contract_addresses.clone()),
+        ));
+        let events_extractor = Extractor::new(contract_addresses, self.config.archive_raw_events);
+        let events_storer = self.storer();
+        let channel_sizes = &self.config.pipeline_config.channel_sizes;
+
+        let (_, buffer_receiver) = ProcessorBuilder::new_with_inputless_first_step(
+            transaction_stream.into_runnable_step(),
+        )
+        .connect_to(
+            transaction_filter.into_runnable_step(),
+            channel_sizes.extractor,
+        )
+        .connect_to(
+            events_extractor.into_runnable_step(),
+            channel_sizes.extractor,
+        )
+        .connect_to(events_storer.into_runnable_step(), channel_sizes.storer)
+        .end_and_return_output_receiver(channel_sizes.output);
+
+        let mut last_version = None;
+        while let Ok(txn_context) = buffer_receiver.recv().await {
+            last_version = Some(txn_context.metadata.end_version);
+            if txn_context.data.events.is_empty() {
+                continue;
+            }
+            tracing::info!(
+                "Replayed events from versions [{:?}, {:?}]",
+                txn_context.metadata.start_version,
+                txn_context.metadata.end_version,
+            );
+        }
+
+        // The channel also closes when a step fails, so make sure the whole range was covered
+        match last_version {
+            Some(version) if version >= ending_version => Ok(()),
+            _ => Err(anyhow::anyhow!(
+                "Replay stopped at version {:?} before reaching {}",
+                last_version,
+                ending_version
+            )),
+        }
+    }
+
+    /// Run the extractor over [starting_version, ending_version] and report what the storers
+    /// would change, without writing to the marketplace tables or processor_status.
+    pub async fn dry_run(
+        &self,
+        starting_version: u64,
+        ending_version: u64,
+        contract_addresses: Vec<String>,
+    ) -> Result<DryRunReport> {
+        let session = DryRunSession::start(&self.config.db_config.connection_string()?).await?;
+        let transaction_stream =
+            TransactionSourceStep::new(&self.config, starting_version, Some(ending_version))
+                .await?;
+        let transaction_filter = TransactionFilterStep::new(TransactionFilter::new(
+            &CustomConfig::MarketplaceIndexer(contract_addresses.clone()),
+        ));
+        // The raw event archive isn't part of the report
+        let events_extractor = Extractor::new(contract_addresses, false);
+        let events_storer = session.storer();
+        let channel_sizes = &self.config.pipeline_config.channel_sizes;
+
+        let (_, buffer_receiver) = ProcessorBuilder::new_with_inputless_first_step(
+            transaction_stream.into_runnable_step(),
+        )
+        .connect_to(
+            transaction_filter.into_runnable_step(),
+            channel_sizes.extractor,
+        )
+        .connect_to(
+            events_extractor.into_runnable_step(),
+            channel_sizes.extractor,
+        )
+        .connect_to(events_storer.into_runnable_step(), channel_sizes.storer)
+        .end_and_return_output_receiver(channel_sizes.output);
+
+        let mut last_version = None;
+        while let Ok(txn_context) = buffer_receiver.recv().await {
+            last_version = Some(txn_context.metadata.end_version);
+        }
+        match last_version {
+            Some(version) if version >= ending_version => {}
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Dry run stopped at version {:?} before reaching {}",
+                    last_version,
+                    ending_version
+                ))
+            }
+        }
+
+        session.finish(starting_version, ending_version).await
+    }
+
+    /// Parse the events of the contract addresses in [starting_version, ending_version] with the
+    /// parsers of this build, for comparing them with another build.
+    pub async fn parse_range(
+        &self,
+        starting_version: u64,
+        ending_version: u64,
+        contract_addresses: Vec<String>,
+    ) -> Result<Vec<ParsedEvent>> {
+        let transaction_stream =
+            TransactionSourceStep::new(&self.config, starting_version, Some(ending_version))
+                .await?;
+        let transaction_filter = TransactionFilterStep::new(TransactionFilter::new(
+            &CustomConfig::MarketplaceIndexer(contract_addresses.clone()),
+        ));
+        let event_parser = EventParserStep::new(contract_addresses);
+        let channel_sizes = &self.config.pipeline_config.channel_sizes;
+
+        let (_, buffer_receiver) = ProcessorBuilder::new_with_inputless_first_step(
+            transaction_stream.into_runnable_step(),
+        )
+        .connect_to(
+            transaction_filter.into_runnable_step(),
+            channel_sizes.extractor,
+        )
+        .connect_to(event_parser.into_runnable_step(), channel_sizes.extractor)
+        .end_and_return_output_receiver(channel_sizes.output);
+
+        let mut events = vec![];
+        let mut last_version = None;
+        while let Ok(txn_context) = buffer_receiver.recv().await {
+            last_version = Some(txn_context.metadata.end_version);
+            events.extend(txn_context.data);
+        }
+        match last_version {
+            Some(version) if version >= ending_version => {}
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Parsing stopped at version {:?} before reaching {}",
+                    last_version,
+                    ending_version
+                ))
+            }
+        }
+
+        // Batches can arrive out of order
+        events.sort_by_key(|event| (event.txn_version, event.event_idx));
+        Ok(events)
+    }
 }
@@ -0,0 +1,505 @@
+use ahash::AHashSet;
+use diesel::{
+    delete, dsl::exists, sql_types::Bool, update, BoolExpressionMethods, ExpressionMethods,
+    IntoSql, PgArrayExpressionMethods, QueryDsl, QueryResult, SelectableHelper,
+};
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+
+use super::{
+    extractor::{ContractEvent, TransactionContextData},
+    storer::Storer,
+};
+use crate::{
+    db_models::raw_marketplace_events::RawMarketplaceEvent,
+    schema::{
+        activities, collection_bids, filled_collection_bids, nft_asks, nft_bids,
+        raw_marketplace_events, sweeps,
+    },
+    utils::{
+        aptos_utils::OrderStatus, database_connection::get_db_connection, database_utils::ArcDbPool,
+    },
+};
+
+// Number of archived events parsed and stored at a time
+const ARCHIVE_REPLAY_PAGE_SIZE: i64 = 10_000;
+
+/// Version range to re-derive, both ends inclusive, optionally limited to one marketplace.
+#[derive(Clone, Debug)]
+pub struct ReindexRange {
+    pub start_version: i64,
+    pub end_version: i64,
+    pub marketplace_addr: Option<String>,
+}
+
+/// Remove everything that was derived from events in the range so it can be replayed.
+/// Activities and filled collection bids are one row per event so they are deleted. Sweeps
+/// the marketplace is part of are deleted too, replaying their fills groups them again.
+/// Order rows merge several events, so only the fields that came from events in the range are
+/// reset, which lets the replayed upserts win the tx version check. Rows left without any event
+/// are deleted.
+pub async fn reset_derived_rows(
+    conn: &mut AsyncPgConnection,
+    range: ReindexRange,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        Box::pin(async move {
+            let (start, end) = (range.start_version, range.end_version);
+            // Empty string never matches a marketplace, so use it to skip the filter
+            let marketplace = range.marketplace_addr.clone().unwrap_or_default();
+            let all_marketplaces = range.marketplace_addr.is_none().into_sql::<Bool>();
+
+            delete(
+                activities::table
+                    .filter(activities::activity_tx_version.between(start, end))
+                    .filter(
+                        activities::marketplace_addr
+                            .eq(&marketplace)
+                            .or(all_marketplaces),
+                    ),
+            )
+            .execute(conn)
+            .await?;
+
+            delete(
+                sweeps::table
+                    .filter(sweeps::txn_version.between(start, end))
+                    .filter(
+                        sweeps::marketplace_addrs
+                            .contains(vec![Some(marketplace.clone())])
+                            .or(all_marketplaces),
+                    ),
+            )
+            .execute(conn)
+            .await?;
+
+            delete(
+                filled_collection_bids::table
+                    .filter(filled_collection_bids::order_filled_tx_version.between(start, end))
+                    .filter(
+                        filled_collection_bids::bid_obj_addr
+                            .eq_any(
+                                collection_bids::table
+                                    .filter(collection_bids::marketplace_addr.eq(&marketplace))
+                                    .select(collection_bids::bid_obj_addr),
+                            )
+                            .or(all_marketplaces),
+                    ),
+            )
+            .execute(conn)
+            .await?;
+
+            // nft asks
+            let asks_in_marketplace = nft_asks::marketplace_addr
+                .eq(&marketplace)
+                .or(all_marketplaces);
+            update(
+                nft_asks::table
+                    .filter(nft_asks::order_placed_tx_version.between(start, end))
+                    .filter(asks_in_marketplace),
+            )
+            .set((
+                nft_asks::order_placed_timestamp.eq(0),
+                nft_asks::order_placed_tx_version.eq(0),
+                nft_asks::order_placed_event_idx.eq(0),
+            ))
+            .execute(conn)
+            .await?;
+            update(
+                nft_asks::table
+                    .filter(nft_asks::order_filled_tx_version.between(start, end))
+                    .filter(asks_in_marketplace),
+            )
+            .set((
+                nft_asks::buyer_addr.eq(""),
+                nft_asks::royalties.eq(0),
+                nft_asks::commission.eq(0),
+                nft_asks::order_filled_timestamp.eq(0),
+                nft_asks::order_filled_tx_version.eq(0),
+                nft_asks::order_filled_event_idx.eq(0),
+            ))
+            .execute(conn)
+            .await?;
+            update(
+                nft_asks::table
+                    .filter(nft_asks::order_cancelled_tx_version.between(start, end))
+                    .filter(asks_in_marketplace),
+            )
+            .set((
+                nft_asks::order_cancelled_timestamp.eq(0),
+                nft_asks::order_cancelled_tx_version.eq(0),
+                nft_asks::order_cancelled_event_idx.eq(0),
+            ))
+            .execute(conn)
+            .await?;
+            delete(
+                nft_asks::table
+                    .filter(nft_asks::order_placed_tx_version.eq(0))
+                    .filter(nft_asks::order_filled_tx_version.eq(0))
+                    .filter(nft_asks::order_cancelled_tx_version.eq(0))
+                    .filter(asks_in_marketplace),
+            )
+            .execute(conn)
+            .await?;
+
+            // nft bids
+            let bids_in_marketplace = nft_bids::marketplace_addr
+                .eq(&marketplace)
+                .or(all_marketplaces);
+            update(
+                nft_bids::table
+                    .filter(nft_bids::order_placed_tx_version.between(start, end))
+                    .filter(bids_in_marketplace),
+            )
+            .set((
+                nft_bids::order_placed_timestamp.eq(0),
+                nft_bids::order_placed_tx_version.eq(0),
+                nft_bids::order_placed_event_idx.eq(0),
+            ))
+            .execute(conn)
+            .await?;
+            update(
+                nft_bids::table
+                    .filter(nft_bids::order_filled_tx_version.between(start, end))
+                    .filter(bids_in_marketplace),
+            )
+            .set((
+                nft_bids::seller_addr.eq(""),
+                nft_bids::royalties.eq(0),
+                nft_bids::commission.eq(0),
+                nft_bids::order_filled_timestamp.eq(0),
+                nft_bids::order_filled_tx_version.eq(0),
+                nft_bids::order_filled_event_idx.eq(0),
+            ))
+            .execute(conn)
+            .await?;
+            update(
+                nft_bids::table
+                    .filter(nft_bids::order_cancelled_tx_version.between(start, end))
+                    .filter(bids_in_marketplace),
+            )
+            .set((
+                nft_bids::order_cancelled_timestamp.eq(0),
+                nft_bids::order_cancelled_tx_version.eq(0),
+                nft_bids::order_cancelled_event_idx.eq(0),
+            ))
+            .execute(conn)
+            .await?;
+            delete(
+                nft_bids::table
+                    .filter(nft_bids::order_placed_tx_version.eq(0))
+                    .filter(nft_bids::order_filled_tx_version.eq(0))
+                    .filter(nft_bids::order_cancelled_tx_version.eq(0))
+                    .filter(bids_in_marketplace),
+            )
+            .execute(conn)
+            .await?;
+
+            // collection bids
+            let collection_bids_in_marketplace = collection_bids::marketplace_addr
+                .eq(&marketplace)
+                .or(all_marketplaces);
+            update(
+                collection_bids::table
+                    .filter(collection_bids::order_placed_tx_version.between(start, end))
+                    .filter(collection_bids_in_marketplace),
+            )
+            .set((
+                collection_bids::order_placed_timestamp.eq(0),
+                collection_bids::order_placed_tx_version.eq(0),
+                collection_bids::order_placed_event_idx.eq(0),
+            ))
+            .execute(conn)
+            .await?;
+            update(
+                collection_bids::table
+                    .filter(collection_bids::latest_order_filled_tx_version.between(start, end))
+                    .filter(collection_bids_in_marketplace),
+            )
+            .set((
+                collection_bids::latest_order_filled_timestamp.eq(0),
+                collection_bids::latest_order_filled_tx_version.eq(0),
+                collection_bids::latest_order_filled_event_idx.eq(0),
+            ))
+            .execute(conn)
+            .await?;
+            update(
+                collection_bids::table
+                    .filter(collection_bids::order_cancelled_tx_version.between(start, end))
+                    .filter(collection_bids_in_marketplace),
+            )
+            .set((
+                collection_bids::order_cancelled_timestamp.eq(0),
+                collection_bids::order_cancelled_tx_version.eq(0),
+                collection_bids::order_cancelled_event_idx.eq(0),
+            ))
+            .execute(conn)
+            .await?;
+            // filled collection bids outside the range still reference the bid
+            delete(
+                collection_bids::table
+                    .filter(collection_bids::order_placed_tx_version.eq(0))
+                    .filter(collection_bids::latest_order_filled_tx_version.eq(0))
+                    .filter(collection_bids::order_cancelled_tx_version.eq(0))
+                    .filter(collection_bids_in_marketplace)
+                    .filter(diesel::dsl::not(exists(
+                        filled_collection_bids::table.filter(
+                            filled_collection_bids::bid_obj_addr.eq(collection_bids::bid_obj_addr),
+                        ),
+                    ))),
+            )
+            .execute(conn)
+            .await?;
+
+            Ok(())
+        })
+    })
+    .await
+}
+
+/// Order status is written by whichever event was stored last, which is only correct when
+/// events are stored in order. After a replay, events outside the range were stored first,
+/// so derive the status from the latest event instead.
+pub async fn recompute_order_status(
+    conn: &mut AsyncPgConnection,
+    range: ReindexRange,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        Box::pin(async move {
+            let (start, end) = (range.start_version, range.end_version);
+            let marketplace = range.marketplace_addr.clone().unwrap_or_default();
+            let all_marketplaces = range.marketplace_addr.is_none().into_sql::<Bool>();
+
+            // nft asks
+            let asks_in_range = nft_asks::order_placed_tx_version
+                .between(start, end)
+                .or(nft_asks::order_filled_tx_version.between(start, end))
+                .or(nft_asks::order_cancelled_tx_version.between(start, end))
+                .and(
+                    nft_asks::marketplace_addr
+                        .eq(&marketplace)
+                        .or(all_marketplaces),
+                );
+            update(
+                nft_asks::table
+                    .filter(asks_in_range)
+                    .filter(nft_asks::order_filled_tx_version.gt(0))
+                    .filter(nft_asks::order_filled_tx_version.ge(nft_asks::order_placed_tx_version))
+                    .filter(
+                        nft_asks::order_filled_tx_version.ge(nft_asks::order_cancelled_tx_version),
+                    ),
+            )
+            .set(nft_asks::order_status.eq(OrderStatus::Filled as i32))
+            .execute(conn)
+            .await?;
+            update(
+                nft_asks::table
+                    .filter(asks_in_range)
+                    .filter(
+                        nft_asks::order_cancelled_tx_version.gt(nft_asks::order_placed_tx_version),
+                    )
+                    .filter(
+                        nft_asks::order_cancelled_tx_version.gt(nft_asks::order_filled_tx_version),
+                    ),
+            )
+            .set(nft_asks::order_status.eq(OrderStatus::Cancelled as i32))
+            .execute(conn)
+            .await?;
+            update(
+                nft_asks::table
+                    .filter(asks_in_range)
+                    .filter(nft_asks::order_placed_tx_version.gt(nft_asks::order_filled_tx_version))
+                    .filter(
+                        nft_asks::order_placed_tx_version.gt(nft_asks::order_cancelled_tx_version),
+                    ),
+            )
+            .set(nft_asks::order_status.eq(OrderStatus::Open as i32))
+            .execute(conn)
+            .await?;
+
+            // nft bids
+            let bids_in_range = nft_bids::order_placed_tx_version
+                .between(start, end)
+                .or(nft_bids::order_filled_tx_version.between(start, end))
+                .or(nft_bids::order_cancelled_tx_version.between(start, end))
+                .and(
+                    nft_bids::marketplace_addr
+                        .eq(&marketplace)
+                        .or(all_marketplaces),
+                );
+            update(
+                nft_bids::table
+                    .filter(bids_in_range)
+                    .filter(nft_bids::order_filled_tx_version.gt(0))
+                    .filter(nft_bids::order_filled_tx_version.ge(nft_bids::order_placed_tx_version))
+                    .filter(
+                        nft_bids::order_filled_tx_version.ge(nft_bids::order_cancelled_tx_version),
+                    ),
+            )
+            .set(nft_bids::order_status.eq(OrderStatus::Filled as i32))
+            .execute(conn)
+            .await?;
+            update(
+                nft_bids::table
+                    .filter(bids_in_range)
+                    .filter(
+                        nft_bids::order_cancelled_tx_version.gt(nft_bids::order_placed_tx_version),
+                    )
+                    .filter(
+                        nft_bids::order_cancelled_tx_version.gt(nft_bids::order_filled_tx_version),
+                    ),
+            )
+            .set(nft_bids::order_status.eq(OrderStatus::Cancelled as i32))
+            .execute(conn)
+            .await?;
+            update(
+                nft_bids::table
+                    .filter(bids_in_range)
+                    .filter(nft_bids::order_placed_tx_version.gt(nft_bids::order_filled_tx_version))
+                    .filter(
+                        nft_bids::order_placed_tx_version.gt(nft_bids::order_cancelled_tx_version),
+                    ),
+            )
+            .set(nft_bids::order_status.eq(OrderStatus::Open as i32))
+            .execute(conn)
+            .await?;
+
+            // Collection bid filled events don't change the status, it's only open or cancelled
+            let collection_bids_in_range = collection_bids::order_placed_tx_version
+                .between(start, end)
+                .or(collection_bids::order_cancelled_tx_version.between(start, end))
+                .and(
+                    collection_bids::marketplace_addr
+                        .eq(&marketplace)
+                        .or(all_marketplaces),
+                );
+            update(
+                collection_bids::table
+                    .filter(collection_bids_in_range)
+                    .filter(
+                        collection_bids::order_cancelled_tx_version
+                            .gt(collection_bids::order_placed_tx_version),
+                    ),
+            )
+            .set(collection_bids::order_status.eq(OrderStatus::Cancelled as i32))
+            .execute(conn)
+            .await?;
+            update(
+                collection_bids::table
+                    .filter(collection_bids_in_range)
+                    .filter(
+                        collection_bids::order_placed_tx_version
+                            .ge(collection_bids::order_cancelled_tx_version),
+                    ),
+            )
+            .set(collection_bids::order_status.eq(OrderStatus::Open as i32))
+            .execute(conn)
+            .await?;
+
+            Ok(())
+        })
+    })
+    .await
+}
+
+/// Load the archived events after `cursor`, the (txn_version, event_idx) of the last event of the
+/// previous page, up to `end_version`. A page has at least `page_size` events unless the archive
+/// runs out, and is extended to the last event of its last transaction, so the events of a
+/// transaction are always replayed together as the extractor sees them.
+pub async fn load_archive_page(
+    conn: &mut AsyncPgConnection,
+    cursor: (i64, i64),
+    end_version: i64,
+    page_size: i64,
+) -> QueryResult<Vec<RawMarketplaceEvent>> {
+    let mut page = raw_marketplace_events::table
+        .filter(raw_marketplace_events::txn_version.le(end_version))
+        .filter(
+            raw_marketplace_events::txn_version.gt(cursor.0).or(
+                raw_marketplace_events::txn_version
+                    .eq(cursor.0)
+                    .and(raw_marketplace_events::event_idx.gt(cursor.1)),
+            ),
+        )
+        .order((
+            raw_marketplace_events::txn_version.asc(),
+            raw_marketplace_events::event_idx.asc(),
+        ))
+        .limit(page_size)
+        .select(RawMarketplaceEvent::as_select())
+        .load::<RawMarketplaceEvent>(conn)
+        .await?;
+    if let Some(last_event) = page.last().filter(|_| page.len() as i64 == page_size) {
+        let rest_of_txn = raw_marketplace_events::table
+            .filter(raw_marketplace_events::txn_version.eq(last_event.txn_version))
+            .filter(raw_marketplace_events::event_idx.gt(last_event.event_idx))
+            .order(raw_marketplace_events::event_idx.asc())
+            .select(RawMarketplaceEvent::as_select())
+            .load::<RawMarketplaceEvent>(conn)
+            .await?;
+        page.extend(rest_of_txn);
+    }
+    Ok(page)
+}
+
+/// Feed archived raw events in the range back through the event parsers and the storer,
+/// without the transaction stream. Returns the number of archived events read, the archive
+/// only has events from versions processed while archive_raw_events was enabled.
+pub async fn replay_from_archive(
+    pool: ArcDbPool,
+    storer: Storer,
+    range: ReindexRange,
+    contract_addresses: Vec<String>,
+) -> anyhow::Result<usize> {
+    let contract_addresses: AHashSet<String> = contract_addresses.into_iter().collect();
+    let mut total_events = 0;
+    // (txn_version, event_idx) of the last event of the previous page
+    let mut cursor = (range.start_version, -1);
+    loop {
+        let page = {
+            let mut conn = get_db_connection(&pool).await?;
+            load_archive_page(
+                &mut conn,
+                cursor,
+                range.end_version,
+                ARCHIVE_REPLAY_PAGE_SIZE,
+            )
+            .await?
+        };
+        let last_event = match page.last() {
+            Some(event) => (event.txn_version, event.event_idx),
+            None => break,
+        };
+
+        // Pages end on a transaction boundary, so price updates are paired like the extractor does
+        let events = ContractEvent::pair_price_updates(
+            page.iter()
+                .filter_map(|raw_event| {
+                    ContractEvent::from_event(
+                        &contract_addresses,
+                        raw_event.event_idx,
+                        &raw_event.to_event(),
+                        raw_event.txn_version,
+                    )
+                })
+                .collect(),
+        );
+        storer
+            .store(TransactionContextData {
+                events,
+                changes: vec![],
+                raw_events: vec![],
+                // The archive has no transaction metadata, rows already there are left in place
+                transactions: vec![],
+            })
+            .await?;
+
+        total_events += page.len();
+        tracing::info!(
+            "Replayed archived events up to version {}, {} events so far",
+            last_event.0,
+            total_events
+        );
+        cursor = last_event;
+    }
+    Ok(total_events)
+}
@@ -19,6 +19,8 @@ use super::{
         collection_bid_cancelled_event_storer::process_collection_bid_cancelled_events,
         collection_bid_filled_event_storer::process_collection_bid_filled_events,
         collection_bid_placed_event_storer::process_collection_bid_placed_events,
+        raw_event_storer::process_raw_events, sweep_storer::process_sweeps,
+        transaction_storer::process_transactions,
     },
 };
 use crate::utils::database_utils::ArcDbPool;
@@ -29,6 +31,8 @@ where
     Self: Sized + Send + 'static,
 {
     pool: ArcDbPool,
+    // Overrides of the default insert chunk size, keyed by table name
+    per_table_chunk_sizes: AHashMap<String, usize>,
 }
 
 impl AsyncStep for Storer {}
@@ -41,22 +45,39 @@ impl NamedStep for Storer {
 
 impl Storer {
     pub fn new(pool: ArcDbPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            per_table_chunk_sizes: AHashMap::new(),
+        }
     }
-}
 
-#[async_trait]
-impl Processable for Storer {
-    type Input = TransactionContextData;
-    type Output = TransactionContextData;
-    type RunType = AsyncRunType;
+    pub fn with_per_table_chunk_sizes(
+        mut self,
+        per_table_chunk_sizes: AHashMap<String, usize>,
+    ) -> Self {
+        self.per_table_chunk_sizes = per_table_chunk_sizes;
+        self
+    }
+
+    /// Write the extracted events to the database, also used to replay archived events
+    /// outside of the processor pipeline.
+    pub async fn store(&self, data: TransactionContextData) -> Result<(), ProcessorError> {
+        let per_table_chunk_sizes = &self.per_table_chunk_sizes;
+
+        process_raw_events(
+            self.pool.clone(),
+            per_table_chunk_sizes.clone(),
+            data.raw_events,
+        )
+        .await?;
+
+        process_transactions(
+            self.pool.clone(),
+            per_table_chunk_sizes.clone(),
+            data.transactions,
+        )
+        .await?;
 
-    async fn process(
-        &mut self,
-        transaction_context_data: TransactionContext<TransactionContextData>,
-    ) -> Result<Option<TransactionContext<TransactionContextData>>, ProcessorError> {
-        let per_table_chunk_sizes: AHashMap<String, usize> = AHashMap::new();
-        let data = transaction_context_data.data.clone();
         let (
             bid_placed_events,
             bid_filled_events,
@@ -107,6 +128,16 @@ impl Processable for Storer {
                     ContractEvent::CollectionBidCancelledEvent(collection_bid) => {
                         collection_bid_cancelled_events.push(collection_bid)
                     }
+                    // Both orders carry the price update activity, inserting it the second time
+                    // does nothing
+                    ContractEvent::AskPriceUpdatedEvent((cancelled_ask, placed_ask, activity)) => {
+                        ask_cancelled_events.push((cancelled_ask, activity.clone()));
+                        ask_placed_events.push((placed_ask, activity));
+                    }
+                    ContractEvent::BidPriceUpdatedEvent((cancelled_bid, placed_bid, activity)) => {
+                        bid_cancelled_events.push((cancelled_bid, activity.clone()));
+                        bid_placed_events.push((placed_bid, activity));
+                    }
                 }
                 (
                     bid_placed_events,
@@ -150,6 +181,15 @@ impl Processable for Storer {
         )
         .await?;
 
+        // Every version with a fill is grouped again, other marketplaces' fills of the version
+        // may be stored already
+        let mut sweep_versions = ask_filled_events
+            .iter()
+            .map(|(_, activity)| activity.activity_tx_version)
+            .collect::<Vec<_>>();
+        sweep_versions.sort_unstable();
+        sweep_versions.dedup();
+
         process_ask_filled_events(
             self.pool.clone(),
             per_table_chunk_sizes.clone(),
@@ -157,6 +197,13 @@ impl Processable for Storer {
         )
         .await?;
 
+        process_sweeps(
+            self.pool.clone(),
+            per_table_chunk_sizes.clone(),
+            sweep_versions,
+        )
+        .await?;
+
         process_ask_cancelled_events(
             self.pool.clone(),
             per_table_chunk_sizes.clone(),
@@ -185,6 +232,21 @@ impl Processable for Storer {
         )
         .await?;
 
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Processable for Storer {
+    type Input = TransactionContextData;
+    type Output = TransactionContextData;
+    type RunType = AsyncRunType;
+
+    async fn process(
+        &mut self,
+        transaction_context_data: TransactionContext<TransactionContextData>,
+    ) -> Result<Option<TransactionContext<TransactionContextData>>, ProcessorError> {
+        self.store(transaction_context_data.data.clone()).await?;
         Ok(Some(transaction_context_data))
     }
 }
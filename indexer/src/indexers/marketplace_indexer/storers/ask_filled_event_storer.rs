@@ -11,8 +11,7 @@ use crate::{
     db_models::{activities::Activity, nft_asks::NftAsk},
     schema::{activities, nft_asks},
     utils::{
-        database_connection::get_db_connection,
-        database_execution::handle_db_execution,
+        database_execution::{execute_with_retries, handle_db_execution},
         database_utils::{get_config_table_chunk_size, ArcDbPool},
     },
 };
@@ -81,10 +80,7 @@ pub async fn process_ask_filled_events(
             let pool = pool.clone();
             let items = chunk.to_vec();
             tokio::spawn(async move {
-                let conn = &mut get_db_connection(&pool).await.expect(
-                    "Failed to get connection from pool while processing ask filled events",
-                );
-                execute_sql(conn, items).await
+                execute_with_retries(&pool, |conn| Box::pin(execute_sql(conn, items.clone()))).await
             })
         })
         .collect::<Vec<_>>();
@@ -7,3 +7,6 @@ pub mod bid_placed_event_storer;
 pub mod collection_bid_cancelled_event_storer;
 pub mod collection_bid_filled_event_storer;
 pub mod collection_bid_placed_event_storer;
+pub mod raw_event_storer;
+pub mod sweep_storer;
+pub mod transaction_storer;
@@ -0,0 +1,63 @@
+use ahash::AHashMap;
+use anyhow::Result;
+use aptos_indexer_processor_sdk::utils::errors::ProcessorError;
+use diesel::{insert_into, QueryResult};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::{
+    db_models::raw_marketplace_events::RawMarketplaceEvent,
+    schema::raw_marketplace_events,
+    utils::{
+        database_execution::{execute_with_retries, handle_db_execution},
+        database_utils::{get_config_table_chunk_size, ArcDbPool},
+    },
+};
+
+async fn execute_sql(
+    conn: &mut AsyncPgConnection,
+    items_to_insert: Vec<RawMarketplaceEvent>,
+) -> QueryResult<()> {
+    // Raw events never change once emitted
+    insert_into(raw_marketplace_events::table)
+        .values(items_to_insert)
+        .on_conflict((
+            raw_marketplace_events::txn_version,
+            raw_marketplace_events::event_idx,
+        ))
+        .do_nothing()
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn process_raw_events(
+    pool: ArcDbPool,
+    per_table_chunk_sizes: AHashMap<String, usize>,
+    events: Vec<RawMarketplaceEvent>,
+) -> Result<(), ProcessorError> {
+    let chunk_size = get_config_table_chunk_size::<RawMarketplaceEvent>(
+        "raw_marketplace_events",
+        &per_table_chunk_sizes,
+    );
+    let tasks = events
+        .chunks(chunk_size)
+        .map(|chunk| {
+            let pool = pool.clone();
+            let items = chunk.to_vec();
+            tokio::spawn(async move {
+                execute_with_retries(&pool, |conn| Box::pin(execute_sql(conn, items.clone()))).await
+            })
+        })
+        .collect::<Vec<_>>();
+
+    match handle_db_execution(tasks).await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            println!(
+                "error writing raw events to db: {:?} with error: {:?}",
+                events, e
+            );
+            Err(e)
+        }
+    }
+}
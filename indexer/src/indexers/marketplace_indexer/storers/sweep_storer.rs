@@ -0,0 +1,114 @@
+use ahash::AHashMap;
+use anyhow::Result;
+use aptos_indexer_processor_sdk::utils::errors::ProcessorError;
+use diesel::{
+    sql_query,
+    sql_types::{Array, BigInt, Integer},
+    QueryResult,
+};
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+
+use crate::{
+    db_models::sweeps::Sweep,
+    utils::{
+        aptos_utils::ActivityType,
+        database_execution::{execute_with_retries, handle_db_execution},
+        database_utils::{get_config_table_chunk_size, ArcDbPool},
+    },
+};
+
+// Processors of different marketplaces can store fills of the same transaction at once. The
+// lock keeps them from regrouping its sweeps concurrently, which would insert the same sweep
+// twice. Locks are taken in version order so two processors can't deadlock.
+const LOCK_VERSIONS_SQL: &str = "\
+    SELECT pg_advisory_xact_lock(txn_version) \
+    FROM (SELECT unnest($1::BIGINT[]) AS txn_version ORDER BY 1) AS versions";
+
+// Sweeps are grouped from the stored activities rather than the events of the batch, so the
+// fills of every marketplace count even when only one marketplace is re-indexed. v1 tokens have
+// no collection address and are grouped by creator and name, fills without any of them (e.g.
+// Tradeport v2) can't be told apart and are left out.
+const INSERT_SWEEPS_SQL: &str = "\
+    INSERT INTO sweeps (txn_version, event_idx, buyer_addr, collection_addr, \
+        collection_creator_addr, collection_name, nft_count, total_price, marketplace_addrs, \
+        sweep_timestamp) \
+    SELECT activity_tx_version, min(activity_event_idx), buyer_addr, collection_addr, \
+        collection_creator_addr, collection_name, count(*), sum(price)::BIGINT, \
+        array_agg(DISTINCT marketplace_addr ORDER BY marketplace_addr), max(activity_timestamp) \
+    FROM activities \
+    WHERE activity_tx_version = ANY($1) AND activity_type = $2 \
+        AND (collection_addr <> '' OR (collection_creator_addr <> '' AND collection_name <> '')) \
+    GROUP BY activity_tx_version, buyer_addr, collection_addr, collection_creator_addr, \
+        collection_name \
+    HAVING count(*) > 1";
+
+const LINK_ACTIVITIES_SQL: &str = "\
+    UPDATE activities a SET sweep_event_idx = (\
+        SELECT s.event_idx FROM sweeps s \
+        WHERE s.txn_version = a.activity_tx_version AND s.buyer_addr = a.buyer_addr \
+            AND s.collection_addr = a.collection_addr \
+            AND s.collection_creator_addr = a.collection_creator_addr \
+            AND s.collection_name = a.collection_name) \
+    WHERE a.activity_tx_version = ANY($1) AND a.activity_type = $2 \
+        AND (a.collection_addr <> '' \
+            OR (a.collection_creator_addr <> '' AND a.collection_name <> ''))";
+
+async fn execute_sql(conn: &mut AsyncPgConnection, txn_versions: Vec<i64>) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        Box::pin(async move {
+            sql_query(LOCK_VERSIONS_SQL)
+                .bind::<Array<BigInt>, _>(&txn_versions)
+                .execute(conn)
+                .await?;
+            // Recompute the sweeps of the versions from scratch, fills may have been added or
+            // re-indexed since they were last grouped
+            sql_query("DELETE FROM sweeps WHERE txn_version = ANY($1)")
+                .bind::<Array<BigInt>, _>(&txn_versions)
+                .execute(conn)
+                .await?;
+            sql_query(INSERT_SWEEPS_SQL)
+                .bind::<Array<BigInt>, _>(&txn_versions)
+                .bind::<Integer, _>(ActivityType::NFTAskFilled as i32)
+                .execute(conn)
+                .await?;
+            sql_query(LINK_ACTIVITIES_SQL)
+                .bind::<Array<BigInt>, _>(&txn_versions)
+                .bind::<Integer, _>(ActivityType::NFTAskFilled as i32)
+                .execute(conn)
+                .await?;
+            Ok(())
+        })
+    })
+    .await
+}
+
+/// Group the ask fills stored for the versions into sweeps, several NFTs of one collection
+/// bought by the same buyer in one transaction, and link the fills to their sweep.
+pub async fn process_sweeps(
+    pool: ArcDbPool,
+    per_table_chunk_sizes: AHashMap<String, usize>,
+    txn_versions: Vec<i64>,
+) -> Result<(), ProcessorError> {
+    let chunk_size = get_config_table_chunk_size::<Sweep>("sweeps", &per_table_chunk_sizes);
+    let tasks = txn_versions
+        .chunks(chunk_size)
+        .map(|chunk| {
+            let pool = pool.clone();
+            let items = chunk.to_vec();
+            tokio::spawn(async move {
+                execute_with_retries(&pool, |conn| Box::pin(execute_sql(conn, items.clone()))).await
+            })
+        })
+        .collect::<Vec<_>>();
+
+    match handle_db_execution(tasks).await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            println!(
+                "error writing sweeps to db: {:?} with error: {:?}",
+                txn_versions, e
+            );
+            Err(e)
+        }
+    }
+}
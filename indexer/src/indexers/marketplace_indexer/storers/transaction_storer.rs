@@ -0,0 +1,60 @@
+use ahash::AHashMap;
+use anyhow::Result;
+use aptos_indexer_processor_sdk::utils::errors::ProcessorError;
+use diesel::{insert_into, QueryResult};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::{
+    db_models::transactions::MarketplaceTransaction,
+    schema::transactions,
+    utils::{
+        database_execution::{execute_with_retries, handle_db_execution},
+        database_utils::{get_config_table_chunk_size, ArcDbPool},
+    },
+};
+
+async fn execute_sql(
+    conn: &mut AsyncPgConnection,
+    items_to_insert: Vec<MarketplaceTransaction>,
+) -> QueryResult<()> {
+    // A committed transaction never changes
+    insert_into(transactions::table)
+        .values(items_to_insert)
+        .on_conflict(transactions::txn_version)
+        .do_nothing()
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn process_transactions(
+    pool: ArcDbPool,
+    per_table_chunk_sizes: AHashMap<String, usize>,
+    txns: Vec<MarketplaceTransaction>,
+) -> Result<(), ProcessorError> {
+    let chunk_size = get_config_table_chunk_size::<MarketplaceTransaction>(
+        "transactions",
+        &per_table_chunk_sizes,
+    );
+    let tasks = txns
+        .chunks(chunk_size)
+        .map(|chunk| {
+            let pool = pool.clone();
+            let items = chunk.to_vec();
+            tokio::spawn(async move {
+                execute_with_retries(&pool, |conn| Box::pin(execute_sql(conn, items.clone()))).await
+            })
+        })
+        .collect::<Vec<_>>();
+
+    match handle_db_execution(tasks).await {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            println!(
+                "error writing transactions to db: {:?} with error: {:?}",
+                txns, e
+            );
+            Err(e)
+        }
+    }
+}
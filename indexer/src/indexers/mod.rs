@@ -1,2 +1,3 @@
+pub mod composite_indexer;
 pub mod contract_upgrade_indexer;
 pub mod marketplace_indexer;
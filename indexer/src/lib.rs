@@ -1,8 +1,11 @@
+pub mod commands;
 pub mod config;
 pub mod db_models;
 pub mod health_check_server;
 pub mod indexers;
 pub mod onchain_events;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 pub mod utils;
 
 #[path = "db_migrations/schema.rs"]
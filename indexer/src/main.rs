@@ -1,10 +1,15 @@
 use anyhow::Result;
-use aptos_indexer_processor_sdk_server_framework::ServerArgs;
+use aptos_indexer_processor_sdk_server_framework::{
+    load, setup_logging, setup_panic_handler, GenericConfig, ServerArgs,
+};
 use clap::Parser;
 use indexer::{
+    commands::Command,
     config::indexer_processor_config::IndexerProcessorConfig,
     health_check_server::{self, HealthServerConfig},
+    utils::shutdown,
 };
+use std::path::PathBuf;
 
 #[cfg(unix)]
 #[global_allocator]
@@ -14,13 +19,48 @@ async fn run_health_server() -> Result<()> {
     health_check_server::run(HealthServerConfig::default()).await
 }
 
-async fn run_indexer() -> Result<()> {
-    ServerArgs::parse()
-        .run::<IndexerProcessorConfig>(tokio::runtime::Handle::current())
-        .await
+#[derive(Parser)]
+struct Cli {
+    #[clap(short, long, value_parser)]
+    config_path: PathBuf,
+    // Without a subcommand the processor is started
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+async fn run_health_server_and_indexer(config_path: PathBuf) -> Result<()> {
+    // On SIGTERM the processor stops reading transactions and finishes its in-flight batches
+    tokio::spawn(async {
+        if let Err(e) = shutdown::request_shutdown_on_signal().await {
+            tracing::error!("Failed to listen for shutdown signals: {:?}", e);
+        }
+    });
+    let run_indexer = async {
+        ServerArgs { config_path }
+            .run::<IndexerProcessorConfig>(tokio::runtime::Handle::current())
+            .await?;
+        if !shutdown::is_shutdown_requested() {
+            // Keep serving the health check after the processor reached its ending version
+            std::future::pending::<()>().await;
+        }
+        Ok(())
+    };
+    tokio::select! {
+        res = run_health_server() => res,
+        res = run_indexer => res,
+    }
+}
+
+async fn run_command(config_path: PathBuf, command: Command) -> Result<()> {
+    // ServerArgs::run does this for the processor
+    setup_logging();
+    setup_panic_handler();
+    let config = load::<GenericConfig<IndexerProcessorConfig>>(&config_path)?;
+    command.run(config.server_config).await
 }
 
 fn main() -> Result<()> {
+    let cli = Cli::parse();
     let num_cpus = num_cpus::get();
     let worker_threads = (num_cpus).max(16);
 
@@ -32,7 +72,9 @@ fn main() -> Result<()> {
         .build()
         .unwrap()
         .block_on(async {
-            tokio::try_join!(run_health_server(), run_indexer())?;
-            Ok(())
+            match cli.command {
+                Some(command) => run_command(cli.config_path, command).await,
+                None => run_health_server_and_indexer(cli.config_path).await,
+            }
         })
 }
@@ -99,6 +99,7 @@ impl CollectionBidPlacedEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::CollectionBidPlaced as i32,
+                previous_price: None,
             },
         )
     }
@@ -172,6 +173,7 @@ impl CollectionBidFilledEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::CollectionBidFilled as i32,
+                previous_price: None,
             },
         )
     }
@@ -232,6 +234,7 @@ impl CollectionBidCancelledEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::CollectionBidCancelled as i32,
+                previous_price: None,
             },
         )
     }
@@ -112,6 +112,7 @@ impl AskPlacedEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::NFTAskPlaced as i32,
+                previous_price: None,
             },
         )
     }
@@ -177,6 +178,7 @@ impl AskFilledEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::NFTAskFilled as i32,
+                previous_price: None,
             },
         )
     }
@@ -242,6 +244,7 @@ impl AskCancelledEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::NFTAskCancelled as i32,
+                previous_price: None,
             },
         )
     }
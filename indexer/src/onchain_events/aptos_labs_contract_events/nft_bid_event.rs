@@ -98,6 +98,7 @@ impl BidPlacedEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::NFTBidPlaced as i32,
+                previous_price: None,
             },
         )
     }
@@ -163,6 +164,7 @@ impl BidFilledEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::NFTBidFilled as i32,
+                previous_price: None,
             },
         )
     }
@@ -228,6 +230,7 @@ impl BidCancelledEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::NFTBidCancelled as i32,
+                previous_price: None,
             },
         )
     }
@@ -92,6 +92,7 @@ impl TradeportV2CollectionBidPlacedEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::CollectionBidPlaced as i32,
+                previous_price: None,
             },
         )
     }
@@ -160,6 +161,7 @@ impl TradeportV2CollectionBidFilledEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::CollectionBidFilled as i32,
+                previous_price: None,
             },
         )
     }
@@ -216,6 +218,7 @@ impl TradeportV2CollectionBidCancelledEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::CollectionBidCancelled as i32,
+                previous_price: None,
             },
         )
     }
@@ -95,6 +95,7 @@ impl TradeportV2AskPlacedEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::NFTAskPlaced as i32,
+                previous_price: None,
             },
         )
     }
@@ -155,6 +156,7 @@ impl TradeportV2AskFilledEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::NFTAskFilled as i32,
+                previous_price: None,
             },
         )
     }
@@ -215,6 +217,7 @@ impl TradeportV2AskCancelledEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::NFTAskCancelled as i32,
+                previous_price: None,
             },
         )
     }
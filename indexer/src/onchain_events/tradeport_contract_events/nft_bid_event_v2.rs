@@ -93,6 +93,7 @@ impl TradeportV2BidPlacedEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::NFTAskPlaced as i32,
+                previous_price: None,
             },
         )
     }
@@ -153,6 +154,7 @@ impl TradeportV2BidFilledEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::NFTBidFilled as i32,
+                previous_price: None,
             },
         )
     }
@@ -213,6 +215,7 @@ impl TradeportV2BidCancelledEventOnChain {
                 activity_tx_version: tx_version,
                 activity_event_idx: event_idx,
                 activity_type: ActivityType::NFTBidCancelled as i32,
+                previous_price: None,
             },
         )
     }
@@ -0,0 +1,286 @@
+use aptos_indexer_processor_sdk::aptos_protos::transaction::v1::Event as EventPB;
+use serde_json::{json, Value};
+
+use super::{transaction_builder::module_event, TestToken, TEST_BUYER_ADDR, TEST_SELLER_ADDR};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AptosLabsEventKind {
+    ListingPlaced,
+    ListingFilled,
+    ListingCancelled,
+    TokenOfferPlaced,
+    TokenOfferFilled,
+    TokenOfferCancelled,
+    CollectionOfferPlaced,
+    CollectionOfferFilled,
+    CollectionOfferCancelled,
+}
+
+impl AptosLabsEventKind {
+    pub const ALL: [Self; 9] = [
+        Self::ListingPlaced,
+        Self::ListingFilled,
+        Self::ListingCancelled,
+        Self::TokenOfferPlaced,
+        Self::TokenOfferFilled,
+        Self::TokenOfferCancelled,
+        Self::CollectionOfferPlaced,
+        Self::CollectionOfferFilled,
+        Self::CollectionOfferCancelled,
+    ];
+
+    fn event_name(&self) -> &'static str {
+        match self {
+            Self::ListingPlaced => "ListingPlaced",
+            Self::ListingFilled => "ListingFilled",
+            Self::ListingCancelled => "ListingCancelled",
+            Self::TokenOfferPlaced => "TokenOfferPlaced",
+            Self::TokenOfferFilled => "TokenOfferFilled",
+            Self::TokenOfferCancelled => "TokenOfferCancelled",
+            Self::CollectionOfferPlaced => "CollectionOfferPlaced",
+            Self::CollectionOfferFilled => "CollectionOfferFilled",
+            Self::CollectionOfferCancelled => "CollectionOfferCancelled",
+        }
+    }
+}
+
+/// Builds an event of the Aptos Labs marketplace contract (`<marketplace>::events::*`).
+#[derive(Clone, Debug)]
+pub struct AptosLabsEventBuilder {
+    kind: AptosLabsEventKind,
+    marketplace_addr: String,
+    // Listing, token offer or collection offer object depending on the kind
+    order_addr: String,
+    seller: String,
+    purchaser: String,
+    price: u64,
+    commission: u64,
+    royalties: u64,
+    token_amount: u64,
+    auction: bool,
+    // Emit `*Canceled` instead of `*Cancelled`, older contract versions use that spelling
+    canceled_spelling: bool,
+    token: TestToken,
+}
+
+impl AptosLabsEventBuilder {
+    pub fn new(kind: AptosLabsEventKind, marketplace_addr: &str, order_addr: &str) -> Self {
+        Self {
+            kind,
+            marketplace_addr: marketplace_addr.to_string(),
+            order_addr: order_addr.to_string(),
+            seller: TEST_SELLER_ADDR.to_string(),
+            purchaser: TEST_BUYER_ADDR.to_string(),
+            price: 100_000_000,
+            commission: 0,
+            royalties: 0,
+            token_amount: 1,
+            auction: false,
+            canceled_spelling: false,
+            token: TestToken::default(),
+        }
+    }
+
+    pub fn listing_placed(marketplace_addr: &str, listing_addr: &str) -> Self {
+        Self::new(
+            AptosLabsEventKind::ListingPlaced,
+            marketplace_addr,
+            listing_addr,
+        )
+    }
+
+    pub fn listing_filled(marketplace_addr: &str, listing_addr: &str) -> Self {
+        Self::new(
+            AptosLabsEventKind::ListingFilled,
+            marketplace_addr,
+            listing_addr,
+        )
+    }
+
+    pub fn listing_cancelled(marketplace_addr: &str, listing_addr: &str) -> Self {
+        Self::new(
+            AptosLabsEventKind::ListingCancelled,
+            marketplace_addr,
+            listing_addr,
+        )
+    }
+
+    pub fn token_offer_placed(marketplace_addr: &str, offer_addr: &str) -> Self {
+        Self::new(
+            AptosLabsEventKind::TokenOfferPlaced,
+            marketplace_addr,
+            offer_addr,
+        )
+    }
+
+    pub fn token_offer_filled(marketplace_addr: &str, offer_addr: &str) -> Self {
+        Self::new(
+            AptosLabsEventKind::TokenOfferFilled,
+            marketplace_addr,
+            offer_addr,
+        )
+    }
+
+    pub fn token_offer_cancelled(marketplace_addr: &str, offer_addr: &str) -> Self {
+        Self::new(
+            AptosLabsEventKind::TokenOfferCancelled,
+            marketplace_addr,
+            offer_addr,
+        )
+    }
+
+    pub fn collection_offer_placed(marketplace_addr: &str, offer_addr: &str) -> Self {
+        Self::new(
+            AptosLabsEventKind::CollectionOfferPlaced,
+            marketplace_addr,
+            offer_addr,
+        )
+    }
+
+    pub fn collection_offer_filled(marketplace_addr: &str, offer_addr: &str) -> Self {
+        Self::new(
+            AptosLabsEventKind::CollectionOfferFilled,
+            marketplace_addr,
+            offer_addr,
+        )
+    }
+
+    pub fn collection_offer_cancelled(marketplace_addr: &str, offer_addr: &str) -> Self {
+        Self::new(
+            AptosLabsEventKind::CollectionOfferCancelled,
+            marketplace_addr,
+            offer_addr,
+        )
+    }
+
+    pub fn seller(mut self, seller: &str) -> Self {
+        self.seller = seller.to_string();
+        self
+    }
+
+    pub fn purchaser(mut self, purchaser: &str) -> Self {
+        self.purchaser = purchaser.to_string();
+        self
+    }
+
+    pub fn price(mut self, price: u64) -> Self {
+        self.price = price;
+        self
+    }
+
+    pub fn commission(mut self, commission: u64) -> Self {
+        self.commission = commission;
+        self
+    }
+
+    pub fn royalties(mut self, royalties: u64) -> Self {
+        self.royalties = royalties;
+        self
+    }
+
+    /// Number of tokens a collection offer is for, or has left when cancelled.
+    pub fn token_amount(mut self, token_amount: u64) -> Self {
+        self.token_amount = token_amount;
+        self
+    }
+
+    pub fn auction(mut self) -> Self {
+        self.auction = true;
+        self
+    }
+
+    pub fn canceled_spelling(mut self) -> Self {
+        self.canceled_spelling = true;
+        self
+    }
+
+    pub fn token(mut self, token: TestToken) -> Self {
+        self.token = token;
+        self
+    }
+
+    fn data(&self) -> Value {
+        let listing_type = if self.auction {
+            "auction"
+        } else {
+            "fixed price"
+        };
+        let price = self.price.to_string();
+        match self.kind {
+            AptosLabsEventKind::ListingPlaced | AptosLabsEventKind::ListingCancelled => json!({
+                "type": listing_type,
+                "listing": self.order_addr,
+                "seller": self.seller,
+                "price": price,
+                "token_metadata": self.token.token_metadata_json(),
+            }),
+            AptosLabsEventKind::ListingFilled => json!({
+                "type": listing_type,
+                "listing": self.order_addr,
+                "seller": self.seller,
+                "purchaser": self.purchaser,
+                "price": price,
+                "commission": self.commission.to_string(),
+                "royalties": self.royalties.to_string(),
+                "token_metadata": self.token.token_metadata_json(),
+            }),
+            AptosLabsEventKind::TokenOfferPlaced | AptosLabsEventKind::TokenOfferCancelled => {
+                json!({
+                    "token_offer": self.order_addr,
+                    "purchaser": self.purchaser,
+                    "price": price,
+                    "token_metadata": self.token.token_metadata_json(),
+                })
+            }
+            AptosLabsEventKind::TokenOfferFilled => json!({
+                "token_offer": self.order_addr,
+                "purchaser": self.purchaser,
+                "seller": self.seller,
+                "price": price,
+                "royalties": self.royalties.to_string(),
+                "commission": self.commission.to_string(),
+                "token_metadata": self.token.token_metadata_json(),
+            }),
+            AptosLabsEventKind::CollectionOfferPlaced => json!({
+                "collection_offer": self.order_addr,
+                "purchaser": self.purchaser,
+                "price": price,
+                "token_amount": self.token_amount.to_string(),
+                "collection_metadata": self.token.collection_metadata_json(),
+            }),
+            AptosLabsEventKind::CollectionOfferFilled => json!({
+                "collection_offer": self.order_addr,
+                "purchaser": self.purchaser,
+                "seller": self.seller,
+                "price": price,
+                "royalties": self.royalties.to_string(),
+                "commission": self.commission.to_string(),
+                "token_metadata": self.token.token_metadata_json(),
+            }),
+            AptosLabsEventKind::CollectionOfferCancelled => json!({
+                "collection_offer": self.order_addr,
+                "purchaser": self.purchaser,
+                "price": price,
+                "remaining_token_amount": self.token_amount.to_string(),
+                "collection_metadata": self.token.collection_metadata_json(),
+            }),
+        }
+    }
+
+    pub fn build(&self) -> EventPB {
+        let mut event_name = self.kind.event_name().to_string();
+        if self.canceled_spelling {
+            event_name = event_name.replace("Cancelled", "Canceled");
+        }
+        module_event(
+            format!("{}::events::{}", self.marketplace_addr, event_name),
+            self.data(),
+        )
+    }
+}
+
+impl From<AptosLabsEventBuilder> for EventPB {
+    fn from(builder: AptosLabsEventBuilder) -> Self {
+        builder.build()
+    }
+}
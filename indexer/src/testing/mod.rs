@@ -0,0 +1,261 @@
+//! Builders for synthetic transactions, so tests don't have to hand-build protobuf structs and
+//! JSON event payloads. Compiled for unit tests and with the `testing` feature.
+//!
+//! ```ignore
+//! let txn = TransactionBuilder::new(100)
+//!     .event(AptosLabsEventBuilder::listing_placed(MARKETPLACE, LISTING).price(150_000_000))
+//!     .build();
+//! ```
+
+pub mod aptos_labs_events;
+pub mod tradeport_v2_events;
+pub mod transaction_builder;
+pub mod write_set_changes;
+
+use serde_json::{json, Value};
+
+pub use aptos_labs_events::{AptosLabsEventBuilder, AptosLabsEventKind};
+pub use tradeport_v2_events::{TradeportV2EventBuilder, TradeportV2EventKind};
+pub use transaction_builder::{batch, TransactionBuilder};
+pub use write_set_changes::{write_module, PackagePublishBuilder};
+
+pub const TEST_CREATOR_ADDR: &str =
+    "0x3c9a7e5b1f2d4c6e8a0b2d4f6a8c0e2a4c6e8a0b2d4f6a8c0e2a4c6e8a0b2d4f";
+pub const TEST_COLLECTION_ADDR: &str =
+    "0x6f2e1d0c9b8a7f6e5d4c3b2a19f8e7d6c5b4a39281706f5e4d3c2b1a09f8e7d6";
+pub const TEST_TOKEN_ADDR: &str =
+    "0xa1b2c3d4e5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0c1d2e3f4a5b6c7d8e9f0a1b2";
+pub const TEST_SELLER_ADDR: &str =
+    "0x8f2d5c4bd9f5d4e2c1b1f7f0f1f9e6d1a3c6b2e7d5a4f3e2d1c0b9a8f7e6d5c4";
+pub const TEST_BUYER_ADDR: &str =
+    "0x1b2c3d4e5f60718293a4b5c6d7e8f90112233445566778899aabbccddeeff0011";
+pub const TEST_COLLECTION_NAME: &str = "Test Collection";
+pub const TEST_TOKEN_NAME: &str = "Test Token #1";
+
+/// The token an event refers to, v1 tokens are identified by name and v2 tokens by object.
+#[derive(Clone, Debug)]
+pub enum TestToken {
+    V1 {
+        creator_addr: String,
+        collection_name: String,
+        token_name: String,
+        property_version: u64,
+    },
+    V2 {
+        creator_addr: String,
+        collection_name: String,
+        collection_addr: String,
+        token_name: String,
+        token_addr: String,
+    },
+}
+
+impl Default for TestToken {
+    fn default() -> Self {
+        Self::v2(TEST_COLLECTION_ADDR, TEST_TOKEN_ADDR)
+    }
+}
+
+impl TestToken {
+    pub fn v1(creator_addr: &str, collection_name: &str, token_name: &str) -> Self {
+        Self::V1 {
+            creator_addr: creator_addr.to_string(),
+            collection_name: collection_name.to_string(),
+            token_name: token_name.to_string(),
+            property_version: 0,
+        }
+    }
+
+    pub fn v2(collection_addr: &str, token_addr: &str) -> Self {
+        Self::V2 {
+            creator_addr: TEST_CREATOR_ADDR.to_string(),
+            collection_name: TEST_COLLECTION_NAME.to_string(),
+            collection_addr: collection_addr.to_string(),
+            token_name: TEST_TOKEN_NAME.to_string(),
+            token_addr: token_addr.to_string(),
+        }
+    }
+
+    /// Object address of a v2 token, empty for v1 tokens.
+    pub fn token_addr(&self) -> &str {
+        match self {
+            Self::V1 { .. } => "",
+            Self::V2 { token_addr, .. } => token_addr,
+        }
+    }
+
+    /// Object address of a v2 collection, empty for v1 tokens.
+    pub fn collection_addr(&self) -> &str {
+        match self {
+            Self::V1 { .. } => "",
+            Self::V2 {
+                collection_addr, ..
+            } => collection_addr,
+        }
+    }
+
+    /// `TokenMetadata` as emitted by the Aptos Labs marketplace contract.
+    pub fn token_metadata_json(&self) -> Value {
+        match self {
+            Self::V1 {
+                creator_addr,
+                collection_name,
+                token_name,
+                property_version,
+            } => json!({
+                "creator_address": creator_addr,
+                "collection_name": collection_name,
+                "collection": move_option(None),
+                "token_name": token_name,
+                "token": move_option(None),
+                "property_version": { "vec": [property_version.to_string()] },
+            }),
+            Self::V2 {
+                creator_addr,
+                collection_name,
+                collection_addr,
+                token_name,
+                token_addr,
+            } => json!({
+                "creator_address": creator_addr,
+                "collection_name": collection_name,
+                "collection": move_option(Some(collection_addr)),
+                "token_name": token_name,
+                "token": move_option(Some(token_addr)),
+                "property_version": { "vec": [] },
+            }),
+        }
+    }
+
+    /// `CollectionMetadata` as emitted by the Aptos Labs marketplace contract.
+    pub fn collection_metadata_json(&self) -> Value {
+        match self {
+            Self::V1 {
+                creator_addr,
+                collection_name,
+                ..
+            } => json!({
+                "creator_address": creator_addr,
+                "collection_name": collection_name,
+                "collection": move_option(None),
+            }),
+            Self::V2 {
+                creator_addr,
+                collection_name,
+                collection_addr,
+                ..
+            } => json!({
+                "creator_address": creator_addr,
+                "collection_name": collection_name,
+                "collection": move_option(Some(collection_addr)),
+            }),
+        }
+    }
+}
+
+/// JSON of a Move `Object<T>`.
+pub fn move_object(addr: &str) -> Value {
+    json!({ "inner": addr })
+}
+
+/// JSON of a Move `Option<Object<T>>`.
+pub fn move_option(addr: Option<&str>) -> Value {
+    json!({ "vec": addr.map(move_object).into_iter().collect::<Vec<_>>() })
+}
+
+#[cfg(test)]
+mod test {
+    use ahash::AHashSet;
+    use aptos_indexer_processor_sdk::aptos_protos::transaction::v1::{
+        transaction::TxnData, Transaction,
+    };
+
+    use super::*;
+    use crate::indexers::{
+        contract_upgrade_indexer::extractor::ContractUpgradeChange,
+        marketplace_indexer::extractor::ContractEvent,
+    };
+
+    const APTOS_LABS_ADDR: &str =
+        "0x584b50b999c78ade62f8359c91b5165ff390338d45f8e55969a04e65d76258c9";
+    const TRADEPORT_V2_ADDR: &str =
+        "0xe11c12ec495f3989c35e1c6a0af414451223305b579291fc8f3d9d0575a23c26";
+    const ORDER_ADDR: &str = "0x4e5d6c7b8a9f0e1d2c3b4a5f6e7d8c9b0a1f2e3d4c5b6a7f8e9d0c1b2a3f4e5d";
+
+    fn parse(txn: &Transaction) -> Vec<ContractEvent> {
+        let contract_addresses: AHashSet<String> =
+            [APTOS_LABS_ADDR.to_string(), TRADEPORT_V2_ADDR.to_string()]
+                .into_iter()
+                .collect();
+        let events = match txn.txn_data.as_ref() {
+            Some(TxnData::User(user)) => &user.events,
+            _ => panic!("Builder should produce a user transaction"),
+        };
+        ContractEvent::from_events(&contract_addresses, events, txn.version as i64)
+    }
+
+    #[test]
+    fn test_every_aptos_labs_event_is_parsed() {
+        let tokens = [
+            TestToken::default(),
+            TestToken::v1(TEST_CREATOR_ADDR, TEST_COLLECTION_NAME, TEST_TOKEN_NAME),
+        ];
+        for token in tokens {
+            let txn = AptosLabsEventKind::ALL
+                .iter()
+                .fold(TransactionBuilder::new(1), |txn, kind| {
+                    txn.event(
+                        AptosLabsEventBuilder::new(*kind, APTOS_LABS_ADDR, ORDER_ADDR)
+                            .token(token.clone()),
+                    )
+                })
+                .build();
+            assert_eq!(parse(&txn).len(), AptosLabsEventKind::ALL.len());
+        }
+    }
+
+    #[test]
+    fn test_every_tradeport_v2_event_is_parsed() {
+        let txn = TradeportV2EventKind::ALL
+            .iter()
+            .fold(TransactionBuilder::new(1), |txn, kind| {
+                txn.event(TradeportV2EventBuilder::new(
+                    *kind,
+                    TRADEPORT_V2_ADDR,
+                    ORDER_ADDR,
+                ))
+            })
+            .build();
+        assert_eq!(parse(&txn).len(), TradeportV2EventKind::ALL.len());
+    }
+
+    #[test]
+    fn test_failed_transaction_is_unsuccessful() {
+        let txn = TransactionBuilder::new(1)
+            .event(AptosLabsEventBuilder::listing_placed(
+                APTOS_LABS_ADDR,
+                ORDER_ADDR,
+            ))
+            .failed()
+            .build();
+        assert!(!txn.info.unwrap().success);
+    }
+
+    #[test]
+    fn test_package_publish_is_parsed() {
+        let txn = TransactionBuilder::new(1)
+            .changes(
+                PackagePublishBuilder::new(APTOS_LABS_ADDR, "marketplace")
+                    .module("listing", &[0xa1, 0x1c, 0xeb, 0x0b], "module listing {}")
+                    .module("events", &[0xa1, 0x1c, 0xeb, 0x0b], "module events {}")
+                    .build(),
+            )
+            .build();
+        let contract_addresses: AHashSet<String> =
+            [APTOS_LABS_ADDR.to_string()].into_iter().collect();
+        let changes =
+            ContractUpgradeChange::from_changes(&contract_addresses, 1, &txn.info.unwrap().changes);
+        // Two module upgrades and one package upgrade
+        assert_eq!(changes.len(), 3);
+    }
+}
@@ -0,0 +1,240 @@
+use aptos_indexer_processor_sdk::aptos_protos::transaction::v1::Event as EventPB;
+use serde_json::{json, Value};
+
+use super::{
+    move_object, transaction_builder::module_event, TEST_BUYER_ADDR, TEST_COLLECTION_ADDR,
+    TEST_SELLER_ADDR, TEST_TOKEN_ADDR,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TradeportV2EventKind {
+    InsertListing,
+    Buy,
+    DeleteListing,
+    InsertTokenBid,
+    AcceptTokenBid,
+    DeleteTokenBid,
+    InsertCollectionBid,
+    AcceptCollectionBid,
+    DeleteCollectionBid,
+}
+
+impl TradeportV2EventKind {
+    pub const ALL: [Self; 9] = [
+        Self::InsertListing,
+        Self::Buy,
+        Self::DeleteListing,
+        Self::InsertTokenBid,
+        Self::AcceptTokenBid,
+        Self::DeleteTokenBid,
+        Self::InsertCollectionBid,
+        Self::AcceptCollectionBid,
+        Self::DeleteCollectionBid,
+    ];
+
+    fn event_type(&self) -> &'static str {
+        match self {
+            Self::InsertListing => "listings_v2::InsertListingEvent",
+            Self::Buy => "listings_v2::BuyEvent",
+            Self::DeleteListing => "listings_v2::DeleteListingEvent",
+            Self::InsertTokenBid => "biddings_v2::InsertTokenBidEvent",
+            Self::AcceptTokenBid => "biddings_v2::AcceptTokenBidEvent",
+            Self::DeleteTokenBid => "biddings_v2::DeleteTokenBidEvent",
+            Self::InsertCollectionBid => "biddings_v2::InsertCollectionBidEvent",
+            Self::AcceptCollectionBid => "biddings_v2::AcceptCollectionBidEvent",
+            Self::DeleteCollectionBid => "biddings_v2::DeleteCollectionBidEvent",
+        }
+    }
+}
+
+/// Builds an event of the Tradeport v2 marketplace contract (`listings_v2` and `biddings_v2`).
+#[derive(Clone, Debug)]
+pub struct TradeportV2EventBuilder {
+    kind: TradeportV2EventKind,
+    marketplace_addr: String,
+    // Listing or bid object depending on the kind
+    order_addr: String,
+    seller: String,
+    buyer: String,
+    price: u64,
+    timestamp_secs: u64,
+    token_addr: String,
+    collection_addr: String,
+}
+
+impl TradeportV2EventBuilder {
+    pub fn new(kind: TradeportV2EventKind, marketplace_addr: &str, order_addr: &str) -> Self {
+        Self {
+            kind,
+            marketplace_addr: marketplace_addr.to_string(),
+            order_addr: order_addr.to_string(),
+            seller: TEST_SELLER_ADDR.to_string(),
+            buyer: TEST_BUYER_ADDR.to_string(),
+            price: 100_000_000,
+            timestamp_secs: 1_718_000_000,
+            token_addr: TEST_TOKEN_ADDR.to_string(),
+            collection_addr: TEST_COLLECTION_ADDR.to_string(),
+        }
+    }
+
+    pub fn insert_listing(marketplace_addr: &str, listing_addr: &str) -> Self {
+        Self::new(
+            TradeportV2EventKind::InsertListing,
+            marketplace_addr,
+            listing_addr,
+        )
+    }
+
+    pub fn buy(marketplace_addr: &str, listing_addr: &str) -> Self {
+        Self::new(TradeportV2EventKind::Buy, marketplace_addr, listing_addr)
+    }
+
+    pub fn delete_listing(marketplace_addr: &str, listing_addr: &str) -> Self {
+        Self::new(
+            TradeportV2EventKind::DeleteListing,
+            marketplace_addr,
+            listing_addr,
+        )
+    }
+
+    pub fn insert_token_bid(marketplace_addr: &str, bid_addr: &str) -> Self {
+        Self::new(
+            TradeportV2EventKind::InsertTokenBid,
+            marketplace_addr,
+            bid_addr,
+        )
+    }
+
+    pub fn accept_token_bid(marketplace_addr: &str, bid_addr: &str) -> Self {
+        Self::new(
+            TradeportV2EventKind::AcceptTokenBid,
+            marketplace_addr,
+            bid_addr,
+        )
+    }
+
+    pub fn delete_token_bid(marketplace_addr: &str, bid_addr: &str) -> Self {
+        Self::new(
+            TradeportV2EventKind::DeleteTokenBid,
+            marketplace_addr,
+            bid_addr,
+        )
+    }
+
+    pub fn insert_collection_bid(marketplace_addr: &str, bid_addr: &str) -> Self {
+        Self::new(
+            TradeportV2EventKind::InsertCollectionBid,
+            marketplace_addr,
+            bid_addr,
+        )
+    }
+
+    pub fn accept_collection_bid(marketplace_addr: &str, bid_addr: &str) -> Self {
+        Self::new(
+            TradeportV2EventKind::AcceptCollectionBid,
+            marketplace_addr,
+            bid_addr,
+        )
+    }
+
+    pub fn delete_collection_bid(marketplace_addr: &str, bid_addr: &str) -> Self {
+        Self::new(
+            TradeportV2EventKind::DeleteCollectionBid,
+            marketplace_addr,
+            bid_addr,
+        )
+    }
+
+    pub fn seller(mut self, seller: &str) -> Self {
+        self.seller = seller.to_string();
+        self
+    }
+
+    pub fn buyer(mut self, buyer: &str) -> Self {
+        self.buyer = buyer.to_string();
+        self
+    }
+
+    pub fn price(mut self, price: u64) -> Self {
+        self.price = price;
+        self
+    }
+
+    /// On-chain timestamp in the event payload, which the Tradeport parsers use for the rows.
+    pub fn timestamp_secs(mut self, timestamp_secs: u64) -> Self {
+        self.timestamp_secs = timestamp_secs;
+        self
+    }
+
+    pub fn token(mut self, token_addr: &str) -> Self {
+        self.token_addr = token_addr.to_string();
+        self
+    }
+
+    pub fn collection(mut self, collection_addr: &str) -> Self {
+        self.collection_addr = collection_addr.to_string();
+        self
+    }
+
+    fn data(&self) -> Value {
+        let timestamp = self.timestamp_secs.to_string();
+        let price = self.price.to_string();
+        let order = move_object(&self.order_addr);
+        let token = move_object(&self.token_addr);
+        match self.kind {
+            TradeportV2EventKind::InsertListing | TradeportV2EventKind::DeleteListing => json!({
+                "listing": order,
+                "timestamp": timestamp,
+                "token": token,
+                "price": price,
+                "seller": self.seller,
+            }),
+            TradeportV2EventKind::Buy => json!({
+                "listing": order,
+                "timestamp": timestamp,
+                "token": token,
+                "price": price,
+                "seller": self.seller,
+                "buyer": self.buyer,
+            }),
+            TradeportV2EventKind::InsertTokenBid | TradeportV2EventKind::DeleteTokenBid => json!({
+                "timestamp": timestamp,
+                "bid": order,
+                "bid_buyer": self.buyer,
+                "token": token,
+                "price": price,
+            }),
+            TradeportV2EventKind::AcceptTokenBid | TradeportV2EventKind::AcceptCollectionBid => {
+                json!({
+                    "timestamp": timestamp,
+                    "bid": order,
+                    "bid_buyer": self.buyer,
+                    "bid_seller": self.seller,
+                    "token": token,
+                    "price": price,
+                })
+            }
+            TradeportV2EventKind::InsertCollectionBid
+            | TradeportV2EventKind::DeleteCollectionBid => json!({
+                "timestamp": timestamp,
+                "bid": order,
+                "bid_buyer": self.buyer,
+                "collection": move_object(&self.collection_addr),
+                "price": price,
+            }),
+        }
+    }
+
+    pub fn build(&self) -> EventPB {
+        module_event(
+            format!("{}::{}", self.marketplace_addr, self.kind.event_type()),
+            self.data(),
+        )
+    }
+}
+
+impl From<TradeportV2EventBuilder> for EventPB {
+    fn from(builder: TradeportV2EventBuilder) -> Self {
+        builder.build()
+    }
+}
@@ -0,0 +1,145 @@
+use aptos_indexer_processor_sdk::{
+    aptos_protos::{
+        transaction::v1::{
+            transaction::{TransactionType, TxnData},
+            Event as EventPB, EventKey, Transaction, TransactionInfo, UserTransaction,
+            UserTransactionRequest, WriteSetChange,
+        },
+        util::timestamp::Timestamp,
+    },
+    types::transaction_context::{TransactionContext, TransactionMetadata},
+};
+
+use super::TEST_SELLER_ADDR;
+
+// Transactions default to this timestamp plus their version, so timestamps increase with versions
+pub const DEFAULT_TIMESTAMP_SECS: i64 = 1_718_000_000;
+
+/// Builds a user transaction with the given events and write set changes.
+pub struct TransactionBuilder {
+    version: u64,
+    timestamp_secs: i64,
+    sender: String,
+    success: bool,
+    events: Vec<EventPB>,
+    changes: Vec<WriteSetChange>,
+}
+
+impl TransactionBuilder {
+    pub fn new(version: u64) -> Self {
+        Self {
+            version,
+            timestamp_secs: DEFAULT_TIMESTAMP_SECS + version as i64,
+            sender: TEST_SELLER_ADDR.to_string(),
+            success: true,
+            events: vec![],
+            changes: vec![],
+        }
+    }
+
+    pub fn timestamp_secs(mut self, timestamp_secs: i64) -> Self {
+        self.timestamp_secs = timestamp_secs;
+        self
+    }
+
+    pub fn sender(mut self, sender: &str) -> Self {
+        self.sender = sender.to_string();
+        self
+    }
+
+    /// Mark the transaction as aborted, the indexers skip everything it emitted.
+    pub fn failed(mut self) -> Self {
+        self.success = false;
+        self
+    }
+
+    /// Append an event, either a built `Event` or one of the event builders.
+    pub fn event(mut self, event: impl Into<EventPB>) -> Self {
+        self.events.push(event.into());
+        self
+    }
+
+    pub fn change(mut self, change: WriteSetChange) -> Self {
+        self.changes.push(change);
+        self
+    }
+
+    pub fn changes(mut self, changes: impl IntoIterator<Item = WriteSetChange>) -> Self {
+        self.changes.extend(changes);
+        self
+    }
+
+    // Newer protos add fields to `Transaction`, keep the struct update so this compiles with both
+    #[allow(clippy::needless_update)]
+    pub fn build(self) -> Transaction {
+        let timestamp = Timestamp {
+            seconds: self.timestamp_secs,
+            nanos: 0,
+        };
+        let events = self
+            .events
+            .into_iter()
+            .enumerate()
+            .map(|(idx, event)| EventPB {
+                sequence_number: idx as u64,
+                ..event
+            })
+            .collect();
+
+        Transaction {
+            timestamp: Some(timestamp.clone()),
+            version: self.version,
+            info: Some(TransactionInfo {
+                success: self.success,
+                vm_status: if self.success {
+                    "Executed successfully".to_string()
+                } else {
+                    "Move abort".to_string()
+                },
+                changes: self.changes,
+                ..Default::default()
+            }),
+            epoch: 1,
+            block_height: self.version,
+            r#type: TransactionType::User as i32,
+            txn_data: Some(TxnData::User(UserTransaction {
+                request: Some(UserTransactionRequest {
+                    sender: self.sender,
+                    expiration_timestamp_secs: Some(timestamp),
+                    ..Default::default()
+                }),
+                events,
+            })),
+            ..Default::default()
+        }
+    }
+}
+
+/// Build an event the way the node emits module events, used by the event builders.
+pub fn module_event(type_str: String, data: serde_json::Value) -> EventPB {
+    EventPB {
+        key: Some(EventKey {
+            creation_number: 0,
+            account_address: "0x0".to_string(),
+        }),
+        type_str,
+        data: data.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Wrap transactions in a batch the way the transaction stream step would.
+pub fn batch(transactions: Vec<Transaction>) -> TransactionContext<Vec<Transaction>> {
+    let start_version = transactions.first().map(|t| t.version).unwrap_or_default();
+    let end_version = transactions.last().map(|t| t.version).unwrap_or_default();
+    TransactionContext {
+        data: transactions,
+        metadata: TransactionMetadata {
+            start_version,
+            end_version,
+            start_transaction_timestamp: None,
+            end_transaction_timestamp: None,
+            total_size_in_bytes: 0,
+        },
+    }
+}
@@ -0,0 +1,132 @@
+use aptos_indexer_processor_sdk::aptos_protos::transaction::v1::{
+    write_set_change::{Change, Type as WriteSetChangeType},
+    MoveModule, MoveModuleBytecode, MoveStructTag, WriteModule, WriteResource, WriteSetChange,
+};
+use serde_json::json;
+
+pub const PACKAGE_REGISTRY_TYPE: &str = "0x1::code::PackageRegistry";
+
+/// A `WriteModule` change with the given bytecode, as written when a module is published.
+pub fn write_module(address: &str, module_name: &str, bytecode: &[u8]) -> WriteSetChange {
+    WriteSetChange {
+        r#type: WriteSetChangeType::WriteModule as i32,
+        change: Some(Change::WriteModule(WriteModule {
+            address: address.to_string(),
+            data: Some(MoveModuleBytecode {
+                bytecode: bytecode.to_vec(),
+                abi: Some(MoveModule {
+                    address: address.to_string(),
+                    name: module_name.to_string(),
+                    ..Default::default()
+                }),
+            }),
+            ..Default::default()
+        })),
+    }
+}
+
+/// Builds the write set of a package publish or upgrade: one `WriteModule` per module and the
+/// `0x1::code::PackageRegistry` resource of the publishing account.
+#[derive(Clone, Debug)]
+pub struct PackagePublishBuilder {
+    address: String,
+    package_name: String,
+    upgrade_number: u64,
+    upgrade_policy: i64,
+    manifest: String,
+    source_digest: String,
+    // (name, bytecode, source)
+    modules: Vec<(String, Vec<u8>, String)>,
+    // Modules listed in the registry but not rewritten, e.g. unchanged ones on upgrade
+    unchanged_modules: Vec<(String, String)>,
+}
+
+impl PackagePublishBuilder {
+    pub fn new(address: &str, package_name: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            package_name: package_name.to_string(),
+            upgrade_number: 0,
+            upgrade_policy: 1,
+            manifest: "0x".to_string(),
+            source_digest: "0000000000000000000000000000000000000000000000000000000000000000"
+                .to_string(),
+            modules: vec![],
+            unchanged_modules: vec![],
+        }
+    }
+
+    pub fn upgrade_number(mut self, upgrade_number: u64) -> Self {
+        self.upgrade_number = upgrade_number;
+        self
+    }
+
+    pub fn upgrade_policy(mut self, upgrade_policy: i64) -> Self {
+        self.upgrade_policy = upgrade_policy;
+        self
+    }
+
+    pub fn manifest(mut self, manifest: &str) -> Self {
+        self.manifest = manifest.to_string();
+        self
+    }
+
+    pub fn source_digest(mut self, source_digest: &str) -> Self {
+        self.source_digest = source_digest.to_string();
+        self
+    }
+
+    pub fn module(mut self, name: &str, bytecode: &[u8], source: &str) -> Self {
+        self.modules
+            .push((name.to_string(), bytecode.to_vec(), source.to_string()));
+        self
+    }
+
+    pub fn unchanged_module(mut self, name: &str, source: &str) -> Self {
+        self.unchanged_modules
+            .push((name.to_string(), source.to_string()));
+        self
+    }
+
+    pub fn build(self) -> Vec<WriteSetChange> {
+        let registry_modules = self
+            .modules
+            .iter()
+            .map(|(name, _, source)| (name, source))
+            .chain(self.unchanged_modules.iter().map(|(name, source)| (name, source)))
+            .map(|(name, source)| json!({ "name": name, "source": source, "source_map": "0x", "extension": { "vec": [] } }))
+            .collect::<Vec<_>>();
+        let registry = json!({
+            "packages": [{
+                "deps": [],
+                "extension": { "vec": [] },
+                "manifest": self.manifest,
+                "modules": registry_modules,
+                "name": self.package_name,
+                "source_digest": self.source_digest,
+                "upgrade_number": self.upgrade_number.to_string(),
+                "upgrade_policy": { "policy": self.upgrade_policy },
+            }]
+        });
+
+        self.modules
+            .iter()
+            .map(|(name, bytecode, _)| write_module(&self.address, name, bytecode))
+            .chain(std::iter::once(WriteSetChange {
+                r#type: WriteSetChangeType::WriteResource as i32,
+                change: Some(Change::WriteResource(WriteResource {
+                    address: self.address.clone(),
+                    r#type: Some(MoveStructTag {
+                        address: "0x1".to_string(),
+                        module: "code".to_string(),
+                        name: "PackageRegistry".to_string(),
+                        ..Default::default()
+                    }),
+                    type_str: PACKAGE_REGISTRY_TYPE.to_string(),
+                    data: registry.to_string(),
+                    ..Default::default()
+                })),
+            }))
+            .collect()
+    }
+}
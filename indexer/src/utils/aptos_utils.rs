@@ -1,35 +1,129 @@
 pub const APT_COIN: &str = "0x1::aptos_coin::AptosCoin";
 pub const APT_FA: &str = "0xa";
 
-pub enum NFTStandard {
-    V1 = 1,
-    V2 = 2,
+/// Declares an enum stored as an Int4 column together with the label of every value and the
+/// lookup table the labels are written to, so the two can't drift apart.
+macro_rules! labeled_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident in $table:literal {
+            $($variant:ident = $value:literal => $label:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum $name {
+            $($variant = $value,)*
+        }
+
+        impl $name {
+            pub const LOOKUP_TABLE: &'static str = $table;
+            pub const ALL: &'static [$name] = &[$($name::$variant,)*];
+
+            pub fn label(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $label,)*
+                }
+            }
+        }
+    };
+}
+
+labeled_enum! {
+    pub enum NFTStandard in "nft_standards" {
+        V1 = 1 => "v1",
+        V2 = 2 => "v2",
+    }
+}
+
+labeled_enum! {
+    pub enum PaymentTokenType in "payment_token_types" {
+        Coin = 1 => "coin",
+        FA = 2 => "fungible_asset",
+    }
+}
+
+labeled_enum! {
+    pub enum OrderStatus in "order_statuses" {
+        Open = 1 => "open",
+        Filled = 2 => "filled",
+        Cancelled = 3 => "cancelled",
+    }
+}
+
+labeled_enum! {
+    pub enum AskOrderType in "order_types" {
+        FixedPrice = 1 => "fixed_price",
+        Auction = 2 => "auction",
+    }
+}
+
+labeled_enum! {
+    pub enum ActivityType in "activity_types" {
+        CollectionBidPlaced = 1 => "collection_bid_placed",
+        CollectionBidFilled = 2 => "collection_bid_filled",
+        CollectionBidCancelled = 3 => "collection_bid_cancelled",
+        NFTBidPlaced = 4 => "nft_bid_placed",
+        NFTBidFilled = 5 => "nft_bid_filled",
+        NFTBidCancelled = 6 => "nft_bid_cancelled",
+        NFTAskPlaced = 7 => "nft_ask_placed",
+        NFTAskFilled = 8 => "nft_ask_filled",
+        NFTAskCancelled = 9 => "nft_ask_cancelled",
+        // A cancelled ask placed again at another price in the same transaction
+        NFTAskPriceUpdated = 10 => "nft_ask_price_updated",
+        // A cancelled bid placed again at another price in the same transaction
+        NFTBidPriceUpdated = 11 => "nft_bid_price_updated",
+    }
 }
 
-pub enum PaymentTokenType {
-    Coin = 1,
-    FA = 2,
+/// Lookup table of a labeled enum, with the value and label of every variant.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LookupTable {
+    pub name: &'static str,
+    pub rows: Vec<(i32, &'static str)>,
 }
 
-pub enum OrderStatus {
-    Open = 1,
-    Filled = 2,
-    Cancelled = 3,
+impl LookupTable {
+    /// Statement that writes the rows to the table, for the migration that adds or changes them.
+    pub fn upsert_sql(&self) -> String {
+        let values = self
+            .rows
+            .iter()
+            .map(|(id, label)| format!("    ({}, '{}')", id, label))
+            .collect::<Vec<_>>()
+            .join(",\n");
+        format!(
+            "INSERT INTO\n    {} (id, name)\nVALUES\n{}\nON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name;",
+            self.name, values
+        )
+    }
 }
 
-pub enum AskOrderType {
-    FixedPrice = 1,
-    Auction = 2,
+/// Lookup tables of every enum stored in the marketplace tables.
+pub fn lookup_tables() -> Vec<LookupTable> {
+    macro_rules! lookup_table {
+        ($name:ident) => {
+            LookupTable {
+                name: $name::LOOKUP_TABLE,
+                rows: $name::ALL
+                    .iter()
+                    .map(|variant| (*variant as i32, variant.label()))
+                    .collect(),
+            }
+        };
+    }
+    vec![
+        lookup_table!(NFTStandard),
+        lookup_table!(PaymentTokenType),
+        lookup_table!(OrderStatus),
+        lookup_table!(AskOrderType),
+        lookup_table!(ActivityType),
+    ]
 }
 
-pub enum ActivityType {
-    CollectionBidPlaced = 1,
-    CollectionBidFilled = 2,
-    CollectionBidCancelled = 3,
-    NFTBidPlaced = 4,
-    NFTBidFilled = 5,
-    NFTBidCancelled = 6,
-    NFTAskPlaced = 7,
-    NFTAskFilled = 8,
-    NFTAskCancelled = 9,
+pub enum BackfillShardStatus {
+    Pending = 1,
+    InProgress = 2,
+    Completed = 3,
+    Failed = 4,
 }
@@ -4,23 +4,18 @@ use super::database_utils::ArcDbPool;
 use crate::{
     db_models::ledger_info::LedgerInfo,
     schema::ledger_infos,
-    utils::{
-        database_connection::get_db_connection, database_execution::execute_with_better_error,
-    },
+    utils::database_execution::{execute_with_better_error, execute_with_retries},
 };
 
 /// Verify the chain id from GRPC against the database.
 pub async fn check_or_update_chain_id(grpc_chain_id: i64, db_pool: ArcDbPool) -> Result<u64> {
     tracing::info!("Checking if chain id is correct");
 
-    let mut conn = get_db_connection(&db_pool)
-        .await
-        .expect("Failed to get connection from pool while checking or updating chain id");
-
-    let maybe_existing_chain_id = LedgerInfo::get(&mut conn)
-        .await
-        .expect("Failed to get chain id from db while checking or updating chain id")
-        .map(|li| li.chain_id);
+    let maybe_existing_chain_id =
+        execute_with_retries(&db_pool, |conn| Box::pin(LedgerInfo::get(conn)))
+            .await
+            .context("Failed to get chain id from db while checking or updating chain id")?
+            .map(|li| li.chain_id);
 
     match maybe_existing_chain_id {
         Some(chain_id) => {
@@ -36,15 +31,17 @@ pub async fn check_or_update_chain_id(grpc_chain_id: i64, db_pool: ArcDbPool) ->
                 chain_id = grpc_chain_id,
                 "Adding chain id to db, continue to index..."
             );
-            let query = diesel::insert_into(ledger_infos::table)
-                .values(LedgerInfo {
-                    chain_id: grpc_chain_id,
-                })
-                .on_conflict_do_nothing();
-            execute_with_better_error(&mut conn, vec![query])
-                .await
-                .context("Error updating chain_id!")
-                .map(|_| grpc_chain_id as u64)
+            execute_with_retries(&db_pool, |conn| {
+                let query = diesel::insert_into(ledger_infos::table)
+                    .values(LedgerInfo {
+                        chain_id: grpc_chain_id,
+                    })
+                    .on_conflict_do_nothing();
+                Box::pin(execute_with_better_error(conn, vec![query]))
+            })
+            .await
+            .context("Error updating chain_id!")
+            .map(|_| grpc_chain_id as u64)
         }
     }
 }
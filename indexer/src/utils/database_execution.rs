@@ -3,11 +3,76 @@ use diesel::{
     debug_query,
     pg::Pg,
     query_builder::{QueryFragment, QueryId},
-    result::Error as DieselError,
+    result::{DatabaseErrorKind, Error as DieselError},
     QueryResult,
 };
 use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
-use futures_util::future;
+use futures_util::{future, future::BoxFuture};
+use std::time::Duration;
+
+use super::database_utils::ArcDbPool;
+use crate::config::indexer_processor_config::{
+    QUERY_DEFAULT_RETRIES, QUERY_DEFAULT_RETRY_DELAY_MS,
+};
+
+// Postgres messages of errors that go away on their own, e.g. during a failover
+const TRANSIENT_ERROR_MESSAGES: [&str; 4] = [
+    "deadlock detected",
+    "terminating connection",
+    "the database system is",
+    "connection to server",
+];
+
+/// Whether an error is likely to go away when the query is retried, like a dropped connection or
+/// a serialization failure. Constraint violations and bad queries are not.
+pub fn is_transient_error(error: &DieselError) -> bool {
+    match error {
+        DieselError::DatabaseError(kind, info) => match kind {
+            DatabaseErrorKind::SerializationFailure
+            | DatabaseErrorKind::UnableToSendCommand
+            | DatabaseErrorKind::ClosedConnection => true,
+            DatabaseErrorKind::Unknown => TRANSIENT_ERROR_MESSAGES
+                .iter()
+                .any(|message| info.message().contains(message)),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Run `f` with a connection from the pool, retrying transient errors with exponential backoff
+/// starting at QUERY_DEFAULT_RETRY_DELAY_MS, up to QUERY_DEFAULT_RETRIES times. `f` runs again
+/// from the start on every attempt, so it should run its queries in a transaction.
+pub async fn execute_with_retries<T, F>(pool: &ArcDbPool, f: F) -> QueryResult<T>
+where
+    F: for<'c> Fn(&'c mut AsyncPgConnection) -> BoxFuture<'c, QueryResult<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        let result = match pool.get().await {
+            Ok(mut conn) => f(&mut conn).await,
+            // Timeouts and failed connects of the pool, same as a dropped connection
+            Err(e) => Err(DieselError::DatabaseError(
+                DatabaseErrorKind::UnableToSendCommand,
+                Box::new(format!("Failed to get connection from pool: {}", e)),
+            )),
+        };
+        match result {
+            Err(e) if attempt < QUERY_DEFAULT_RETRIES && is_transient_error(&e) => {
+                let delay = Duration::from_millis(QUERY_DEFAULT_RETRY_DELAY_MS << attempt);
+                attempt += 1;
+                tracing::warn!(
+                    attempt,
+                    delay_ms = delay.as_millis() as u64,
+                    "Retrying after transient database error: {:?}",
+                    e
+                );
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
 
 pub async fn execute_with_better_error<U>(
     conn: &mut AsyncPgConnection,
@@ -40,7 +105,9 @@ pub async fn handle_db_execution(
 ) -> Result<(), ProcessorError> {
     let results = future::try_join_all(tasks)
         .await
-        .expect("Task panicked executing in chunks");
+        .map_err(|e| ProcessorError::ProcessError {
+            message: format!("Task panicked executing in chunks: {}", e),
+        })?;
     for res in results {
         res.map_err(|e| {
             tracing::warn!("Error running query: {:?}", e);
@@ -51,3 +118,42 @@ pub async fn handle_db_execution(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn database_error(kind: DatabaseErrorKind, message: &str) -> DieselError {
+        DieselError::DatabaseError(kind, Box::new(message.to_string()))
+    }
+
+    #[test]
+    fn test_only_transient_errors_are_retried() {
+        assert!(is_transient_error(&database_error(
+            DatabaseErrorKind::SerializationFailure,
+            "could not serialize access due to concurrent update",
+        )));
+        assert!(is_transient_error(&database_error(
+            DatabaseErrorKind::UnableToSendCommand,
+            "connection closed",
+        )));
+        assert!(is_transient_error(&database_error(
+            DatabaseErrorKind::Unknown,
+            "deadlock detected",
+        )));
+        assert!(is_transient_error(&database_error(
+            DatabaseErrorKind::Unknown,
+            "the database system is starting up",
+        )));
+
+        assert!(!is_transient_error(&database_error(
+            DatabaseErrorKind::UniqueViolation,
+            "duplicate key value violates unique constraint",
+        )));
+        assert!(!is_transient_error(&database_error(
+            DatabaseErrorKind::Unknown,
+            "column \"foo\" does not exist",
+        )));
+        assert!(!is_transient_error(&DieselError::NotFound));
+    }
+}
@@ -0,0 +1,19 @@
+use anyhow::Result;
+use diesel::Connection;
+use diesel_async::{async_connection_wrapper::AsyncConnectionWrapper, AsyncPgConnection};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("src/db_migrations/migrations");
+
+/// Apply the migrations that haven't run yet, used to set up local and test databases.
+pub async fn run_migrations(postgres_connection_string: String) -> Result<()> {
+    // The migration harness is sync, the wrapper blocks on the current runtime
+    tokio::task::spawn_blocking(move || {
+        let mut conn =
+            AsyncConnectionWrapper::<AsyncPgConnection>::establish(&postgres_connection_string)?;
+        conn.run_pending_migrations(MIGRATIONS)
+            .map_err(|e| anyhow::anyhow!("Failed to run migrations: {}", e))?;
+        Ok(())
+    })
+    .await?
+}
@@ -6,12 +6,18 @@ use aptos_indexer_processor_sdk::{
     utils::{errors::ProcessorError, time::parse_timestamp},
 };
 use async_trait::async_trait;
-use diesel::{query_dsl::methods::FilterDsl, upsert::excluded, ExpressionMethods};
-use std::marker::PhantomData;
+use diesel::{
+    pg::Pg,
+    query_builder::{QueryFragment, QueryId},
+    query_dsl::methods::FilterDsl,
+    upsert::excluded,
+    ExpressionMethods,
+};
+use std::{fmt::Debug, marker::PhantomData, time::Duration};
 
 use super::{
-    database_connection::{get_db_connection, new_db_pool},
-    database_execution::execute_with_better_error,
+    database_connection::new_db_pool,
+    database_execution::{execute_with_better_error, execute_with_retries},
     database_utils::ArcDbPool,
 };
 use crate::{
@@ -19,7 +25,28 @@ use crate::{
     schema::processor_status,
 };
 
-const UPDATE_PROCESSOR_STATUS_SECS: u64 = 1;
+pub const UPDATE_PROCESSOR_STATUS_SECS: u64 = 1;
+
+/// Upsert of processor_status that never moves last_success_version backwards.
+fn upsert_processor_status_query(
+    status: &ProcessorStatus,
+) -> impl QueryFragment<Pg> + QueryId + Send + Debug + '_ {
+    diesel::insert_into(processor_status::table)
+        .values(status)
+        .on_conflict(processor_status::processor)
+        .do_update()
+        .set((
+            processor_status::last_success_version
+                .eq(excluded(processor_status::last_success_version)),
+            processor_status::last_updated.eq(excluded(processor_status::last_updated)),
+            processor_status::last_transaction_timestamp
+                .eq(excluded(processor_status::last_transaction_timestamp)),
+        ))
+        .filter(
+            processor_status::last_success_version
+                .lt(excluded(processor_status::last_success_version)),
+        )
+}
 
 pub struct LatestVersionProcessedTracker<T>
 where
@@ -36,6 +63,8 @@ where
     // Tracks all the versions that have been processed out of order.
     // seen_versions: AHashMap<u64, TransactionContext<T>>,
     seen_versions: AHashMap<u64, TransactionContext<()>>,
+    // How often processor_status is written
+    poll_interval: Duration,
     _marker: PhantomData<T>,
 }
 
@@ -49,19 +78,26 @@ where
         starting_version: u64,
         tracker_name: String,
     ) -> Result<Self> {
-        let pool = new_db_pool(
-            &db_config.postgres_connection_string,
-            db_config.db_pool_size,
-        )
-        .await;
-        Ok(Self {
+        let pool = new_db_pool(&db_config.connection_string()?, db_config.db_pool_size).await;
+        Ok(Self::new_with_pool(pool, starting_version, tracker_name))
+    }
+
+    /// Create a tracker that shares an existing pool, used when many trackers run in one process.
+    pub fn new_with_pool(pool: ArcDbPool, starting_version: u64, tracker_name: String) -> Self {
+        Self {
             pool,
             tracker_name,
             next_version: starting_version,
             last_success_batch: None,
             seen_versions: AHashMap::new(),
+            poll_interval: Duration::from_secs(UPDATE_PROCESSOR_STATUS_SECS),
             _marker: PhantomData,
-        })
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
     }
 
     fn update_last_success_batch(&mut self, current_batch: TransactionContext<()>) {
@@ -91,28 +127,18 @@ where
                 last_success_version: last_success_batch.metadata.end_version as i64,
                 last_transaction_timestamp: end_timestamp,
             };
-            let query = diesel::insert_into(processor_status::table)
-                .values(&status)
-                .on_conflict(processor_status::processor)
-                .do_update()
-                .set((
-                    processor_status::last_success_version
-                        .eq(excluded(processor_status::last_success_version)),
-                    processor_status::last_updated.eq(excluded(processor_status::last_updated)),
-                    processor_status::last_transaction_timestamp
-                        .eq(excluded(processor_status::last_transaction_timestamp)),
-                ))
-                .filter(
-                    processor_status::last_success_version
-                        .lt(excluded(processor_status::last_success_version)),
-                );
-            let conn = &mut get_db_connection(&self.pool).await?;
-            execute_with_better_error(conn, vec![query])
-                .await
-                .map_err(|e| ProcessorError::DBStoreError {
-                    message: format!("Failed to update processor status: {}", e),
-                    query: Some(format!("{:?}", query)),
-                })?;
+            execute_with_retries(&self.pool, |conn| {
+                let status = status.clone();
+                Box::pin(async move {
+                    execute_with_better_error(conn, vec![upsert_processor_status_query(&status)])
+                        .await
+                })
+            })
+            .await
+            .map_err(|e| ProcessorError::DBStoreError {
+                message: format!("Failed to update processor status: {}", e),
+                query: Some(format!("{:?}", upsert_processor_status_query(&status))),
+            })?;
         }
         Ok(())
     }
@@ -173,7 +199,7 @@ where
     T: Send + 'static,
 {
     fn poll_interval(&self) -> std::time::Duration {
-        std::time::Duration::from_secs(UPDATE_PROCESSOR_STATUS_SECS)
+        self.poll_interval
     }
 
     async fn poll(&mut self) -> Result<Option<Vec<TransactionContext<T>>>, ProcessorError> {
@@ -0,0 +1,127 @@
+use anyhow::Result;
+use diesel::{
+    sql_query,
+    sql_types::{Bool, Text},
+    QueryableByName,
+};
+use diesel_async::{AsyncPgConnection, RunQueryDsl, SimpleAsyncConnection};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use super::{database_connection::establish_connection, shutdown::shutdown_requested};
+
+// Set while this replica waits for the leader lock, reported by the health server
+static IS_STANDBY: AtomicBool = AtomicBool::new(false);
+
+#[derive(QueryableByName)]
+struct LockResult {
+    #[diesel(sql_type = Bool)]
+    locked: bool,
+}
+
+/// Role of this replica, a replica without leader election always processes so it's the leader.
+pub fn role() -> &'static str {
+    // RunQueryDsl::load would be picked for IS_STANDBY.load
+    if AtomicBool::load(&IS_STANDBY, Ordering::Relaxed) {
+        "standby"
+    } else {
+        "leader"
+    }
+}
+
+/// Postgres session advisory lock that only one replica of a processor holds at a time. The lock
+/// lives as long as its connection, so it's released as soon as the leader exits or its
+/// connection drops, and a standby takes over on its next attempt.
+pub struct LeaderLock {
+    conn: AsyncPgConnection,
+    processor_name: String,
+    check_interval: Duration,
+}
+
+impl LeaderLock {
+    /// Wait until this replica holds the lock of `processor_name`, trying every
+    /// `check_interval`. Returns None if a shutdown is requested while waiting.
+    pub async fn acquire(
+        database_url: &str,
+        processor_name: &str,
+        check_interval: Duration,
+    ) -> Result<Option<Self>> {
+        IS_STANDBY.store(true, Ordering::Relaxed);
+        loop {
+            match Self::try_acquire(database_url, processor_name, check_interval).await {
+                Ok(Some(lock)) => {
+                    IS_STANDBY.store(false, Ordering::Relaxed);
+                    tracing::info!("Became the leader of {}", processor_name);
+                    return Ok(Some(lock));
+                }
+                Ok(None) => tracing::info!(
+                    "Another replica is the leader of {}, waiting as standby",
+                    processor_name
+                ),
+                // The database may be what took the leader down, keep trying
+                Err(e) => tracing::warn!(
+                    "Failed to try the leader lock of {}: {:#}",
+                    processor_name,
+                    e
+                ),
+            }
+            tokio::select! {
+                _ = shutdown_requested() => return Ok(None),
+                _ = tokio::time::sleep(check_interval) => {}
+            }
+        }
+    }
+
+    async fn try_acquire(
+        database_url: &str,
+        processor_name: &str,
+        check_interval: Duration,
+    ) -> Result<Option<Self>> {
+        let mut conn = establish_connection(database_url).await?;
+        // So the server releases the lock within seconds when the leader's host goes away,
+        // instead of waiting for the OS to time out the connection
+        conn.batch_execute(&format!(
+            "SET tcp_keepalives_idle = {}; SET tcp_keepalives_interval = 1; \
+             SET tcp_keepalives_count = 3;",
+            check_interval.as_secs().max(1)
+        ))
+        .await?;
+        // Advisory locks are per database, the schema keeps networks sharing one apart
+        let result = sql_query(
+            "SELECT pg_try_advisory_lock(hashtext(coalesce(current_schema(), '')), hashtext($1)) \
+             AS locked",
+        )
+        .bind::<Text, _>(processor_name)
+        .get_result::<LockResult>(&mut conn)
+        .await?;
+        Ok(result.locked.then(|| Self {
+            conn,
+            processor_name: processor_name.to_string(),
+            check_interval,
+        }))
+    }
+
+    /// Resolves with an error once the lock connection fails, after which another replica may
+    /// hold the lock, so the leader has to stop processing.
+    pub async fn lost(mut self) -> anyhow::Error {
+        loop {
+            tokio::time::sleep(self.check_interval).await;
+            let check =
+                tokio::time::timeout(self.check_interval, self.conn.batch_execute("SELECT 1"))
+                    .await;
+            let reason = match check {
+                Ok(Ok(())) => continue,
+                Ok(Err(e)) => e.to_string(),
+                Err(_) => format!("no response within {}s", self.check_interval.as_secs_f64()),
+            };
+            IS_STANDBY.store(true, Ordering::Relaxed);
+            return anyhow::anyhow!(
+                "Lost the leader lock of {}, stopping so another replica can take over: {}",
+                self.processor_name,
+                reason
+            );
+        }
+    }
+}
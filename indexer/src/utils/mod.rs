@@ -2,7 +2,13 @@ pub mod aptos_utils;
 pub mod chain_id;
 pub mod database_connection;
 pub mod database_execution;
+pub mod database_migrations;
 pub mod database_utils;
 pub mod latest_processed_version_tracker;
+pub mod leader_election;
+pub mod shutdown;
 pub mod starting_version;
 pub mod time_utils;
+pub mod tracked_contracts;
+pub mod transaction_filter;
+pub mod transaction_source;
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use std::{sync::OnceLock, time::Duration};
+use tokio::sync::watch;
+
+// Set once a shutdown is requested, shared by every pipeline of the process
+static SHUTDOWN: OnceLock<watch::Sender<bool>> = OnceLock::new();
+
+fn shutdown_sender() -> &'static watch::Sender<bool> {
+    SHUTDOWN.get_or_init(|| watch::channel(false).0)
+}
+
+/// Ask every running pipeline to stop reading transactions. Batches already read still go
+/// through the extractor, storer and version tracker before the pipeline ends.
+pub fn request_shutdown() {
+    shutdown_sender().send_replace(true);
+}
+
+pub fn is_shutdown_requested() -> bool {
+    *shutdown_sender().borrow()
+}
+
+/// Resolves once a shutdown is requested.
+pub async fn shutdown_requested() {
+    let mut receiver = shutdown_sender().subscribe();
+    // The sender is static, so the channel never closes
+    let _ = receiver.wait_for(|requested| *requested).await;
+}
+
+/// Resolves `timeout` after a shutdown is requested, to bound how long a pipeline can drain.
+pub async fn shutdown_deadline(timeout: Duration) {
+    shutdown_requested().await;
+    tokio::time::sleep(timeout).await;
+}
+
+/// Wait for SIGTERM or ctrl-c, then request a shutdown.
+pub async fn request_shutdown_on_signal() -> Result<()> {
+    #[cfg(unix)]
+    {
+        let mut sigterm =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            _ = sigterm.recv() => tracing::info!("Received SIGTERM, shutting down"),
+            res = tokio::signal::ctrl_c() => {
+                res?;
+                tracing::info!("Received ctrl-c, shutting down");
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await?;
+        tracing::info!("Received ctrl-c, shutting down");
+    }
+    request_shutdown();
+    Ok(())
+}
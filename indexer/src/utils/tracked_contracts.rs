@@ -0,0 +1,217 @@
+use anyhow::{Context, Result};
+use aptos_indexer_processor_sdk::utils::convert::standardize_address;
+use std::{fmt, str::FromStr, sync::Arc, time::Duration};
+use tokio::sync::watch;
+
+use super::{database_connection::get_db_connection, database_utils::ArcDbPool};
+use crate::{
+    config::indexer_processor_config::IndexerProcessorConfig,
+    db_models::tracked_contracts::TrackedContract,
+    indexers::marketplace_indexer::event_parsers::ParserFamily,
+};
+
+/// Processors a tracked contract is tracked by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum TrackedContractKind {
+    Marketplace,
+    ContractUpgrade,
+}
+
+impl TrackedContractKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrackedContractKind::Marketplace => "marketplace",
+            TrackedContractKind::ContractUpgrade => "contract_upgrade",
+        }
+    }
+}
+
+impl FromStr for TrackedContractKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "marketplace" => Ok(TrackedContractKind::Marketplace),
+            "contract_upgrade" => Ok(TrackedContractKind::ContractUpgrade),
+            _ => Err(anyhow::anyhow!("Unsupported tracked contract kind: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for TrackedContractKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A row of tracked_contracts as the processors use it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchedContract {
+    // Padded and lowercase, like the configured addresses after normalization
+    pub contract_address: String,
+    pub kind: TrackedContractKind,
+    pub parser_family: Option<ParserFamily>,
+    pub start_version: u64,
+}
+
+impl TryFrom<TrackedContract> for WatchedContract {
+    type Error = anyhow::Error;
+
+    fn try_from(row: TrackedContract) -> Result<Self> {
+        Ok(Self {
+            contract_address: standardize_address(&row.contract_address.to_lowercase()),
+            kind: row.kind.parse()?,
+            parser_family: row.parser_family.as_deref().map(str::parse).transpose()?,
+            start_version: row.start_version as u64,
+        })
+    }
+}
+
+/// The contracts in tracked_contracts when they were last loaded.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Watchlist {
+    contracts: Vec<WatchedContract>,
+}
+
+impl Watchlist {
+    pub fn new(contracts: Vec<WatchedContract>) -> Self {
+        Self { contracts }
+    }
+
+    pub fn contracts(&self, kind: TrackedContractKind) -> impl Iterator<Item = &WatchedContract> {
+        self.contracts
+            .iter()
+            .filter(move |contract| contract.kind == kind)
+    }
+
+    /// The configured contract addresses with the tracked ones of `kind` added by `add` from
+    /// their start versions on, for every version of [start_version, end_version] where they
+    /// change. Usually there is just one for the whole range.
+    pub fn contract_addresses_by_version<T: Clone>(
+        &self,
+        kind: TrackedContractKind,
+        configured: &T,
+        start_version: u64,
+        end_version: u64,
+        add: impl Fn(&mut T, &WatchedContract),
+    ) -> ContractAddressesByVersion<T> {
+        let mut versions = vec![start_version];
+        versions.extend(
+            self.contracts(kind)
+                .map(|contract| contract.start_version)
+                .filter(|version| *version > start_version && *version <= end_version),
+        );
+        versions.sort_unstable();
+        versions.dedup();
+        ContractAddressesByVersion(
+            versions
+                .into_iter()
+                .map(|version| {
+                    let mut contract_addresses = configured.clone();
+                    for contract in self
+                        .contracts(kind)
+                        .filter(|contract| contract.start_version <= version)
+                    {
+                        add(&mut contract_addresses, contract);
+                    }
+                    (version, contract_addresses)
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Contract addresses from each version on, in version order.
+pub struct ContractAddressesByVersion<T>(Vec<(u64, T)>);
+
+impl<T> ContractAddressesByVersion<T> {
+    /// Contract addresses to track at `version`, which must not be before the first version.
+    pub fn at(&self, version: u64) -> &T {
+        let idx = self
+            .0
+            .partition_point(|(from_version, _)| *from_version <= version);
+        &self.0[idx.saturating_sub(1)].1
+    }
+}
+
+/// TrackedContracts follows the tracked_contracts table, it's reloaded in the background every
+/// poll interval so contracts can be added while the processor runs. Clones share the reloads,
+/// which stop once every clone is dropped.
+#[derive(Clone)]
+pub struct TrackedContracts {
+    receiver: watch::Receiver<Arc<Watchlist>>,
+}
+
+impl TrackedContracts {
+    /// Start following tracked_contracts if the config has a tracked_contracts_config.
+    pub async fn watch_if_configured(
+        config: &IndexerProcessorConfig,
+        db_pool: ArcDbPool,
+    ) -> Result<Option<Self>> {
+        match &config.tracked_contracts_config {
+            Some(tracked_contracts_config) => Ok(Some(
+                Self::watch(
+                    db_pool,
+                    Duration::from_secs(tracked_contracts_config.poll_interval_secs),
+                )
+                .await?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn watch(db_pool: ArcDbPool, poll_interval: Duration) -> Result<Self> {
+        let watchlist = load_watchlist(&db_pool)
+            .await
+            .context("Failed to load tracked_contracts")?;
+        tracing::info!(
+            "Tracking {} contracts of tracked_contracts",
+            watchlist.contracts.len()
+        );
+        let (sender, receiver) = watch::channel(Arc::new(watchlist));
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = sender.closed() => return,
+                    _ = tokio::time::sleep(poll_interval) => {}
+                }
+                let watchlist = match load_watchlist(&db_pool).await {
+                    Ok(watchlist) => watchlist,
+                    Err(e) => {
+                        // Keep tracking the contracts loaded last
+                        tracing::warn!("Failed to reload tracked_contracts: {:#}", e);
+                        continue;
+                    }
+                };
+                sender.send_if_modified(|current| {
+                    if **current == watchlist {
+                        return false;
+                    }
+                    tracing::info!(
+                        "tracked_contracts changed, tracking {} contracts",
+                        watchlist.contracts.len()
+                    );
+                    *current = Arc::new(watchlist);
+                    true
+                });
+            }
+        });
+        Ok(Self { receiver })
+    }
+
+    /// The contracts loaded last.
+    pub fn current(&mut self) -> Arc<Watchlist> {
+        self.receiver.borrow_and_update().clone()
+    }
+}
+
+async fn load_watchlist(db_pool: &ArcDbPool) -> Result<Watchlist> {
+    let mut conn = get_db_connection(db_pool).await?;
+    let contracts = TrackedContract::load_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(WatchedContract::try_from)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Watchlist::new(contracts))
+}
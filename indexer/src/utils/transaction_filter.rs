@@ -0,0 +1,238 @@
+use ahash::AHashSet;
+use aptos_indexer_processor_sdk::{
+    aptos_protos::transaction::v1::{write_set_change::Change, Transaction},
+    traits::{async_step::AsyncRunType, AsyncStep, NamedStep, Processable},
+    types::transaction_context::TransactionContext,
+    utils::{convert::standardize_address, errors::ProcessorError},
+};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use super::{
+    database_connection::get_db_connection,
+    database_utils::ArcDbPool,
+    tracked_contracts::{TrackedContractKind, TrackedContracts, Watchlist},
+};
+use crate::{
+    config::indexer_processor_config::CustomConfig,
+    db_models::tracked_contract_activations::TrackedContractActivation,
+    indexers::marketplace_indexer::extractor::{successful_transaction_events, ContractEvent},
+};
+
+/// The transactions a processor can get anything out of, built from its contract addresses:
+/// marketplaces need the events whose type is defined at one of their addresses, contract
+/// upgrades need the write set changes at one of theirs. Failed transactions are never needed,
+/// the extractors skip them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransactionFilter {
+    event_addresses: AHashSet<String>,
+    write_set_addresses: AHashSet<String>,
+    // Kinds of tracked_contracts the processor tracks
+    tracked_kinds: AHashSet<TrackedContractKind>,
+}
+
+impl TransactionFilter {
+    pub fn new(custom_config: &CustomConfig) -> Self {
+        let mut filter = Self::default();
+        filter.add(custom_config);
+        filter
+    }
+
+    fn add(&mut self, custom_config: &CustomConfig) {
+        // Compared padded and lowercase, like the extractors do
+        let standardize = |addr: &String| standardize_address(&addr.to_lowercase());
+        match custom_config {
+            CustomConfig::MarketplaceIndexer(contract_addresses) => {
+                self.event_addresses
+                    .extend(contract_addresses.iter().map(standardize));
+                self.tracked_kinds.insert(TrackedContractKind::Marketplace);
+            }
+            CustomConfig::ContractUpgradeIndexer(contract_addresses) => {
+                self.write_set_addresses
+                    .extend(contract_addresses.iter().map(standardize));
+                self.tracked_kinds
+                    .insert(TrackedContractKind::ContractUpgrade);
+            }
+            CustomConfig::CompositeIndexer(sub_processors) => {
+                for sub_processor in sub_processors {
+                    self.add(&sub_processor.custom_config);
+                }
+            }
+        }
+    }
+
+    /// The filter with the tracked contracts of the processor added, from any version on.
+    pub fn with_watchlist(&self, watchlist: &Watchlist) -> Self {
+        let mut filter = self.clone();
+        for kind in &self.tracked_kinds {
+            let addresses = match kind {
+                TrackedContractKind::Marketplace => &mut filter.event_addresses,
+                TrackedContractKind::ContractUpgrade => &mut filter.write_set_addresses,
+            };
+            addresses.extend(
+                watchlist
+                    .contracts(*kind)
+                    .map(|contract| contract.contract_address.clone()),
+            );
+        }
+        filter
+    }
+
+    pub fn matches(&self, txn: &Transaction) -> bool {
+        let has_tracked_event = successful_transaction_events(txn)
+            .iter()
+            .any(|event| ContractEvent::is_from_contracts(&self.event_addresses, event));
+        has_tracked_event || self.has_tracked_write_set_change(txn)
+    }
+
+    fn has_tracked_write_set_change(&self, txn: &Transaction) -> bool {
+        if self.write_set_addresses.is_empty() {
+            return false;
+        }
+        let Some(info) = txn.info.as_ref().filter(|info| info.success) else {
+            return false;
+        };
+        info.changes
+            .iter()
+            .any(|change| match change.change.as_ref() {
+                Some(Change::WriteModule(write_module)) => self
+                    .write_set_addresses
+                    .contains(&standardize_address(&write_module.address)),
+                Some(Change::WriteResource(write_resource)) => self
+                    .write_set_addresses
+                    .contains(&standardize_address(&write_resource.address)),
+                _ => false,
+            })
+    }
+}
+
+/// TransactionFilterStep drops the transactions a processor can't get anything out of before
+/// they reach the extractor. Batch metadata is kept, so the version tracker still moves past the
+/// versions of the dropped transactions.
+pub struct TransactionFilterStep
+where
+    Self: Sized + Send + 'static,
+{
+    filter: TransactionFilter,
+    // With the watchlist below added, the filter that's applied
+    tracked_filter: Option<(TrackedContracts, Arc<Watchlist>, TransactionFilter)>,
+    // Processors and the kind of contracts they track, to record the version each tracked
+    // contract is picked up at for
+    activations: Option<(ArcDbPool, Vec<(String, TrackedContractKind)>)>,
+    // Watchlist the activations were last recorded for
+    recorded_watchlist: Option<Arc<Watchlist>>,
+}
+
+impl TransactionFilterStep {
+    pub fn new(filter: TransactionFilter) -> Self {
+        Self {
+            filter,
+            tracked_filter: None,
+            activations: None,
+            recorded_watchlist: None,
+        }
+    }
+
+    /// Record in tracked_contract_activations the first version the processors track each
+    /// tracked contract from, which a backfill of the contract runs up to.
+    pub fn with_activations(
+        mut self,
+        db_pool: ArcDbPool,
+        processors: Vec<(String, TrackedContractKind)>,
+    ) -> Self {
+        self.activations = Some((db_pool, processors));
+        self
+    }
+
+    pub fn with_tracked_contracts(mut self, tracked_contracts: Option<TrackedContracts>) -> Self {
+        self.tracked_filter = tracked_contracts.map(|mut tracked_contracts| {
+            let watchlist = tracked_contracts.current();
+            let filter = self.filter.with_watchlist(&watchlist);
+            (tracked_contracts, watchlist, filter)
+        });
+        self
+    }
+
+    fn current_filter(&mut self) -> &TransactionFilter {
+        match &mut self.tracked_filter {
+            Some((tracked_contracts, watchlist, filter)) => {
+                let current = tracked_contracts.current();
+                if !Arc::ptr_eq(&current, watchlist) {
+                    *filter = self.filter.with_watchlist(&current);
+                    *watchlist = current;
+                }
+                filter
+            }
+            None => &self.filter,
+        }
+    }
+
+    /// Record the contracts of the applied watchlist that weren't in the one recorded last as
+    /// tracked from `version` on. At startup every contract is recorded, it may have been
+    /// tracked while the processor was stopped.
+    async fn record_activations(&mut self, version: u64) -> Result<(), ProcessorError> {
+        let (Some((_, watchlist, _)), Some((db_pool, processors))) =
+            (&self.tracked_filter, &self.activations)
+        else {
+            return Ok(());
+        };
+        let recorded = self.recorded_watchlist.as_ref();
+        if recorded.is_some_and(|recorded| Arc::ptr_eq(recorded, watchlist)) {
+            return Ok(());
+        }
+        let activations = processors
+            .iter()
+            .flat_map(|(processor, kind)| {
+                watchlist
+                    .contracts(*kind)
+                    .filter(move |contract| {
+                        !recorded.is_some_and(|recorded| {
+                            recorded.contracts(*kind).any(|other| other == *contract)
+                        })
+                    })
+                    .map(move |contract| TrackedContractActivation {
+                        processor: processor.clone(),
+                        contract_address: contract.contract_address.clone(),
+                        kind: kind.to_string(),
+                        activated_version: version as i64,
+                    })
+            })
+            .collect::<Vec<_>>();
+        if !activations.is_empty() {
+            let mut conn = get_db_connection(db_pool).await?;
+            TrackedContractActivation::record(activations, &mut conn)
+                .await
+                .map_err(|e| ProcessorError::DBStoreError {
+                    message: format!("Failed to record tracked contract activations: {:?}", e),
+                    query: None,
+                })?;
+        }
+        self.recorded_watchlist = Some(watchlist.clone());
+        Ok(())
+    }
+}
+
+impl AsyncStep for TransactionFilterStep {}
+
+impl NamedStep for TransactionFilterStep {
+    fn name(&self) -> String {
+        "TransactionFilterStep".to_string()
+    }
+}
+
+#[async_trait]
+impl Processable for TransactionFilterStep {
+    type Input = Vec<Transaction>;
+    type Output = Vec<Transaction>;
+    type RunType = AsyncRunType;
+
+    async fn process(
+        &mut self,
+        mut item: TransactionContext<Vec<Transaction>>,
+    ) -> Result<Option<TransactionContext<Vec<Transaction>>>, ProcessorError> {
+        let filter = self.current_filter();
+        item.data.retain(|txn| filter.matches(txn));
+        self.record_activations(item.metadata.start_version).await?;
+        Ok(Some(item))
+    }
+}
@@ -0,0 +1,398 @@
+use anyhow::{Context, Result};
+use aptos_indexer_processor_sdk::{
+    aptos_indexer_transaction_stream::{TransactionStream, TransactionStreamConfig},
+    aptos_protos::{indexer::v1::TransactionsInStorage, transaction::v1::Transaction},
+    common_steps::TransactionStreamStep,
+    traits::{NamedStep, PollableAsyncRunType, PollableAsyncStep, Processable},
+    types::transaction_context::{TransactionContext, TransactionMetadata},
+    utils::errors::ProcessorError,
+};
+use async_trait::async_trait;
+use prost::Message;
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+};
+
+use super::shutdown::{is_shutdown_requested, shutdown_requested};
+use crate::{
+    config::indexer_processor_config::{FileSourceConfig, IndexerProcessorConfig},
+    indexers::composite_indexer::fanout::FanoutSource,
+};
+
+// Written next to recorded transaction files, contains the chain id of the recorded network
+pub const CHAIN_ID_FILE_NAME: &str = "chain_id";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum TransactionFileFormat {
+    // JSON array of transactions
+    Json,
+    // TransactionsInStorage protobuf
+    Protobuf,
+}
+
+impl TransactionFileFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TransactionFileFormat::Json => "json",
+            TransactionFileFormat::Protobuf => "pb",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(TransactionFileFormat::Json),
+            "pb" => Some(TransactionFileFormat::Protobuf),
+            _ => None,
+        }
+    }
+}
+
+pub fn read_transactions_file(path: &Path) -> Result<Vec<Transaction>> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    match TransactionFileFormat::from_path(path) {
+        Some(TransactionFileFormat::Json) => serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed to parse transactions from {}", path.display())),
+        Some(TransactionFileFormat::Protobuf) => {
+            Ok(TransactionsInStorage::decode(bytes.as_slice())
+                .with_context(|| format!("Failed to decode transactions from {}", path.display()))?
+                .transactions)
+        }
+        None => Err(anyhow::anyhow!(
+            "Unsupported transaction file {}, expected .json or .pb",
+            path.display()
+        )),
+    }
+}
+
+pub fn write_transactions_file(
+    dir: &Path,
+    transactions: Vec<Transaction>,
+    format: TransactionFileFormat,
+) -> Result<PathBuf> {
+    let (first_version, last_version) = match (transactions.first(), transactions.last()) {
+        (Some(first), Some(last)) => (first.version, last.version),
+        _ => return Err(anyhow::anyhow!("No transactions to write")),
+    };
+    // Zero padded so files sort by version
+    let path = dir.join(format!(
+        "{:020}-{:020}.{}",
+        first_version,
+        last_version,
+        format.extension()
+    ));
+    let bytes = match format {
+        TransactionFileFormat::Json => serde_json::to_vec(&transactions)?,
+        TransactionFileFormat::Protobuf => TransactionsInStorage {
+            transactions,
+            starting_version: Some(first_version),
+        }
+        .encode_to_vec(),
+    };
+    std::fs::write(&path, bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// FileTransactionSource is a step that reads recorded transactions from the .json and .pb
+/// files of a directory, in file name order, instead of the transaction stream.
+/// Versions missing from the files are treated as having no transactions, so batches always
+/// cover contiguous version ranges and the version tracker can move past them.
+pub struct FileTransactionSource
+where
+    Self: Sized + Send + 'static,
+{
+    files: VecDeque<PathBuf>,
+    // Transactions read from files but not returned yet
+    pending: VecDeque<Transaction>,
+    batch_size: usize,
+    next_version: u64,
+    ending_version: Option<u64>,
+    finished: bool,
+}
+
+impl FileTransactionSource {
+    pub fn new(
+        config: &FileSourceConfig,
+        starting_version: u64,
+        ending_version: Option<u64>,
+    ) -> Result<Self> {
+        let mut files = std::fs::read_dir(&config.directory)
+            .with_context(|| format!("Failed to list {}", config.directory.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|path| TransactionFileFormat::from_path(path).is_some())
+            .collect::<Vec<_>>();
+        files.sort();
+        anyhow::ensure!(
+            !files.is_empty(),
+            "No .json or .pb transaction files in {}",
+            config.directory.display()
+        );
+
+        Ok(Self {
+            files: files.into(),
+            pending: VecDeque::new(),
+            batch_size: config.batch_size.max(1),
+            next_version: starting_version,
+            ending_version,
+            finished: false,
+        })
+    }
+
+    fn is_past_ending_version(&self, version: u64) -> bool {
+        self.ending_version
+            .is_some_and(|ending_version| version > ending_version)
+    }
+
+    fn next_batch(&mut self) -> Result<Option<TransactionContext<Vec<Transaction>>>> {
+        while self.pending.len() < self.batch_size {
+            let Some(path) = self.files.pop_front() else {
+                break;
+            };
+            let mut last_version = self.pending.back().map(|txn| txn.version);
+            for txn in read_transactions_file(&path)? {
+                if last_version.is_some_and(|version| txn.version <= version) {
+                    return Err(anyhow::anyhow!(
+                        "Transaction versions in {} are not ascending across files at version {}",
+                        path.display(),
+                        txn.version
+                    ));
+                }
+                last_version = Some(txn.version);
+                if txn.version >= self.next_version && !self.is_past_ending_version(txn.version) {
+                    self.pending.push_back(txn);
+                }
+            }
+        }
+
+        let take = self.pending.len().min(self.batch_size);
+        let transactions = self.pending.drain(..take).collect::<Vec<_>>();
+        let Some(last_txn) = transactions.last() else {
+            // Versions after the last recorded transaction are empty too, the range is only
+            // covered once they're reported
+            return Ok(match self.ending_version {
+                Some(ending_version) if self.next_version <= ending_version => {
+                    let metadata = TransactionMetadata {
+                        start_version: self.next_version,
+                        end_version: ending_version,
+                        start_transaction_timestamp: None,
+                        end_transaction_timestamp: None,
+                        total_size_in_bytes: 0,
+                    };
+                    self.next_version = ending_version + 1;
+                    Some(TransactionContext {
+                        data: vec![],
+                        metadata,
+                    })
+                }
+                _ => {
+                    self.finished = true;
+                    None
+                }
+            });
+        };
+
+        let metadata = TransactionMetadata {
+            start_version: self.next_version,
+            end_version: last_txn.version,
+            start_transaction_timestamp: transactions[0].timestamp.clone(),
+            end_transaction_timestamp: last_txn.timestamp.clone(),
+            total_size_in_bytes: transactions
+                .iter()
+                .map(|txn| txn.encoded_len() as u64)
+                .sum(),
+        };
+        self.next_version = last_txn.version + 1;
+        Ok(Some(TransactionContext {
+            data: transactions,
+            metadata,
+        }))
+    }
+}
+
+impl NamedStep for FileTransactionSource {
+    fn name(&self) -> String {
+        "FileTransactionSource".to_string()
+    }
+}
+
+#[async_trait]
+impl Processable for FileTransactionSource {
+    type Input = ();
+    type Output = Vec<Transaction>;
+    type RunType = PollableAsyncRunType;
+
+    async fn process(
+        &mut self,
+        _item: TransactionContext<()>,
+    ) -> Result<Option<TransactionContext<Vec<Transaction>>>, ProcessorError> {
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl PollableAsyncStep for FileTransactionSource {
+    fn poll_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(0)
+    }
+
+    async fn poll(
+        &mut self,
+    ) -> Result<Option<Vec<TransactionContext<Vec<Transaction>>>>, ProcessorError> {
+        self.next_batch()
+            .map(|batch| batch.map(|batch| vec![batch]))
+            .map_err(|e| ProcessorError::PollError {
+                message: format!("{:#}", e),
+            })
+    }
+
+    async fn should_continue_polling(&mut self) -> bool {
+        !self.finished
+    }
+}
+
+/// TransactionSourceStep is the first step of a processor, it reads transactions from the
+/// transaction stream, or from recorded files when file_source_config is set. Sub-processors of
+/// the composite indexer read the batches of its stream instead.
+/// It stops once a shutdown is requested, which lets the following steps drain and end.
+pub enum TransactionSourceStep {
+    Stream(TransactionStreamStep),
+    File(FileTransactionSource),
+    Fanout(FanoutSource),
+}
+
+impl TransactionSourceStep {
+    pub async fn new(
+        config: &IndexerProcessorConfig,
+        starting_version: u64,
+        request_ending_version: Option<u64>,
+    ) -> Result<Self> {
+        match &config.file_source_config {
+            Some(file_source_config) => Ok(Self::File(FileTransactionSource::new(
+                file_source_config,
+                starting_version,
+                request_ending_version,
+            )?)),
+            None => Ok(Self::Stream(
+                TransactionStreamStep::new(TransactionStreamConfig {
+                    starting_version: Some(starting_version),
+                    request_ending_version,
+                    ..config.transaction_stream_config.clone()
+                })
+                .await?,
+            )),
+        }
+    }
+
+    /// Chain id of the transactions, None for recorded files without a chain id file.
+    pub async fn get_chain_id(config: &IndexerProcessorConfig) -> Result<Option<u64>> {
+        match &config.file_source_config {
+            Some(file_source_config) => {
+                let path = file_source_config.directory.join(CHAIN_ID_FILE_NAME);
+                if !path.exists() {
+                    return Ok(None);
+                }
+                let chain_id = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                Ok(Some(chain_id.trim().parse().with_context(|| {
+                    format!("Invalid chain id in {}", path.display())
+                })?))
+            }
+            None => Ok(Some(
+                TransactionStream::new(config.transaction_stream_config.clone())
+                    .await?
+                    .get_chain_id()
+                    .await?,
+            )),
+        }
+    }
+}
+
+impl NamedStep for TransactionSourceStep {
+    fn name(&self) -> String {
+        match self {
+            TransactionSourceStep::Stream(step) => step.name(),
+            TransactionSourceStep::File(step) => step.name(),
+            TransactionSourceStep::Fanout(step) => step.name(),
+        }
+    }
+}
+
+#[async_trait]
+impl Processable for TransactionSourceStep {
+    type Input = ();
+    type Output = Vec<Transaction>;
+    type RunType = PollableAsyncRunType;
+
+    async fn init(&mut self) {
+        match self {
+            TransactionSourceStep::Stream(step) => step.init().await,
+            TransactionSourceStep::File(step) => step.init().await,
+            TransactionSourceStep::Fanout(step) => step.init().await,
+        }
+    }
+
+    async fn process(
+        &mut self,
+        item: TransactionContext<()>,
+    ) -> Result<Option<TransactionContext<Vec<Transaction>>>, ProcessorError> {
+        match self {
+            TransactionSourceStep::Stream(step) => step.process(item).await,
+            TransactionSourceStep::File(step) => step.process(item).await,
+            TransactionSourceStep::Fanout(step) => step.process(item).await,
+        }
+    }
+
+    async fn cleanup(
+        &mut self,
+    ) -> Result<Option<Vec<TransactionContext<Vec<Transaction>>>>, ProcessorError> {
+        match self {
+            TransactionSourceStep::Stream(step) => step.cleanup().await,
+            TransactionSourceStep::File(step) => step.cleanup().await,
+            TransactionSourceStep::Fanout(step) => step.cleanup().await,
+        }
+    }
+}
+
+#[async_trait]
+impl PollableAsyncStep for TransactionSourceStep {
+    fn poll_interval(&self) -> std::time::Duration {
+        match self {
+            TransactionSourceStep::Stream(step) => step.poll_interval(),
+            TransactionSourceStep::File(step) => step.poll_interval(),
+            TransactionSourceStep::Fanout(step) => step.poll_interval(),
+        }
+    }
+
+    async fn poll(
+        &mut self,
+    ) -> Result<Option<Vec<TransactionContext<Vec<Transaction>>>>, ProcessorError> {
+        let poll = async {
+            match self {
+                TransactionSourceStep::Stream(step) => step.poll().await,
+                TransactionSourceStep::File(step) => step.poll().await,
+                TransactionSourceStep::Fanout(step) => step.poll().await,
+            }
+        };
+        // Don't wait for the stream to send the next batch, a partially read batch is dropped
+        // and read again on restart
+        tokio::select! {
+            biased;
+            _ = shutdown_requested() => Ok(None),
+            res = poll => res,
+        }
+    }
+
+    async fn should_continue_polling(&mut self) -> bool {
+        if is_shutdown_requested() {
+            return false;
+        }
+        match self {
+            TransactionSourceStep::Stream(step) => step.should_continue_polling().await,
+            TransactionSourceStep::File(step) => step.should_continue_polling().await,
+            TransactionSourceStep::Fanout(step) => step.should_continue_polling().await,
+        }
+    }
+}
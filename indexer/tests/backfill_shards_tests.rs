@@ -0,0 +1,131 @@
+//! Tests of claiming backfill shards against a real Postgres, see `common` for how the database
+//! is provisioned.
+
+mod common;
+
+use common::TestDatabase;
+use diesel::{
+    dsl::{now, IntervalDsl},
+    ExpressionMethods, QueryDsl,
+};
+use diesel_async::RunQueryDsl;
+use indexer::{
+    db_models::backfill_shards::BackfillShard,
+    schema::backfill_shards,
+    utils::{
+        aptos_utils::BackfillShardStatus, database_connection::get_db_connection,
+        database_utils::DbPoolConnection,
+    },
+};
+
+const BACKFILL_NAME: &str = "test_backfill";
+const LEASE_SECS: u64 = 60;
+const MAX_ATTEMPTS: i32 = 2;
+
+/// Move the shard's last heartbeat back past its lease, like its worker died.
+async fn expire_lease(shard_id: i64, conn: &mut DbPoolConnection<'_>) {
+    diesel::update(backfill_shards::table.find(shard_id))
+        .set(backfill_shards::last_updated.eq(now - (2 * LEASE_SECS as i64).seconds()))
+        .execute(conn)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_worker_that_lost_its_lease_leaves_the_shard_alone() {
+    let db = TestDatabase::new().await;
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    BackfillShard::create_shards(BACKFILL_NAME, 0, 99, 100, &mut conn)
+        .await
+        .unwrap();
+    let claimed_by_a =
+        BackfillShard::claim_next(BACKFILL_NAME, "a", LEASE_SECS, MAX_ATTEMPTS, &mut conn)
+            .await
+            .unwrap()
+            .unwrap();
+
+    // Worker a stops heartbeating until its lease runs out, so worker b claims the shard
+    expire_lease(claimed_by_a.shard_id, &mut conn).await;
+    let claimed_by_b =
+        BackfillShard::claim_next(BACKFILL_NAME, "b", LEASE_SECS, MAX_ATTEMPTS, &mut conn)
+            .await
+            .unwrap()
+            .unwrap();
+    assert_eq!(claimed_by_b.shard_id, claimed_by_a.shard_id);
+
+    // Worker a finishing or failing late doesn't touch worker b's claim
+    assert_eq!(
+        BackfillShard::mark_completed(claimed_by_a.shard_id, "a", &mut conn)
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        claimed_by_a
+            .mark_failed("error".to_string(), 1, &mut conn)
+            .await
+            .unwrap(),
+        0
+    );
+    let (status, worker_id) = backfill_shards::table
+        .find(claimed_by_a.shard_id)
+        .select((backfill_shards::status, backfill_shards::worker_id))
+        .first::<(i32, Option<String>)>(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(status, BackfillShardStatus::InProgress as i32);
+    assert_eq!(worker_id.as_deref(), Some("b"));
+
+    assert_eq!(
+        BackfillShard::mark_completed(claimed_by_b.shard_id, "b", &mut conn)
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        BackfillShard::count_remaining(BACKFILL_NAME, &mut conn)
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_shard_whose_lease_keeps_expiring_fails() {
+    let db = TestDatabase::new().await;
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    BackfillShard::create_shards(BACKFILL_NAME, 0, 99, 100, &mut conn)
+        .await
+        .unwrap();
+
+    // Every worker that claims the shard dies without releasing it
+    for attempt in 1..=MAX_ATTEMPTS {
+        let shard =
+            BackfillShard::claim_next(BACKFILL_NAME, "a", LEASE_SECS, MAX_ATTEMPTS, &mut conn)
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(shard.attempts, attempt);
+        expire_lease(shard.shard_id, &mut conn).await;
+    }
+
+    assert!(
+        BackfillShard::claim_next(BACKFILL_NAME, "a", LEASE_SECS, MAX_ATTEMPTS, &mut conn)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    let (status, worker_id) = backfill_shards::table
+        .select((backfill_shards::status, backfill_shards::worker_id))
+        .first::<(i32, Option<String>)>(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(status, BackfillShardStatus::Failed as i32);
+    assert_eq!(worker_id, None);
+    assert_eq!(
+        BackfillShard::count_remaining(BACKFILL_NAME, &mut conn)
+            .await
+            .unwrap(),
+        0
+    );
+}
@@ -0,0 +1,254 @@
+//! Shared helpers for the integration tests.
+//!
+//! Tests run against `TEST_DATABASE_URL` when it's set (e.g. a local Postgres in CI), otherwise an
+//! ephemeral Postgres is started in docker. Every test gets its own freshly migrated database so
+//! tests can run in parallel.
+#![allow(dead_code)]
+
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use aptos_indexer_processor_sdk::{
+    aptos_protos::transaction::v1::Transaction, traits::Processable,
+    types::transaction_context::TransactionContext,
+};
+use diesel_async::{AsyncConnection, AsyncPgConnection};
+use indexer::{
+    config::indexer_processor_config::{DbConfig, IndexerProcessorConfig},
+    indexers::marketplace_indexer::{
+        dry_run::{DryRunReport, DryRunSession},
+        extractor::Extractor,
+        storer::Storer,
+    },
+    utils::{
+        database_connection::new_db_pool, database_migrations::run_migrations,
+        database_utils::ArcDbPool, latest_processed_version_tracker::LatestVersionProcessedTracker,
+        transaction_source::read_transactions_file,
+    },
+};
+
+// Not every test binary uses it
+#[allow(unused_imports)]
+pub use indexer::testing::batch;
+use serde_json::json;
+use testcontainers::{runners::AsyncRunner, ContainerAsync};
+use testcontainers_modules::postgres::Postgres;
+
+pub const WAPAL_MARKETPLACE_ADDR: &str =
+    "0x584b50b999c78ade62f8359c91b5165ff390338d45f8e55969a04e65d76258c9";
+pub const TRADEPORT_V2_MARKETPLACE_ADDR: &str =
+    "0xe11c12ec495f3989c35e1c6a0af414451223305b579291fc8f3d9d0575a23c26";
+
+pub const TEST_TRACKER_NAME: &str = "test_marketplace_processor";
+
+static DATABASE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub struct TestDatabase {
+    pub url: String,
+    pub pool: ArcDbPool,
+    // Keeps the container alive for as long as the test holds the database
+    _container: Option<ContainerAsync<Postgres>>,
+}
+
+impl TestDatabase {
+    pub async fn new() -> Self {
+        use diesel_async::RunQueryDsl;
+
+        let (server_url, container) = match std::env::var("TEST_DATABASE_URL") {
+            Ok(url) => (url.trim_end_matches('/').to_string(), None),
+            Err(_) => {
+                let container = Postgres::default()
+                    .start()
+                    .await
+                    .expect("Failed to start postgres container, is docker running?");
+                let host = container.get_host().await.expect("Failed to get host");
+                let port = container
+                    .get_host_port_ipv4(5432)
+                    .await
+                    .expect("Failed to get postgres port");
+                (
+                    format!("postgres://postgres:postgres@{}:{}/postgres", host, port),
+                    Some(container),
+                )
+            }
+        };
+
+        let db_name = format!(
+            "marketplace_test_{}_{}",
+            std::process::id(),
+            DATABASE_COUNTER.fetch_add(1, Ordering::SeqCst)
+        );
+        let mut admin_conn = AsyncPgConnection::establish(&server_url)
+            .await
+            .expect("Failed to connect to test postgres");
+        diesel::sql_query(format!("DROP DATABASE IF EXISTS {}", db_name))
+            .execute(&mut admin_conn)
+            .await
+            .expect("Failed to drop test database");
+        diesel::sql_query(format!("CREATE DATABASE {}", db_name))
+            .execute(&mut admin_conn)
+            .await
+            .expect("Failed to create test database");
+
+        let url = match server_url.rsplit_once('/') {
+            Some((base, _)) => format!("{}/{}", base, db_name),
+            None => panic!("Invalid TEST_DATABASE_URL: {}", server_url),
+        };
+        run_migrations(url.clone())
+            .await
+            .expect("Failed to run migrations on test database");
+        let pool = new_db_pool(&url, 10).await;
+
+        Self {
+            url,
+            pool,
+            _container: container,
+        }
+    }
+}
+
+impl TestDatabase {
+    /// The same database through `db_config`, with the migrations run in its schema. Doesn't own
+    /// the Postgres server, keep `self` alive while using it.
+    pub async fn with_db_config(&self, db_config: &DbConfig) -> TestDatabase {
+        use diesel_async::RunQueryDsl;
+
+        let schema = db_config.schema.as_ref().expect("db_config has no schema");
+        let mut conn = self.pool.get().await.expect("Failed to get connection");
+        diesel::sql_query(format!("CREATE SCHEMA {}", schema))
+            .execute(&mut conn)
+            .await
+            .expect("Failed to create schema");
+
+        let url = db_config.connection_string().unwrap();
+        run_migrations(url.clone())
+            .await
+            .expect("Failed to run migrations in schema");
+        let pool = new_db_pool(&url, 10).await;
+
+        TestDatabase {
+            url,
+            pool,
+            _container: None,
+        }
+    }
+}
+
+pub fn fixture_path(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(relative)
+}
+
+/// Load every recorded transaction file of a fixture directory, in version order.
+pub fn load_transactions(fixture_dir: &str) -> Vec<Transaction> {
+    let dir = fixture_path(fixture_dir);
+    let mut files = std::fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("Failed to read fixture dir {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+        .iter()
+        .flat_map(|path| read_transactions_file(path).unwrap())
+        .collect()
+}
+
+/// Run batches through the extractor, storer and version tracker in the given order, the same
+/// steps the marketplace processor connects together.
+pub async fn run_pipeline(
+    db: &TestDatabase,
+    starting_version: u64,
+    batches: Vec<TransactionContext<Vec<Transaction>>>,
+) {
+    let mut extractor = Extractor::new(
+        vec![
+            WAPAL_MARKETPLACE_ADDR.to_string(),
+            TRADEPORT_V2_MARKETPLACE_ADDR.to_string(),
+        ],
+        true,
+    );
+    let mut storer = Storer::new(db.pool.clone());
+    let mut tracker = LatestVersionProcessedTracker::new_with_pool(
+        db.pool.clone(),
+        starting_version,
+        TEST_TRACKER_NAME.to_string(),
+    );
+
+    for batch in batches {
+        let extracted = extractor
+            .process(batch)
+            .await
+            .expect("Extractor failed")
+            .expect("Extractor returned no output");
+        let stored = storer
+            .process(extracted)
+            .await
+            .expect("Storer failed")
+            .expect("Storer returned no output");
+        tracker.process(stored).await.expect("Tracker failed");
+    }
+    tracker.cleanup().await.expect("Tracker cleanup failed");
+}
+
+/// Run batches through the extractor and a dry run storer, the same steps the dry-run command
+/// connects together.
+pub async fn run_dry_run(
+    db: &TestDatabase,
+    batches: Vec<TransactionContext<Vec<Transaction>>>,
+) -> DryRunReport {
+    let session = DryRunSession::start(&db.url)
+        .await
+        .expect("Failed to start dry run session");
+    let mut extractor = Extractor::new(
+        vec![
+            WAPAL_MARKETPLACE_ADDR.to_string(),
+            TRADEPORT_V2_MARKETPLACE_ADDR.to_string(),
+        ],
+        false,
+    );
+    let mut storer = session.storer();
+
+    let start_version = batches.first().map(|b| b.metadata.start_version);
+    let end_version = batches.last().map(|b| b.metadata.end_version);
+    for batch in batches {
+        let extracted = extractor
+            .process(batch)
+            .await
+            .expect("Extractor failed")
+            .expect("Extractor returned no output");
+        storer
+            .process(extracted)
+            .await
+            .expect("Dry run storer failed");
+    }
+    session
+        .finish(
+            start_version.unwrap_or_default(),
+            end_version.unwrap_or_default(),
+        )
+        .await
+        .expect("Failed to build dry run report")
+}
+
+/// Marketplace processor config for the test database that reads the recorded marketplace
+/// transactions.
+pub fn marketplace_config(db: &TestDatabase) -> IndexerProcessorConfig {
+    serde_json::from_value(json!({
+        "processor_config": { "type": "wapal_marketplace_indexer" },
+        "transaction_stream_config": {
+            "indexer_grpc_data_service_address": "https://grpc.mainnet.aptoslabs.com:443",
+            "auth_token": "_",
+            "request_name_header": "_",
+        },
+        "db_config": { "postgres_connection_string": db.url, "ssl_mode": "disable" },
+        // Uppercase on purpose, the extractor lowercases it
+        "custom_config": {
+            "marketplace_indexer": [WAPAL_MARKETPLACE_ADDR.to_uppercase().replace("0X", "0x")]
+        },
+        "file_source_config": { "directory": fixture_path("transactions/marketplace") },
+    }))
+    .unwrap()
+}
@@ -0,0 +1,72 @@
+//! Tests of the composite indexer fanning one transaction stream out to its sub-processors.
+
+mod common;
+
+use aptos_indexer_processor_sdk::traits::{PollableAsyncStep, Processable};
+use common::{batch, load_transactions};
+use indexer::indexers::composite_indexer::fanout::{FanoutSource, FanoutStep};
+use tokio::sync::mpsc;
+
+#[tokio::test]
+async fn test_fanout_sends_each_sub_processor_its_versions() {
+    let (caught_up_sender, caught_up_receiver) = mpsc::channel(10);
+    let (behind_sender, behind_receiver) = mpsc::channel(10);
+    let mut fanout = FanoutStep::new(vec![
+        ("wapal_marketplace_indexer".to_string(), caught_up_sender),
+        ("contract_upgrade_indexer".to_string(), behind_sender),
+    ]);
+    // The stream starts at 100 for the sub-processor that is behind
+    let mut caught_up = FanoutSource::new(caught_up_receiver, 105);
+    let mut behind = FanoutSource::new(behind_receiver, 100);
+
+    let transactions = load_transactions("transactions/marketplace");
+    let (first_batch, second_batch) = transactions.split_at(3);
+    for transactions in [first_batch, second_batch] {
+        let output = fanout
+            .process(batch(transactions.to_vec()))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            output.metadata.end_version,
+            transactions.last().unwrap().version
+        );
+    }
+
+    // [100, 102] is before its starting version, [103, 107] starts at it
+    let batches = caught_up.poll().await.unwrap();
+    assert!(batches.is_none());
+    let batches = caught_up.poll().await.unwrap().unwrap();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].metadata.start_version, 105);
+    assert_eq!(batches[0].metadata.end_version, 107);
+    assert_eq!(
+        batches[0]
+            .data
+            .iter()
+            .map(|txn| txn.version)
+            .collect::<Vec<_>>(),
+        vec![105, 106, 107]
+    );
+
+    for (start_version, end_version) in [(100, 102), (103, 107)] {
+        let batches = behind.poll().await.unwrap().unwrap();
+        assert_eq!(batches[0].metadata.start_version, start_version);
+        assert_eq!(batches[0].metadata.end_version, end_version);
+    }
+
+    // The sources end with the stream
+    drop(fanout);
+    assert!(behind.poll().await.unwrap().is_none());
+    assert!(!behind.should_continue_polling().await);
+}
+
+#[tokio::test]
+async fn test_fanout_fails_when_a_sub_processor_stopped() {
+    let (sender, receiver) = mpsc::channel(10);
+    let mut fanout = FanoutStep::new(vec![("wapal_marketplace_indexer".to_string(), sender)]);
+    drop(receiver);
+
+    let transactions = load_transactions("transactions/marketplace");
+    assert!(fanout.process(batch(transactions)).await.is_err());
+}
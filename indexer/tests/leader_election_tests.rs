@@ -0,0 +1,59 @@
+//! Tests of the leader election between replicas against a real Postgres, see `common` for how
+//! the database is provisioned.
+
+mod common;
+
+use common::TestDatabase;
+use diesel_async::RunQueryDsl;
+use indexer::utils::{
+    database_connection::get_db_connection,
+    leader_election::{role, LeaderLock},
+};
+use std::time::Duration;
+
+const CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+#[tokio::test]
+async fn test_standby_takes_over_when_leader_loses_its_lock() {
+    let db = TestDatabase::new().await;
+    let leader = LeaderLock::acquire(&db.url, "wapal_marketplace_indexer", CHECK_INTERVAL)
+        .await
+        .unwrap()
+        .expect("First replica should become the leader");
+    assert_eq!(role(), "leader");
+
+    let url = db.url.clone();
+    let standby = tokio::spawn(async move {
+        LeaderLock::acquire(&url, "wapal_marketplace_indexer", CHECK_INTERVAL).await
+    });
+    tokio::time::sleep(CHECK_INTERVAL * 3).await;
+    assert!(!standby.is_finished());
+    assert_eq!(role(), "standby");
+
+    // Other processors have their own lock
+    let other = LeaderLock::acquire(&db.url, "contract_upgrade_indexer", CHECK_INTERVAL)
+        .await
+        .unwrap();
+    assert!(other.is_some());
+
+    // Like the leader's connection dropping
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    diesel::sql_query(
+        "SELECT pg_terminate_backend(pid) FROM pg_locks \
+         WHERE locktype = 'advisory' AND granted AND pid <> pg_backend_pid()",
+    )
+    .execute(&mut conn)
+    .await
+    .unwrap();
+    let lost = tokio::time::timeout(Duration::from_secs(5), leader.lost())
+        .await
+        .expect("Leader should notice it lost the lock");
+    assert!(lost.to_string().contains("Lost the leader lock"));
+
+    let new_leader = tokio::time::timeout(Duration::from_secs(5), standby)
+        .await
+        .expect("Standby should take over")
+        .unwrap()
+        .unwrap();
+    assert!(new_leader.is_some());
+}
@@ -0,0 +1,106 @@
+//! Tests of the lookup tables and views of the enum columns against a real Postgres, see `common`
+//! for how the database is provisioned.
+
+mod common;
+
+use common::{batch, load_transactions, run_pipeline, TestDatabase};
+use diesel::{
+    sql_query,
+    sql_types::{Int4, Int8, Nullable, Text},
+    QueryableByName,
+};
+use diesel_async::RunQueryDsl;
+use indexer::utils::{aptos_utils::lookup_tables, database_connection::get_db_connection};
+
+#[derive(QueryableByName)]
+struct LookupRow {
+    #[diesel(sql_type = Int4)]
+    id: i32,
+    #[diesel(sql_type = Text)]
+    name: String,
+}
+
+#[derive(QueryableByName)]
+struct ActivityLabels {
+    #[diesel(sql_type = Nullable<Text>)]
+    activity_type_name: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    nft_standard_name: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    payment_token_type_name: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    marketplace_name: Option<String>,
+}
+
+#[derive(QueryableByName)]
+struct OpenAskLabels {
+    #[diesel(sql_type = Nullable<Text>)]
+    order_type_name: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    marketplace_name: Option<String>,
+}
+
+#[tokio::test]
+async fn test_lookup_tables_match_enums() {
+    let db = TestDatabase::new().await;
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    for table in lookup_tables() {
+        let rows = sql_query(format!("SELECT id, name FROM {} ORDER BY id", table.name))
+            .load::<LookupRow>(&mut conn)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| (row.id, row.name))
+            .collect::<Vec<_>>();
+        let expected = table
+            .rows
+            .iter()
+            .map(|(id, name)| (*id, name.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            expected,
+            "{} is out of date, add a migration with:\n{}",
+            table.name,
+            table.upsert_sql()
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_views_show_labels() {
+    let db = TestDatabase::new().await;
+    // The ask placed at 100 is filled later on
+    let transactions = load_transactions("transactions/marketplace")
+        .into_iter()
+        .filter(|txn| txn.version == 100)
+        .collect();
+    run_pipeline(&db, 100, vec![batch(transactions)]).await;
+
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    let labels = sql_query(
+        "SELECT activity_type_name, nft_standard_name, payment_token_type_name, marketplace_name \
+         FROM v_activities WHERE activity_tx_version = $1 ORDER BY activity_event_idx",
+    )
+    .bind::<Int8, _>(100)
+    .load::<ActivityLabels>(&mut conn)
+    .await
+    .unwrap();
+    assert!(!labels.is_empty());
+    assert!(labels.iter().all(|label| label.activity_type_name.is_some()
+        && label.nft_standard_name.is_some()
+        && label.payment_token_type_name.is_some()
+        && label.marketplace_name.as_deref() == Some("wapal")));
+    assert!(labels
+        .iter()
+        .any(|label| label.activity_type_name.as_deref() == Some("nft_ask_placed")));
+
+    let open_asks = sql_query("SELECT order_type_name, marketplace_name FROM v_open_asks")
+        .load::<OpenAskLabels>(&mut conn)
+        .await
+        .unwrap();
+    assert!(!open_asks.is_empty());
+    assert!(open_asks
+        .iter()
+        .all(|ask| ask.order_type_name.is_some() && ask.marketplace_name.is_some()));
+}
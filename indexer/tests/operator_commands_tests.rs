@@ -0,0 +1,183 @@
+//! Tests of the operator commands against a real Postgres, see `common` for how the database is
+//! provisioned.
+
+mod common;
+
+use common::{
+    batch, load_transactions, marketplace_config, run_pipeline, TestDatabase, TEST_TRACKER_NAME,
+    TRADEPORT_V2_MARKETPLACE_ADDR,
+};
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use indexer::{
+    commands::{
+        reset_chain_id::ResetChainIdArgs, rewind::RewindArgs, stats::StatsArgs, status::StatusArgs,
+        track_contract::TrackContractArgs, untrack_contract::UntrackContractArgs,
+    },
+    db_models::{
+        ledger_info::LedgerInfo, tracked_contract_activations::TrackedContractActivation,
+        tracked_contracts::TrackedContract,
+    },
+    indexers::marketplace_indexer::event_parsers::ParserFamily,
+    schema::{ledger_infos, operator_audit_log, processor_status},
+    utils::{
+        database_connection::get_db_connection, database_utils::ArcDbPool,
+        tracked_contracts::TrackedContractKind,
+    },
+};
+use serde_json::{json, Value};
+
+async fn audit_log(pool: &ArcDbPool) -> Vec<(String, Value)> {
+    let mut conn = get_db_connection(pool).await.unwrap();
+    operator_audit_log::table
+        .order(operator_audit_log::id)
+        .select((operator_audit_log::command, operator_audit_log::details))
+        .load(&mut conn)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_rewind_moves_processor_back_and_is_audited() {
+    let db = TestDatabase::new().await;
+    let transactions = load_transactions("transactions/marketplace");
+    run_pipeline(&db, 100, vec![batch(transactions)]).await;
+
+    let rewind = |version| RewindArgs {
+        processor: TEST_TRACKER_NAME.to_string(),
+        version,
+        yes: true,
+    };
+    // Only moves processors back
+    assert!(rewind(200).run(marketplace_config(&db)).await.is_err());
+    rewind(103).run(marketplace_config(&db)).await.unwrap();
+
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    let last_success_version: i64 = processor_status::table
+        .filter(processor_status::processor.eq(TEST_TRACKER_NAME))
+        .select(processor_status::last_success_version)
+        .first(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(last_success_version, 102);
+    assert_eq!(
+        audit_log(&db.pool).await,
+        vec![(
+            "rewind".to_string(),
+            json!({
+                "processor": TEST_TRACKER_NAME,
+                "previous_last_success_version": 107,
+                "version": 103,
+            })
+        )]
+    );
+
+    // Status and stats only read
+    StatusArgs {}.run(marketplace_config(&db)).await.unwrap();
+    StatsArgs {}.run(marketplace_config(&db)).await.unwrap();
+    assert_eq!(audit_log(&db.pool).await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_reset_chain_id_removes_chain_id_and_is_audited() {
+    let db = TestDatabase::new().await;
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    diesel::insert_into(ledger_infos::table)
+        .values(LedgerInfo { chain_id: 2 })
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    ResetChainIdArgs { yes: true }
+        .run(marketplace_config(&db))
+        .await
+        .unwrap();
+
+    assert!(LedgerInfo::get(&mut conn).await.unwrap().is_none());
+    assert_eq!(
+        audit_log(&db.pool).await,
+        vec![("reset-chain-id".to_string(), json!({ "chain_id": 2 }))]
+    );
+}
+
+#[tokio::test]
+async fn test_track_and_untrack_contract_are_audited() {
+    let db = TestDatabase::new().await;
+    let track = |kind, parser_family| TrackContractArgs {
+        // Uppercase on purpose, it's stored normalized
+        contract_address: TRADEPORT_V2_MARKETPLACE_ADDR
+            .to_uppercase()
+            .replace("0X", "0x"),
+        kind,
+        parser_family,
+        start_version: 106,
+        backfill: false,
+    };
+    // Parser families are for marketplaces only
+    assert!(track(
+        TrackedContractKind::ContractUpgrade,
+        Some(ParserFamily::TradeportV2)
+    )
+    .run(marketplace_config(&db))
+    .await
+    .is_err());
+    // Picked up when it was tracked before, a backfill waits for processors to pick up the
+    // change instead
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    let activation = TrackedContractActivation {
+        processor: TEST_TRACKER_NAME.to_string(),
+        contract_address: TRADEPORT_V2_MARKETPLACE_ADDR.to_string(),
+        kind: "marketplace".to_string(),
+        activated_version: 100,
+    };
+    TrackedContractActivation::record(vec![activation], &mut conn)
+        .await
+        .unwrap();
+    track(
+        TrackedContractKind::Marketplace,
+        Some(ParserFamily::TradeportV2),
+    )
+    .run(marketplace_config(&db))
+    .await
+    .unwrap();
+
+    let activated_version = TrackedContractActivation::get(
+        TEST_TRACKER_NAME,
+        TRADEPORT_V2_MARKETPLACE_ADDR,
+        "marketplace",
+        &mut conn,
+    )
+    .await
+    .unwrap();
+    assert_eq!(activated_version, None);
+    let tracked = TrackedContract::load_all(&mut conn).await.unwrap();
+    assert_eq!(tracked.len(), 1);
+    assert_eq!(tracked[0].contract_address, TRADEPORT_V2_MARKETPLACE_ADDR);
+    assert_eq!(tracked[0].parser_family.as_deref(), Some("tradeport_v2"));
+
+    let untrack = || UntrackContractArgs {
+        contract_address: TRADEPORT_V2_MARKETPLACE_ADDR.to_string(),
+        kind: TrackedContractKind::Marketplace,
+    };
+    untrack().run(marketplace_config(&db)).await.unwrap();
+    // Nothing left to remove, so nothing is audited
+    untrack().run(marketplace_config(&db)).await.unwrap();
+    assert!(TrackedContract::load_all(&mut conn)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let details = json!({
+        "contract_address": TRADEPORT_V2_MARKETPLACE_ADDR,
+        "kind": "marketplace",
+        "parser_family": "tradeport_v2",
+        "start_version": 106,
+    });
+    assert_eq!(
+        audit_log(&db.pool).await,
+        vec![
+            ("track-contract".to_string(), details.clone()),
+            ("untrack-contract".to_string(), details),
+        ]
+    );
+}
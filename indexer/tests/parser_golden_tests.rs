@@ -0,0 +1,135 @@
+//! Golden-file tests for the marketplace event parsers.
+//!
+//! Every fixture under `tests/fixtures/events/<family>/` is a single on-chain event. It's parsed
+//! the same way the extractor parses events and the resulting rows are compared against
+//! `tests/golden/events/<family>/<case>.json`. Run with `UPDATE_GOLDEN=1` to rewrite the
+//! snapshots after an intended parser change and review the diff.
+
+use std::path::{Path, PathBuf};
+
+use ahash::AHashSet;
+use aptos_indexer_processor_sdk::{
+    aptos_protos::transaction::v1::Event as EventPB, utils::convert::standardize_address,
+};
+use indexer::indexers::marketplace_indexer::extractor::ContractEvent;
+use serde::Deserialize;
+use serde_json::Value;
+
+const FIXTURE_TXN_VERSION: i64 = 1_000_000;
+
+// Aptos Labs parsers stamp rows with the processing time, anything this close to now is masked
+const NOW_TOLERANCE_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Deserialize)]
+struct EventFixture {
+    type_str: String,
+    data: Value,
+}
+
+fn parse_fixture(path: &Path) -> Vec<ContractEvent> {
+    let content = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+    let fixture: EventFixture = serde_json::from_str(&content)
+        .unwrap_or_else(|e| panic!("Failed to parse {}: {}", path.display(), e));
+
+    let contract_addr = standardize_address(fixture.type_str.split("::").next().unwrap());
+    let contract_addresses: AHashSet<String> = [contract_addr].into_iter().collect();
+    let event = EventPB {
+        type_str: fixture.type_str,
+        data: fixture.data.to_string(),
+        ..Default::default()
+    };
+    ContractEvent::from_events(&contract_addresses, &[event], FIXTURE_TXN_VERSION)
+}
+
+fn mask_processing_timestamps(value: &mut Value, now: i64) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                let is_recent = field
+                    .as_i64()
+                    .is_some_and(|ts| (ts - now).abs() <= NOW_TOLERANCE_SECS);
+                if key.contains("timestamp") && is_recent {
+                    *field = Value::String("<now>".to_string());
+                } else {
+                    mask_processing_timestamps(field, now);
+                }
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| mask_processing_timestamps(item, now)),
+        _ => {}
+    }
+}
+
+fn check_family(family: &str) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let fixture_dir = root.join("fixtures/events").join(family);
+    let golden_dir = root.join("golden/events").join(family);
+    let update = std::env::var("UPDATE_GOLDEN").is_ok();
+    let now = chrono::Utc::now().timestamp();
+
+    let mut fixtures: Vec<PathBuf> = std::fs::read_dir(&fixture_dir)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", fixture_dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    fixtures.sort();
+    assert!(
+        !fixtures.is_empty(),
+        "No fixtures in {}",
+        fixture_dir.display()
+    );
+
+    let mut mismatches = vec![];
+    for fixture in fixtures {
+        let mut actual = serde_json::to_value(parse_fixture(&fixture)).unwrap();
+        mask_processing_timestamps(&mut actual, now);
+        let golden = golden_dir.join(fixture.file_name().unwrap());
+
+        if update {
+            std::fs::create_dir_all(&golden_dir).unwrap();
+            let mut content = serde_json::to_string_pretty(&actual).unwrap();
+            content.push('\n');
+            std::fs::write(&golden, content).unwrap();
+            continue;
+        }
+
+        let expected: Value = match std::fs::read_to_string(&golden) {
+            Ok(content) => serde_json::from_str(&content)
+                .unwrap_or_else(|e| panic!("Failed to parse {}: {}", golden.display(), e)),
+            Err(_) => {
+                mismatches.push(format!(
+                    "{}: missing snapshot, run with UPDATE_GOLDEN=1 to create it",
+                    golden.display()
+                ));
+                continue;
+            }
+        };
+        if expected != actual {
+            mismatches.push(format!(
+                "{}:\nexpected: {}\nactual:   {}",
+                golden.display(),
+                serde_json::to_string_pretty(&expected).unwrap(),
+                serde_json::to_string_pretty(&actual).unwrap()
+            ));
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "Parser output differs from golden files (run with UPDATE_GOLDEN=1 if intended):\n{}",
+        mismatches.join("\n\n")
+    );
+}
+
+#[test]
+fn test_aptos_labs_events_match_golden_files() {
+    check_family("aptos_labs");
+}
+
+#[test]
+fn test_tradeport_v2_events_match_golden_files() {
+    check_family("tradeport_v2");
+}
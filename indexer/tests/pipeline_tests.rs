@@ -0,0 +1,558 @@
+//! End-to-end tests of the marketplace pipeline against a real Postgres, see `common` for how the
+//! database is provisioned. Fixtures are recorded transactions under `tests/fixtures/transactions`.
+
+mod common;
+
+use aptos_indexer_processor_sdk::aptos_protos::transaction::v1::Transaction;
+use common::{
+    batch, load_transactions, run_dry_run, run_pipeline, TestDatabase, TEST_TRACKER_NAME,
+    TRADEPORT_V2_MARKETPLACE_ADDR, WAPAL_MARKETPLACE_ADDR,
+};
+use diesel::{ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use indexer::{
+    db_models::{sweeps::Sweep, transactions::MarketplaceTransaction},
+    indexers::marketplace_indexer::{
+        reindex::load_archive_page, storers::sweep_storer::process_sweeps,
+    },
+    schema::{
+        activities, collection_bids, filled_collection_bids, nft_asks, nft_bids, processor_status,
+        raw_marketplace_events, sweeps, transactions,
+    },
+    utils::{
+        aptos_utils::{ActivityType, OrderStatus},
+        database_connection::get_db_connection,
+        database_utils::ArcDbPool,
+    },
+};
+
+const MARKETPLACE_FIXTURES: &str = "transactions/marketplace";
+const PRICE_UPDATE_FIXTURES: &str = "transactions/price_updates";
+const V1_TOKEN_ORDER_FIXTURES: &str = "transactions/v1_token_orders";
+const SWEEP_FIXTURES: &str = "transactions/sweeps";
+const COLLECTION_SWEEP_FIXTURES: &str = "transactions/collection_sweeps";
+
+const WAPAL_LISTING: &str = "0x4e5d6c7b8a9f0e1d2c3b4a5f6e7d8c9b0a1f2e3d4c5b6a7f8e9d0c1b2a3f4e5d";
+// Placed by a failed transaction, must never be indexed
+const FAILED_LISTING: &str = "0x9f8e7d6c5b4a39281706f5e4d3c2b1a09f8e7d6c5b4a39281706f5e4d3c2b1a0";
+const TRADEPORT_LISTING: &str =
+    "0x5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b";
+const TOKEN_OFFER: &str = "0x7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b";
+const COLLECTION_OFFER: &str = "0x2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c2d3e";
+// Placed again at another price in place of WAPAL_LISTING and TOKEN_OFFER
+const UPDATED_LISTING: &str = "0x3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f708192a";
+const UPDATED_TOKEN_OFFER: &str =
+    "0x6c7d8e9fa0b1c2d3e4f5061728394a5b6c7d8e9fa0b1c2d3e4f5061728394a5b";
+const COLLECTION: &str = "0x6f2e1d0c9b8a7f6e5d4c3b2a19f8e7d6c5b4a39281706f5e4d3c2b1a09f8e7d6";
+const BUYER: &str = "0x1b2c3d4e5f60718293a4b5c6d7e8f90112233445566778899aabbccddeeff0011";
+const V1_CREATOR: &str = "0x7d2c9e4f1a3b5c6d8e0f2a4b6c8d0e2f4a6b8c0d2e4f6a8b0c2d4e6f8a0b2c4d";
+
+const FIRST_VERSION: u64 = 100;
+const LAST_VERSION: i64 = 107;
+// Successful marketplace events across all fixture transactions
+const EXPECTED_EVENT_COUNT: i64 = 8;
+
+/// Split the fixture in the two batches it was recorded as.
+fn fixture_batches() -> Vec<Vec<Transaction>> {
+    let transactions = load_transactions(MARKETPLACE_FIXTURES);
+    let (first, second): (Vec<_>, Vec<_>) =
+        transactions.into_iter().partition(|txn| txn.version <= 102);
+    vec![first, second]
+}
+
+async fn ask(pool: &ArcDbPool, ask_obj_addr: &str) -> Option<(i32, i64, i64, String, String)> {
+    let mut conn = get_db_connection(pool).await.unwrap();
+    nft_asks::table
+        .filter(nft_asks::ask_obj_addr.eq(ask_obj_addr))
+        .select((
+            nft_asks::order_status,
+            nft_asks::order_placed_tx_version,
+            nft_asks::order_filled_tx_version,
+            nft_asks::buyer_addr,
+            nft_asks::marketplace_addr,
+        ))
+        .first(&mut conn)
+        .await
+        .ok()
+}
+
+async fn table_counts(pool: &ArcDbPool) -> (i64, i64, i64, i64, i64, i64) {
+    let mut conn = get_db_connection(pool).await.unwrap();
+    (
+        nft_asks::table.count().get_result(&mut conn).await.unwrap(),
+        nft_bids::table.count().get_result(&mut conn).await.unwrap(),
+        collection_bids::table
+            .count()
+            .get_result(&mut conn)
+            .await
+            .unwrap(),
+        filled_collection_bids::table
+            .count()
+            .get_result(&mut conn)
+            .await
+            .unwrap(),
+        activities::table
+            .count()
+            .get_result(&mut conn)
+            .await
+            .unwrap(),
+        raw_marketplace_events::table
+            .count()
+            .get_result(&mut conn)
+            .await
+            .unwrap(),
+    )
+}
+
+async fn last_success_version(pool: &ArcDbPool) -> i64 {
+    let mut conn = get_db_connection(pool).await.unwrap();
+    processor_status::table
+        .filter(processor_status::processor.eq(TEST_TRACKER_NAME))
+        .select(processor_status::last_success_version)
+        .first(&mut conn)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_pipeline_indexes_recorded_transactions() {
+    let db = TestDatabase::new().await;
+    let batches = fixture_batches().into_iter().map(batch).collect();
+    run_pipeline(&db, FIRST_VERSION, batches).await;
+
+    let (status, placed, filled, buyer, marketplace) = ask(&db.pool, WAPAL_LISTING).await.unwrap();
+    assert_eq!(status, OrderStatus::Filled as i32);
+    assert_eq!((placed, filled), (100, 103));
+    assert_eq!(buyer, BUYER);
+    assert_eq!(marketplace, WAPAL_MARKETPLACE_ADDR);
+
+    let (status, placed, filled, buyer, marketplace) =
+        ask(&db.pool, TRADEPORT_LISTING).await.unwrap();
+    assert_eq!(status, OrderStatus::Filled as i32);
+    assert_eq!((placed, filled), (106, 107));
+    assert_eq!(buyer, BUYER);
+    assert_eq!(marketplace, TRADEPORT_V2_MARKETPLACE_ADDR);
+
+    assert!(ask(&db.pool, FAILED_LISTING).await.is_none());
+
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    let (bid_status, bid_cancelled): (i32, i64) = nft_bids::table
+        .filter(nft_bids::bid_obj_addr.eq(TOKEN_OFFER))
+        .select((nft_bids::order_status, nft_bids::order_cancelled_tx_version))
+        .first(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(bid_status, OrderStatus::Cancelled as i32);
+    assert_eq!(bid_cancelled, 104);
+
+    let (total_nft_amount, bid_placed, bid_filled): (i64, i64, i64) = collection_bids::table
+        .filter(collection_bids::bid_obj_addr.eq(COLLECTION_OFFER))
+        .select((
+            collection_bids::total_nft_amount,
+            collection_bids::order_placed_tx_version,
+            collection_bids::latest_order_filled_tx_version,
+        ))
+        .first(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!((total_nft_amount, bid_placed, bid_filled), (2, 102, 105));
+
+    let activity_types: Vec<i32> = activities::table
+        .order((
+            activities::activity_tx_version,
+            activities::activity_event_idx,
+        ))
+        .select(activities::activity_type)
+        .load(&mut conn)
+        .await
+        .unwrap();
+    // ask placed, bid placed, collection bid placed, ask filled, bid cancelled,
+    // collection bid filled, ask placed, ask filled
+    assert_eq!(activity_types, vec![7, 4, 1, 8, 6, 2, 7, 8]);
+
+    let (_, _, _, filled_collection_bid_count, _, raw_event_count) = table_counts(&db.pool).await;
+    assert_eq!(filled_collection_bid_count, 1);
+    assert_eq!(raw_event_count, EXPECTED_EVENT_COUNT);
+
+    assert_eq!(last_success_version(&db.pool).await, LAST_VERSION);
+}
+
+#[tokio::test]
+async fn test_pipeline_merges_cancel_and_replace_into_price_update() {
+    let db = TestDatabase::new().await;
+    let transactions = load_transactions(PRICE_UPDATE_FIXTURES);
+    run_pipeline(&db, 200, vec![batch(transactions)]).await;
+
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    let activities: Vec<(i32, i64, Option<i64>)> = activities::table
+        .order(activities::activity_event_idx)
+        .select((
+            activities::activity_type,
+            activities::price,
+            activities::previous_price,
+        ))
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(
+        activities,
+        vec![
+            (
+                ActivityType::NFTAskPriceUpdated as i32,
+                140000000,
+                Some(150000000)
+            ),
+            (
+                ActivityType::NFTBidPriceUpdated as i32,
+                125000000,
+                Some(120000000)
+            ),
+        ]
+    );
+
+    // The orders themselves are still cancelled and placed
+    let (status, ..) = ask(&db.pool, WAPAL_LISTING).await.unwrap();
+    assert_eq!(status, OrderStatus::Cancelled as i32);
+    let (status, ..) = ask(&db.pool, UPDATED_LISTING).await.unwrap();
+    assert_eq!(status, OrderStatus::Open as i32);
+    let bid_statuses: Vec<(String, i32)> = nft_bids::table
+        .order(nft_bids::bid_obj_addr)
+        .select((nft_bids::bid_obj_addr, nft_bids::order_status))
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(
+        bid_statuses,
+        vec![
+            (UPDATED_TOKEN_OFFER.to_string(), OrderStatus::Open as i32),
+            (TOKEN_OFFER.to_string(), OrderStatus::Cancelled as i32),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_pipeline_only_merges_price_updates_of_the_same_v1_token() {
+    let db = TestDatabase::new().await;
+    let transactions = load_transactions(V1_TOKEN_ORDER_FIXTURES);
+    run_pipeline(&db, 201, vec![batch(transactions)]).await;
+
+    // Every v1 token has nft_id 0, #12 is cancelled and #13 listed instead, #14 is re-priced
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    let activities: Vec<(String, i32, i64, Option<i64>)> = activities::table
+        .order(activities::activity_event_idx)
+        .select((
+            activities::nft_name,
+            activities::activity_type,
+            activities::price,
+            activities::previous_price,
+        ))
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(
+        activities,
+        vec![
+            (
+                "Bruh Bear #12".to_string(),
+                ActivityType::NFTAskCancelled as i32,
+                30000000,
+                None
+            ),
+            (
+                "Bruh Bear #13".to_string(),
+                ActivityType::NFTAskPlaced as i32,
+                25000000,
+                None
+            ),
+            (
+                "Bruh Bear #14".to_string(),
+                ActivityType::NFTAskPriceUpdated as i32,
+                35000000,
+                Some(40000000)
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_archive_pages_keep_transactions_whole() {
+    let db = TestDatabase::new().await;
+    run_pipeline(
+        &db,
+        200,
+        vec![batch(load_transactions(PRICE_UPDATE_FIXTURES))],
+    )
+    .await;
+
+    // A page cut after the first cancel would replay it apart from its re-place
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    let page = load_archive_page(&mut conn, (200, -1), 200, 1)
+        .await
+        .unwrap();
+    let archived: i64 = raw_marketplace_events::table
+        .count()
+        .get_result(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(page.len() as i64, archived);
+    assert!(page.iter().all(|event| event.txn_version == 200));
+    assert!(
+        load_archive_page(&mut conn, (200, page.len() as i64 - 1), 200, 1)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_pipeline_groups_fills_of_a_buyer_into_sweeps() {
+    let db = TestDatabase::new().await;
+    let transactions = load_transactions(SWEEP_FIXTURES);
+    // Storing the transaction twice must not change the sweep
+    run_pipeline(&db, 300, vec![batch(transactions.clone())]).await;
+    run_pipeline(&db, 300, vec![batch(transactions)]).await;
+
+    // Two wapal fills of the collection by BUYER make a sweep, BUYER's tradeport fill has no
+    // collection and the last fill is by another buyer
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    let sweeps: Vec<Sweep> = sweeps::table
+        .select(Sweep::as_select())
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(sweeps.len(), 1);
+    let sweep = &sweeps[0];
+    assert_eq!((sweep.txn_version, sweep.event_idx), (300, 0));
+    assert_eq!(sweep.buyer_addr, BUYER);
+    assert_eq!(sweep.collection_addr, COLLECTION);
+    assert_eq!((sweep.nft_count, sweep.total_price), (2, 250000000));
+    assert_eq!(
+        sweep.marketplace_addrs,
+        vec![Some(WAPAL_MARKETPLACE_ADDR.to_string())]
+    );
+
+    let sweep_event_idxs: Vec<Option<i64>> = activities::table
+        .order(activities::activity_event_idx)
+        .select(activities::sweep_event_idx)
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(sweep_event_idxs, vec![Some(0), Some(0), None, None]);
+}
+
+#[tokio::test]
+async fn test_pipeline_groups_sweeps_by_collection_without_address() {
+    let db = TestDatabase::new().await;
+    run_pipeline(
+        &db,
+        310,
+        vec![batch(load_transactions(COLLECTION_SWEEP_FIXTURES))],
+    )
+    .await;
+
+    // BUYER fills two Tradeport v2 listings with no collection, then v1 NFTs of two collections
+    // of the same creator, only the two of the same collection make a sweep
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    let sweeps: Vec<Sweep> = sweeps::table
+        .select(Sweep::as_select())
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(sweeps.len(), 1);
+    let sweep = &sweeps[0];
+    assert_eq!((sweep.txn_version, sweep.event_idx), (310, 2));
+    assert_eq!(sweep.collection_addr, "");
+    assert_eq!(sweep.collection_creator_addr, V1_CREATOR);
+    assert_eq!(sweep.collection_name, "Bruh Bears");
+    assert_eq!((sweep.nft_count, sweep.total_price), (2, 45000000));
+
+    let sweep_event_idxs: Vec<Option<i64>> = activities::table
+        .order(activities::activity_event_idx)
+        .select(activities::sweep_event_idx)
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(sweep_event_idxs, vec![None, None, Some(2), None, Some(2)]);
+}
+
+#[tokio::test]
+async fn test_sweeps_of_a_transaction_are_regrouped_one_at_a_time() {
+    let db = TestDatabase::new().await;
+    run_pipeline(
+        &db,
+        310,
+        vec![batch(load_transactions(COLLECTION_SWEEP_FIXTURES))],
+    )
+    .await;
+
+    // Like processors of different marketplaces storing fills of the same transaction
+    let regroups = (0..8).map(|_| {
+        tokio::spawn(process_sweeps(
+            db.pool.clone(),
+            Default::default(),
+            vec![310],
+        ))
+    });
+    for regroup in futures_util::future::join_all(regroups).await {
+        regroup.unwrap().unwrap();
+    }
+
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    let sweeps: Vec<(i64, i64)> = sweeps::table
+        .select((sweeps::txn_version, sweeps::event_idx))
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(sweeps, vec![(310, 2)]);
+}
+
+#[tokio::test]
+async fn test_pipeline_records_transactions_of_activities() {
+    let db = TestDatabase::new().await;
+    run_pipeline(&db, 300, vec![batch(load_transactions(SWEEP_FIXTURES))]).await;
+
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    let txns: Vec<MarketplaceTransaction> = transactions::table
+        .select(MarketplaceTransaction::as_select())
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(txns.len(), 1);
+    let txn = &txns[0];
+    assert_eq!(txn.txn_version, 300);
+    assert_eq!(
+        txn.txn_hash,
+        "0x303132333435363738393a3b3c3d3e3f404142434445464748494a4b4c4d4e4f"
+    );
+    assert_eq!(txn.sender, BUYER);
+    assert_eq!(
+        txn.entry_function_id,
+        Some(format!("{}::marketplace::buy_many", WAPAL_MARKETPLACE_ADDR))
+    );
+    assert_eq!(
+        (txn.gas_used, txn.gas_unit_price, txn.gas_fee),
+        (1520, 100, 152000)
+    );
+}
+
+#[tokio::test]
+async fn test_pipeline_handles_out_of_order_batches() {
+    let db = TestDatabase::new().await;
+    let batches = fixture_batches().into_iter().rev().map(batch).collect();
+    run_pipeline(&db, FIRST_VERSION, batches).await;
+
+    // Each lifecycle stage keeps the latest version it saw regardless of arrival order. The
+    // status is whatever the last written stage set, reindex recomputes it if that matters.
+    let (_, placed, filled, buyer, _) = ask(&db.pool, WAPAL_LISTING).await.unwrap();
+    assert_eq!((placed, filled), (100, 103));
+    assert_eq!(buyer, BUYER);
+
+    let (_, _, _, filled_collection_bid_count, activity_count, _) = table_counts(&db.pool).await;
+    assert_eq!(filled_collection_bid_count, 1);
+    assert_eq!(activity_count, EXPECTED_EVENT_COUNT);
+
+    // The tracker only advances once the gap left by the first batch is filled
+    assert_eq!(last_success_version(&db.pool).await, LAST_VERSION);
+}
+
+#[tokio::test]
+async fn test_pipeline_replay_is_idempotent() {
+    let db = TestDatabase::new().await;
+    run_pipeline(
+        &db,
+        FIRST_VERSION,
+        fixture_batches().into_iter().map(batch).collect(),
+    )
+    .await;
+    let counts_after_first_run = table_counts(&db.pool).await;
+    let ask_after_first_run = ask(&db.pool, WAPAL_LISTING).await;
+
+    run_pipeline(
+        &db,
+        FIRST_VERSION,
+        fixture_batches().into_iter().map(batch).collect(),
+    )
+    .await;
+
+    assert_eq!(table_counts(&db.pool).await, counts_after_first_run);
+    assert_eq!(ask(&db.pool, WAPAL_LISTING).await, ask_after_first_run);
+    assert_eq!(last_success_version(&db.pool).await, LAST_VERSION);
+}
+
+#[tokio::test]
+async fn test_dry_run_reports_changes_without_writing() {
+    let db = TestDatabase::new().await;
+    let mut batches = fixture_batches();
+    let second_batch = batches.pop().unwrap();
+    run_pipeline(&db, FIRST_VERSION, batches.into_iter().map(batch).collect()).await;
+    let counts_before = table_counts(&db.pool).await;
+    let ask_before = ask(&db.pool, WAPAL_LISTING).await;
+
+    let report = run_dry_run(&db, vec![batch(second_batch)]).await;
+
+    // Nothing was written and the tracker didn't move
+    assert_eq!(table_counts(&db.pool).await, counts_before);
+    assert_eq!(ask(&db.pool, WAPAL_LISTING).await, ask_before);
+    assert_eq!(last_success_version(&db.pool).await, 102);
+
+    let asks = report
+        .tables
+        .iter()
+        .find(|table| table.table == "nft_asks")
+        .unwrap();
+    assert_eq!(asks.new_rows.len(), 1);
+    assert_eq!(asks.new_rows[0]["ask_obj_addr"], TRADEPORT_LISTING);
+    assert_eq!(asks.changed_rows.len(), 1);
+    assert_eq!(asks.changed_rows[0].key["ask_obj_addr"], WAPAL_LISTING);
+    let status_change = asks.changed_rows[0]
+        .columns
+        .iter()
+        .find(|change| change.column == "order_status")
+        .unwrap();
+    assert_eq!(
+        (status_change.current.clone(), status_change.dry_run.clone()),
+        (
+            (OrderStatus::Open as i32).into(),
+            (OrderStatus::Filled as i32).into()
+        )
+    );
+    let transitions = asks
+        .status_transitions
+        .iter()
+        .map(|t| (t.from.as_deref(), t.to.as_str(), t.count))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        transitions,
+        vec![(None, "filled", 1), (Some("open"), "filled", 1)]
+    );
+
+    let activities = report
+        .tables
+        .iter()
+        .find(|table| table.table == "activities")
+        .unwrap();
+    assert_eq!(activities.new_rows.len(), 5);
+    assert!(activities.changed_rows.is_empty());
+}
+
+#[tokio::test]
+async fn test_dry_run_of_indexed_range_reports_no_changes() {
+    let db = TestDatabase::new().await;
+    run_pipeline(
+        &db,
+        FIRST_VERSION,
+        fixture_batches().into_iter().map(batch).collect(),
+    )
+    .await;
+
+    let report = run_dry_run(&db, fixture_batches().into_iter().map(batch).collect()).await;
+
+    for table in report.tables {
+        assert!(table.new_rows.is_empty(), "{} has new rows", table.table);
+        assert!(
+            table.changed_rows.is_empty(),
+            "{} has changed rows",
+            table.table
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! Tests of processors of different networks sharing a database through their own schema, see
+//! `common` for how the database is provisioned.
+
+mod common;
+
+use common::{batch, load_transactions, marketplace_config, run_pipeline, TestDatabase};
+use diesel::QueryDsl;
+use diesel_async::RunQueryDsl;
+use indexer::{
+    config::validation::validate_database,
+    db_models::ledger_info::LedgerInfo,
+    schema::{ledger_infos, nft_asks, processor_status},
+    utils::{database_connection::get_db_connection, database_utils::ArcDbPool},
+};
+
+async fn count_rows(pool: &ArcDbPool) -> (i64, i64) {
+    let mut conn = get_db_connection(pool).await.unwrap();
+    let processors = processor_status::table
+        .count()
+        .get_result(&mut conn)
+        .await
+        .unwrap();
+    let asks = nft_asks::table.count().get_result(&mut conn).await.unwrap();
+    (processors, asks)
+}
+
+#[tokio::test]
+async fn test_networks_in_separate_schemas_share_a_database() {
+    let db = TestDatabase::new().await;
+    let mut config = marketplace_config(&db);
+    config.db_config.schema = Some("testnet".to_string());
+    let testnet_db = db.with_db_config(&config.db_config).await;
+
+    let transactions = load_transactions("transactions/marketplace");
+    run_pipeline(&testnet_db, 100, vec![batch(transactions)]).await;
+
+    let (processors, asks) = count_rows(&testnet_db.pool).await;
+    assert_eq!(processors, 1);
+    assert!(asks > 0);
+    assert_eq!(count_rows(&db.pool).await, (0, 0));
+
+    // Each schema has its own chain id
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    diesel::insert_into(ledger_infos::table)
+        .values(LedgerInfo { chain_id: 2 })
+        .execute(&mut conn)
+        .await
+        .unwrap();
+    let mut testnet_conn = get_db_connection(&testnet_db.pool).await.unwrap();
+    assert!(LedgerInfo::get(&mut testnet_conn).await.unwrap().is_none());
+    assert_eq!(validate_database(&config).await, Vec::<String>::new());
+
+    config.db_config.schema = Some("devnet".to_string());
+    let problems = validate_database(&config).await;
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+    assert!(problems[0].contains("Schema devnet doesn't exist"));
+
+    config.db_config.schema = Some("test-net".to_string());
+    assert!(config.db_config.connection_string().is_err());
+}
@@ -0,0 +1,34 @@
+//! Shutdown is requested process wide, so these tests live in their own test binary instead of
+//! next to the pipeline tests.
+
+use aptos_indexer_processor_sdk::traits::PollableAsyncStep;
+use indexer::{
+    config::indexer_processor_config::FileSourceConfig,
+    utils::{
+        shutdown::request_shutdown,
+        transaction_source::{FileTransactionSource, TransactionSourceStep},
+    },
+};
+use std::path::Path;
+
+#[tokio::test]
+async fn test_transaction_source_stops_after_shutdown() {
+    let config = FileSourceConfig {
+        directory: Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/transactions/marketplace"),
+        batch_size: 2,
+    };
+    let mut source =
+        TransactionSourceStep::File(FileTransactionSource::new(&config, 100, None).unwrap());
+
+    assert!(source.should_continue_polling().await);
+    let batches = source.poll().await.unwrap().unwrap();
+    assert_eq!(batches[0].metadata.start_version, 100);
+    assert_eq!(batches[0].metadata.end_version, 101);
+
+    request_shutdown();
+
+    // The remaining transactions are left for the next run
+    assert!(!source.should_continue_polling().await);
+    assert!(source.poll().await.unwrap().is_none());
+}
@@ -0,0 +1,126 @@
+//! Tests of tracking the contracts of the tracked_contracts table against a real Postgres, see
+//! `common` for how the database is provisioned.
+
+mod common;
+
+use aptos_indexer_processor_sdk::traits::Processable;
+use common::{
+    batch, load_transactions, TestDatabase, TRADEPORT_V2_MARKETPLACE_ADDR, WAPAL_MARKETPLACE_ADDR,
+};
+use indexer::{
+    config::indexer_processor_config::CustomConfig,
+    db_models::{
+        tracked_contract_activations::TrackedContractActivation, tracked_contracts::TrackedContract,
+    },
+    indexers::marketplace_indexer::extractor::{Extractor, TransactionContextData},
+    utils::{
+        database_connection::get_db_connection,
+        tracked_contracts::{TrackedContractKind, TrackedContracts},
+        transaction_filter::{TransactionFilter, TransactionFilterStep},
+    },
+};
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+async fn extract(extractor: &mut Extractor) -> TransactionContextData {
+    let transactions = load_transactions("transactions/marketplace");
+    extractor
+        .process(batch(transactions))
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+}
+
+async fn track(db: &TestDatabase, parser_family: Option<&str>, start_version: i64) {
+    TrackedContract {
+        contract_address: TRADEPORT_V2_MARKETPLACE_ADDR.to_string(),
+        kind: "marketplace".to_string(),
+        parser_family: parser_family.map(str::to_string),
+        start_version,
+    }
+    .upsert(&mut get_db_connection(&db.pool).await.unwrap())
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_tracked_contracts_are_picked_up_while_running() {
+    let db = TestDatabase::new().await;
+    let tracked_contracts = TrackedContracts::watch(db.pool.clone(), POLL_INTERVAL)
+        .await
+        .unwrap();
+    let mut extractor = Extractor::new(vec![WAPAL_MARKETPLACE_ADDR.to_string()], true)
+        .with_tracked_contracts(Some(tracked_contracts));
+
+    let wapal_only = extract(&mut extractor).await;
+    assert!(wapal_only
+        .raw_events
+        .iter()
+        .all(|event| !event.type_str.starts_with(TRADEPORT_V2_MARKETPLACE_ADDR)));
+
+    // Tradeport has events at 106 and 107, it's only tracked from 107 on
+    track(&db, None, 107).await;
+    tokio::time::sleep(POLL_INTERVAL * 3).await;
+    let with_tradeport = extract(&mut extractor).await;
+    let tradeport_versions = with_tradeport
+        .raw_events
+        .iter()
+        .filter(|event| event.type_str.starts_with(TRADEPORT_V2_MARKETPLACE_ADDR))
+        .map(|event| event.txn_version)
+        .collect::<Vec<_>>();
+    assert_eq!(tradeport_versions, vec![107]);
+    assert_eq!(with_tradeport.events.len(), wapal_only.events.len() + 1);
+
+    // Parsed with the wrong parser nothing comes out of its events
+    track(&db, Some("aptos_labs"), 107).await;
+    tokio::time::sleep(POLL_INTERVAL * 3).await;
+    let wrong_parser = extract(&mut extractor).await;
+    assert_eq!(wrong_parser.events.len(), wapal_only.events.len());
+}
+
+#[tokio::test]
+async fn test_filter_records_the_version_it_picks_up_tracked_contracts_at() {
+    let db = TestDatabase::new().await;
+    let tracked_contracts = TrackedContracts::watch(db.pool.clone(), POLL_INTERVAL)
+        .await
+        .unwrap();
+    let mut step = TransactionFilterStep::new(TransactionFilter::new(
+        &CustomConfig::MarketplaceIndexer(vec![WAPAL_MARKETPLACE_ADDR.to_string()]),
+    ))
+    .with_tracked_contracts(Some(tracked_contracts))
+    .with_activations(
+        db.pool.clone(),
+        vec![("processor".to_string(), TrackedContractKind::Marketplace)],
+    );
+    let mut transactions = load_transactions("transactions/marketplace");
+    let later_transactions = transactions.split_off(4);
+    let later_version = later_transactions.first().unwrap().version as i64;
+    let activated_version = || async {
+        TrackedContractActivation::get(
+            "processor",
+            TRADEPORT_V2_MARKETPLACE_ADDR,
+            "marketplace",
+            &mut get_db_connection(&db.pool).await.unwrap(),
+        )
+        .await
+        .unwrap()
+    };
+
+    step.process(batch(transactions.clone())).await.unwrap();
+    assert_eq!(activated_version().await, None);
+
+    // Tracked from 0 on, the processor only picks it up with the batch after the reload
+    track(&db, None, 0).await;
+    tokio::time::sleep(POLL_INTERVAL * 3).await;
+    step.process(batch(later_transactions.clone()))
+        .await
+        .unwrap();
+    assert_eq!(activated_version().await, Some(later_version));
+
+    // Batches after it don't move it
+    step.process(batch(later_transactions)).await.unwrap();
+    step.process(batch(transactions)).await.unwrap();
+    assert_eq!(activated_version().await, Some(later_version));
+}
@@ -0,0 +1,58 @@
+//! Tests of the pre-filter that drops transactions before the extractor.
+
+mod common;
+
+use aptos_indexer_processor_sdk::traits::Processable;
+use common::{batch, load_transactions, TRADEPORT_V2_MARKETPLACE_ADDR, WAPAL_MARKETPLACE_ADDR};
+use indexer::{
+    config::indexer_processor_config::CustomConfig,
+    indexers::marketplace_indexer::extractor::Extractor,
+    utils::transaction_filter::{TransactionFilter, TransactionFilterStep},
+};
+
+fn marketplace_addresses() -> Vec<String> {
+    vec![
+        WAPAL_MARKETPLACE_ADDR.to_string(),
+        TRADEPORT_V2_MARKETPLACE_ADDR.to_string(),
+    ]
+}
+
+#[tokio::test]
+async fn test_filter_keeps_the_transactions_the_extractor_uses() {
+    let transactions = load_transactions("transactions/marketplace");
+    let filter = TransactionFilter::new(&CustomConfig::MarketplaceIndexer(marketplace_addresses()));
+
+    let mut extractor = Extractor::new(marketplace_addresses(), false);
+    for txn in &transactions {
+        let output = extractor
+            .process(batch(vec![txn.clone()]))
+            .await
+            .unwrap()
+            .unwrap();
+        let has_output = !output.data.events.is_empty() || !output.data.changes.is_empty();
+        assert_eq!(filter.matches(txn), has_output, "version {}", txn.version);
+    }
+
+    // Nothing matches addresses that aren't on chain
+    let other =
+        TransactionFilter::new(&CustomConfig::MarketplaceIndexer(
+            vec!["0x1234".to_string()],
+        ));
+    assert!(!transactions.iter().any(|txn| other.matches(txn)));
+}
+
+#[tokio::test]
+async fn test_filter_step_keeps_batch_versions() {
+    let transactions = load_transactions("transactions/marketplace");
+    let first_version = transactions.first().unwrap().version;
+    let last_version = transactions.last().unwrap().version;
+    let mut step = TransactionFilterStep::new(TransactionFilter::new(
+        &CustomConfig::MarketplaceIndexer(vec!["0x1234".to_string()]),
+    ));
+
+    let output = step.process(batch(transactions)).await.unwrap().unwrap();
+    assert!(output.data.is_empty());
+    // The version tracker still moves past the dropped transactions
+    assert_eq!(output.metadata.start_version, first_version);
+    assert_eq!(output.metadata.end_version, last_version);
+}
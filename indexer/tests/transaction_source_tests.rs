@@ -0,0 +1,33 @@
+//! Tests of reading recorded transaction files, see `tests/fixtures/transactions`.
+
+mod common;
+
+use aptos_indexer_processor_sdk::traits::PollableAsyncStep;
+use common::fixture_path;
+use indexer::{
+    config::indexer_processor_config::FileSourceConfig,
+    utils::transaction_source::FileTransactionSource,
+};
+
+#[tokio::test]
+async fn test_sparse_recording_is_read_up_to_ending_version() {
+    // The recording ends at 107, like `record --only-tracked` output it has no transactions
+    // for the versions after it
+    let config = FileSourceConfig {
+        directory: fixture_path("transactions/marketplace"),
+        batch_size: 4,
+    };
+    let mut source = FileTransactionSource::new(&config, 100, Some(120)).unwrap();
+
+    let mut ranges = vec![];
+    while source.should_continue_polling().await {
+        for batch in source.poll().await.unwrap().unwrap_or_default() {
+            ranges.push((
+                batch.metadata.start_version,
+                batch.metadata.end_version,
+                batch.data.len(),
+            ));
+        }
+    }
+    assert_eq!(ranges, vec![(100, 103, 4), (104, 107, 4), (108, 120, 0)]);
+}
@@ -0,0 +1,85 @@
+//! Tests of the validate-config checks against a real Postgres, see `common` for how the
+//! database is provisioned.
+
+mod common;
+
+use common::{marketplace_config, TestDatabase, WAPAL_MARKETPLACE_ADDR};
+use diesel_async::RunQueryDsl;
+use indexer::{
+    config::{
+        indexer_processor_config::{CustomConfig, IndexerProcessorConfig},
+        validation::{validate_config, validate_database},
+    },
+    utils::database_connection::get_db_connection,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_valid_config_has_no_problems() {
+    let db = TestDatabase::new().await;
+    let config = marketplace_config(&db);
+
+    assert_eq!(validate_config(&config), Vec::<String>::new());
+    assert_eq!(validate_database(&config).await, Vec::<String>::new());
+}
+
+#[tokio::test]
+async fn test_every_problem_is_reported() {
+    let db = TestDatabase::new().await;
+    let mut config = marketplace_config(&db);
+    config.custom_config = CustomConfig::ContractUpgradeIndexer(vec![
+        WAPAL_MARKETPLACE_ADDR.to_string(),
+        WAPAL_MARKETPLACE_ADDR.to_uppercase().replace("0X", "0x"),
+        "0xnot-an-address".to_string(),
+    ]);
+    let problems = validate_config(&config);
+    assert_eq!(problems.len(), 3, "{:?}", problems);
+    assert!(problems[0].contains("needs a custom_config of marketplace_indexer"));
+    assert!(problems[1].contains("listed more than once"));
+    assert!(problems[2].contains("0xnot-an-address"));
+
+    let mut conn = get_db_connection(&db.pool).await.unwrap();
+    diesel::sql_query(
+        "DELETE FROM __diesel_schema_migrations \
+         WHERE version = (SELECT max(version) FROM __diesel_schema_migrations)",
+    )
+    .execute(&mut conn)
+    .await
+    .unwrap();
+    let problems = validate_database(&config).await;
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+    assert!(problems[0].contains("missing migrations"));
+}
+
+#[tokio::test]
+async fn test_composite_sub_processors_are_validated() {
+    let db = TestDatabase::new().await;
+    let mut config = serde_json::to_value(marketplace_config(&db)).unwrap();
+    config["processor_config"] = json!({ "type": "composite_indexer" });
+    config["custom_config"] = json!({
+        "composite_indexer": [
+            {
+                "processor_config": { "type": "wapal_marketplace_indexer" },
+                "custom_config": { "marketplace_indexer": [WAPAL_MARKETPLACE_ADDR] },
+            },
+            {
+                "processor_config": { "type": "contract_upgrade_indexer" },
+                "custom_config": { "contract_upgrade_indexer": [WAPAL_MARKETPLACE_ADDR] },
+            },
+        ]
+    });
+    let mut config: IndexerProcessorConfig = serde_json::from_value(config).unwrap();
+    assert_eq!(validate_config(&config), Vec::<String>::new());
+    assert_eq!(validate_database(&config).await, Vec::<String>::new());
+
+    let CustomConfig::CompositeIndexer(sub_processors) = &mut config.custom_config else {
+        unreachable!()
+    };
+    sub_processors.push(sub_processors[0].clone());
+    sub_processors[1].custom_config =
+        CustomConfig::MarketplaceIndexer(vec![WAPAL_MARKETPLACE_ADDR.to_string()]);
+    let problems = validate_config(&config);
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+    assert!(problems[0].contains("needs a custom_config of contract_upgrade_indexer"));
+    assert!(problems[1].contains("runs wapal_marketplace_indexer more than once"));
+}